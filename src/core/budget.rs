@@ -0,0 +1,223 @@
+//! Token budgets for tool responses
+//!
+//! Responses are sized with a cheap estimate (serialized bytes /
+//! [`BYTES_PER_TOKEN`]) and, when over budget, reduced in stages that lose
+//! the least information first:
+//! 1. `description` annotations are dropped
+//! 2. Long `enum`s keep their first [`ENUM_KEEP`] values and record the full
+//!    count in `x-enum-total`
+//! 3. Nested schemas are collapsed behind `$ref` handles, deepest first; each
+//!    handle is a JSON pointer into the callable's input schema that the
+//!    `schema` tool's `json_pointer` fetches in full
+//!
+//! Callers stop as soon as the response fits and report what was elided.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Average serialized bytes per token used for estimates
+pub const BYTES_PER_TOKEN: usize = 4;
+
+/// Enum values kept when enums are truncated
+pub const ENUM_KEEP: usize = 5;
+
+/// Estimated token count of a value's JSON serialization
+pub fn estimate_tokens<T: Serialize + ?Sized>(value: &T) -> usize {
+    serde_json::to_string(value)
+        .map(|json| json.len().div_ceil(BYTES_PER_TOKEN))
+        .unwrap_or(0)
+}
+
+/// One lossy reduction of a JSON Schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaReduction {
+    /// Remove `description` annotations
+    Descriptions,
+    /// Truncate enums longer than [`ENUM_KEEP`]
+    Enums,
+    /// Replace nested schemas at this depth with `$ref` handles
+    Collapse(usize),
+}
+
+impl SchemaReduction {
+    /// Reductions applicable to a schema, least lossy first
+    pub fn plan(schema: &Value) -> Vec<SchemaReduction> {
+        let mut depth = 0;
+        visit(
+            &mut schema.clone(),
+            "",
+            0,
+            false,
+            &mut |node, _, d, in_defs| {
+                if !in_defs && d > 0 && is_nested(node) {
+                    depth = depth.max(d);
+                }
+                true
+            },
+        );
+
+        let mut plan = vec![SchemaReduction::Descriptions, SchemaReduction::Enums];
+        plan.extend((1..=depth).rev().map(SchemaReduction::Collapse));
+        plan
+    }
+
+    /// Apply the reduction, returning a note of what was elided
+    ///
+    /// `base` is the JSON pointer of `schema` within the callable's input
+    /// schema (empty for the whole schema), so `$ref` handles stay fetchable.
+    pub fn apply(&self, schema: &mut Value, base: &str) -> Option<String> {
+        let mut count = 0;
+        match self {
+            SchemaReduction::Descriptions => {
+                visit(schema, "", 0, false, &mut |node, _, _, _| {
+                    if let Some(obj) = node.as_object_mut() {
+                        count += usize::from(obj.remove("description").is_some());
+                    }
+                    true
+                });
+                (count > 0).then(|| format!("{} descriptions", count))
+            }
+            SchemaReduction::Enums => {
+                visit(schema, "", 0, false, &mut |node, _, _, _| {
+                    let Some(obj) = node.as_object_mut() else {
+                        return true;
+                    };
+                    if let Some(Value::Array(values)) = obj.get_mut("enum") {
+                        let total = values.len();
+                        if total > ENUM_KEEP {
+                            values.truncate(ENUM_KEEP);
+                            obj.insert("x-enum-total".to_string(), Value::from(total));
+                            count += 1;
+                        }
+                    }
+                    true
+                });
+                (count > 0).then(|| {
+                    format!(
+                        "{} enums truncated to {} values (full count in x-enum-total)",
+                        count, ENUM_KEEP
+                    )
+                })
+            }
+            SchemaReduction::Collapse(depth) => {
+                visit(schema, "", 0, false, &mut |node, pointer, d, in_defs| {
+                    if in_defs || d < *depth {
+                        return true;
+                    }
+                    if is_nested(node) {
+                        let mut handle = serde_json::Map::new();
+                        handle.insert(
+                            "$ref".to_string(),
+                            Value::String(format!("#{}{}", base, pointer)),
+                        );
+                        if let Some(kind) = node.get("type") {
+                            handle.insert("type".to_string(), kind.clone());
+                        }
+                        // Long pointers to small schemas would not save anything
+                        let handle = Value::Object(handle);
+                        if estimate_tokens(&handle) < estimate_tokens(node) {
+                            *node = handle;
+                            count += 1;
+                        }
+                    }
+                    false
+                });
+                (count > 0).then(|| {
+                    format!(
+                        "{} nested schemas at depth {} collapsed to $ref (fetch with json_pointer)",
+                        count, depth
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Keywords holding a map of subschemas
+pub(crate) const SCHEMA_MAPS: &[&str] = &[
+    "properties",
+    "patternProperties",
+    "dependentSchemas",
+    "$defs",
+    "definitions",
+];
+
+/// Keywords holding a subschema or an array of subschemas
+pub(crate) const SCHEMA_VALUES: &[&str] = &[
+    "items",
+    "prefixItems",
+    "additionalItems",
+    "additionalProperties",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+    "propertyNames",
+    "contains",
+    "allOf",
+    "anyOf",
+    "oneOf",
+    "not",
+    "if",
+    "then",
+    "else",
+];
+
+/// Whether a schema has subschemas worth collapsing
+fn is_nested(node: &Value) -> bool {
+    node.as_object().is_some_and(|obj| {
+        obj.iter().any(|(key, value)| {
+            (SCHEMA_MAPS.contains(&key.as_str())
+                && value.as_object().is_some_and(|m| !m.is_empty()))
+                || (SCHEMA_VALUES.contains(&key.as_str())
+                    && (value.is_object() || value.is_array()))
+        })
+    })
+}
+
+/// Visit a schema and its subschemas with their JSON pointer and nesting depth
+///
+/// `f` returns whether to descend into the node's subschemas.
+fn visit(
+    node: &mut Value,
+    pointer: &str,
+    depth: usize,
+    in_defs: bool,
+    f: &mut dyn FnMut(&mut Value, &str, usize, bool) -> bool,
+) {
+    if !f(node, pointer, depth, in_defs) {
+        return;
+    }
+    let Some(obj) = node.as_object_mut() else {
+        return;
+    };
+
+    for (key, child) in obj.iter_mut() {
+        let defs = in_defs || key == "$defs" || key == "definitions";
+        if SCHEMA_MAPS.contains(&key.as_str()) {
+            if let Some(map) = child.as_object_mut() {
+                for (name, sub) in map.iter_mut() {
+                    let path = format!("{}/{}/{}", pointer, key, escape(name));
+                    visit(sub, &path, depth + 1, defs, f);
+                }
+            }
+        } else if SCHEMA_VALUES.contains(&key.as_str()) {
+            match child {
+                Value::Array(items) => {
+                    for (i, sub) in items.iter_mut().enumerate() {
+                        let path = format!("{}/{}/{}", pointer, key, i);
+                        visit(sub, &path, depth + 1, in_defs, f);
+                    }
+                }
+                Value::Object(_) => {
+                    let path = format!("{}/{}", pointer, key);
+                    visit(child, &path, depth + 1, in_defs, f);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Escape a JSON pointer reference token
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
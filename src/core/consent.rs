@@ -0,0 +1,430 @@
+//! Consent grants
+//!
+//! Elevated consent levels are never taken on an agent's word. When `exec`
+//! hits a risk tier that needs consent, the server records a pending
+//! [`ConsentRequest`] bound to the callable and a digest of its arguments.
+//! A human approves it out of band (`skills approve <id>` or the HTTP
+//! endpoint), which issues a signed, expiring token. `exec` verifies the
+//! token against the same callable and arguments and consumes it, so each
+//! approval authorizes exactly one execution.
+//!
+//! Token format: `sct1.<request-id>.<expires-unix>.<signature>`, where the
+//! signature is a keyed BLAKE3 hash over the request id, callable id,
+//! argument digest, granted level, and expiry. The signing key is generated
+//! once and kept in the `server_state` table.
+
+use crate::core::persistence::{PersistenceError, PersistenceLayer};
+use crate::core::policy::ConsentLevel;
+use crate::core::{canonicalize_json, CallableId, CallableRecord, CoreError};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{debug, info};
+
+/// `server_state` key holding the token signing key
+const SIGNING_KEY_STATE: &str = "consent_signing_key";
+
+/// Version prefix for issued tokens
+const TOKEN_PREFIX: &str = "sct1";
+
+/// Default lifetime of pending requests and issued tokens
+pub const DEFAULT_CONSENT_TTL_SECS: u64 = 600;
+
+#[derive(Error, Debug)]
+pub enum ConsentError {
+    #[error("Consent request not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid consent token: {0}")]
+    InvalidToken(String),
+
+    #[error("Consent expired: {0}")]
+    Expired(String),
+
+    #[error("Consent token does not match this call: {0}")]
+    Mismatch(String),
+
+    #[error("Consent request {id} is {status}")]
+    InvalidState { id: String, status: ConsentStatus },
+
+    #[error("Persistence error: {0}")]
+    Persistence(#[from] PersistenceError),
+
+    #[error("Argument digest failed: {0}")]
+    Digest(#[from] CoreError),
+}
+
+pub type Result<T> = std::result::Result<T, ConsentError>;
+
+/// Lifecycle of a consent request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentStatus {
+    Pending,
+    Approved,
+    Denied,
+    Consumed,
+}
+
+impl ConsentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentStatus::Pending => "pending",
+            ConsentStatus::Approved => "approved",
+            ConsentStatus::Denied => "denied",
+            ConsentStatus::Consumed => "consumed",
+        }
+    }
+}
+
+impl fmt::Display for ConsentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ConsentStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(ConsentStatus::Pending),
+            "approved" => Ok(ConsentStatus::Approved),
+            "denied" => Ok(ConsentStatus::Denied),
+            "consumed" => Ok(ConsentStatus::Consumed),
+            other => Err(format!("Unknown consent status: {}", other)),
+        }
+    }
+}
+
+/// A request for consent to run one callable with one set of arguments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentRequest {
+    pub id: String,
+    pub callable_id: CallableId,
+    pub fq_name: String,
+    /// BLAKE3 digest of the canonicalized arguments
+    pub args_digest: String,
+    /// Consent level the approval grants
+    pub level: ConsentLevel,
+    /// Why consent is required (from the policy engine)
+    pub reason: String,
+    pub status: ConsentStatus,
+    pub created_at: DateTime<Utc>,
+    /// Approval deadline while pending; token expiry once approved
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ConsentRequest {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// Token issued when a request is approved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentGrant {
+    pub request_id: String,
+    pub token: String,
+    pub level: ConsentLevel,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Issues, approves, and verifies consent grants
+pub struct ConsentManager {
+    persistence: Arc<PersistenceLayer>,
+    key: [u8; 32],
+    ttl: Duration,
+}
+
+impl ConsentManager {
+    /// Create a manager, generating the signing key on first use
+    pub async fn new(persistence: Arc<PersistenceLayer>) -> Result<Self> {
+        let stored = persistence
+            .load_state(SIGNING_KEY_STATE)
+            .await
+            .ok()
+            .and_then(|v| v.as_str().and_then(|s| blake3::Hash::from_hex(s).ok()));
+
+        let key = match stored {
+            Some(hash) => *hash.as_bytes(),
+            None => {
+                let mut seed = Vec::with_capacity(32);
+                seed.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+                seed.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+                let hash = blake3::hash(&seed);
+                persistence
+                    .save_state(
+                        SIGNING_KEY_STATE,
+                        &serde_json::Value::String(hash.to_hex().to_string()),
+                    )
+                    .await?;
+                info!("Generated consent signing key");
+                *hash.as_bytes()
+            }
+        };
+
+        Ok(ConsentManager {
+            persistence,
+            key,
+            ttl: Duration::seconds(DEFAULT_CONSENT_TTL_SECS as i64),
+        })
+    }
+
+    /// Set how long pending requests and issued tokens stay valid
+    pub fn with_ttl_secs(mut self, secs: u64) -> Self {
+        self.ttl = Duration::seconds(secs.min(i64::MAX as u64) as i64);
+        self
+    }
+
+    /// Digest of a set of arguments, independent of key order
+    pub fn args_digest(arguments: &serde_json::Value) -> Result<String> {
+        let canonical = canonicalize_json(arguments)?;
+        Ok(blake3::hash(canonical.as_bytes()).to_hex().to_string())
+    }
+
+    /// Record a pending request, reusing an identical one that is still open
+    pub async fn request(
+        &self,
+        record: &CallableRecord,
+        arguments: &serde_json::Value,
+        level: ConsentLevel,
+        reason: &str,
+    ) -> Result<ConsentRequest> {
+        let args_digest = Self::args_digest(arguments)?;
+        if let Some(existing) = self
+            .persistence
+            .find_pending_consent_request(&record.id, &args_digest)
+            .await?
+        {
+            if existing.level >= level {
+                return Ok(existing);
+            }
+        }
+
+        let now = Utc::now();
+        let request = ConsentRequest {
+            id: uuid::Uuid::new_v4().simple().to_string()[..12].to_string(),
+            callable_id: record.id.clone(),
+            fq_name: record.fq_name.clone(),
+            args_digest,
+            level,
+            reason: reason.to_string(),
+            status: ConsentStatus::Pending,
+            created_at: now,
+            expires_at: now + self.ttl,
+        };
+        self.persistence.save_consent_request(&request).await?;
+
+        info!(
+            "Consent request {} created for {} ({})",
+            request.id, request.fq_name, request.level
+        );
+        Ok(request)
+    }
+
+    /// Look up a request by ID
+    pub async fn get(&self, id: &str) -> Result<ConsentRequest> {
+        self.persistence
+            .load_consent_request(id)
+            .await
+            .map_err(|e| match e {
+                PersistenceError::NotFound(_) => ConsentError::NotFound(id.to_string()),
+                other => other.into(),
+            })
+    }
+
+    /// List requests still awaiting a decision
+    pub async fn list_pending(&self) -> Result<Vec<ConsentRequest>> {
+        let requests = self
+            .persistence
+            .list_consent_requests(Some(ConsentStatus::Pending))
+            .await?;
+        Ok(requests.into_iter().filter(|r| !r.is_expired()).collect())
+    }
+
+    /// Approve a pending request and issue its token
+    pub async fn approve(&self, id: &str) -> Result<ConsentGrant> {
+        let request = self.get(id).await?;
+        if request.status != ConsentStatus::Pending {
+            return Err(ConsentError::InvalidState {
+                id: request.id,
+                status: request.status,
+            });
+        }
+        if request.is_expired() {
+            return Err(ConsentError::Expired(format!(
+                "request {} was not approved in time",
+                request.id
+            )));
+        }
+
+        // Tokens carry whole seconds, so truncate before signing
+        let expires_at = DateTime::from_timestamp((Utc::now() + self.ttl).timestamp(), 0)
+            .unwrap_or_else(Utc::now);
+        if !self
+            .persistence
+            .transition_consent_request(
+                &request.id,
+                ConsentStatus::Pending,
+                ConsentStatus::Approved,
+                Some(expires_at),
+            )
+            .await?
+        {
+            let current = self.get(id).await?;
+            return Err(ConsentError::InvalidState {
+                id: current.id,
+                status: current.status,
+            });
+        }
+
+        let signature = self.sign(&request, expires_at.timestamp());
+        info!("Consent request {} approved", request.id);
+        Ok(ConsentGrant {
+            token: format!(
+                "{}.{}.{}.{}",
+                TOKEN_PREFIX,
+                request.id,
+                expires_at.timestamp(),
+                signature.to_hex()
+            ),
+            request_id: request.id,
+            level: request.level,
+            expires_at,
+        })
+    }
+
+    /// Deny a pending request
+    pub async fn deny(&self, id: &str) -> Result<()> {
+        let request = self.get(id).await?;
+        if !self
+            .persistence
+            .transition_consent_request(
+                &request.id,
+                ConsentStatus::Pending,
+                ConsentStatus::Denied,
+                None,
+            )
+            .await?
+        {
+            return Err(ConsentError::InvalidState {
+                id: request.id,
+                status: request.status,
+            });
+        }
+        info!("Consent request {} denied", request.id);
+        Ok(())
+    }
+
+    /// Check a token against a call without spending it
+    ///
+    /// Returns the consent level the token grants.
+    pub async fn verify(
+        &self,
+        token: &str,
+        callable_id: &CallableId,
+        arguments: &serde_json::Value,
+    ) -> Result<ConsentLevel> {
+        let request = self.check(token, callable_id, arguments).await?;
+        Ok(request.level)
+    }
+
+    /// Verify a token and mark its request consumed so it cannot be replayed
+    pub async fn consume(
+        &self,
+        token: &str,
+        callable_id: &CallableId,
+        arguments: &serde_json::Value,
+    ) -> Result<ConsentLevel> {
+        let request = self.check(token, callable_id, arguments).await?;
+        if !self
+            .persistence
+            .transition_consent_request(
+                &request.id,
+                ConsentStatus::Approved,
+                ConsentStatus::Consumed,
+                None,
+            )
+            .await?
+        {
+            return Err(ConsentError::InvalidState {
+                id: request.id,
+                status: ConsentStatus::Consumed,
+            });
+        }
+        debug!("Consent request {} consumed", request.id);
+        Ok(request.level)
+    }
+
+    async fn check(
+        &self,
+        token: &str,
+        callable_id: &CallableId,
+        arguments: &serde_json::Value,
+    ) -> Result<ConsentRequest> {
+        let malformed = || ConsentError::InvalidToken("malformed token".to_string());
+        let mut parts = token.trim().split('.');
+        if parts.next() != Some(TOKEN_PREFIX) {
+            return Err(malformed());
+        }
+        let (id, expires, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(id), Some(expires), Some(sig)) if parts.next().is_none() => (id, expires, sig),
+            _ => return Err(malformed()),
+        };
+        let expires: i64 = expires.parse().map_err(|_| malformed())?;
+        let signature = blake3::Hash::from_hex(signature).map_err(|_| malformed())?;
+
+        let request = match self.get(id).await {
+            Err(ConsentError::NotFound(_)) => {
+                return Err(ConsentError::InvalidToken("unknown request".to_string()))
+            }
+            other => other?,
+        };
+
+        // blake3::Hash compares in constant time
+        if self.sign(&request, expires) != signature || request.expires_at.timestamp() != expires {
+            return Err(ConsentError::InvalidToken("bad signature".to_string()));
+        }
+        if request.status != ConsentStatus::Approved {
+            return Err(ConsentError::InvalidState {
+                id: request.id,
+                status: request.status,
+            });
+        }
+        if request.is_expired() {
+            return Err(ConsentError::Expired(format!(
+                "token for request {}",
+                request.id
+            )));
+        }
+        if &request.callable_id != callable_id {
+            return Err(ConsentError::Mismatch(format!(
+                "issued for {}",
+                request.fq_name
+            )));
+        }
+        if Self::args_digest(arguments)? != request.args_digest {
+            return Err(ConsentError::Mismatch(
+                "arguments differ from the approved request".to_string(),
+            ));
+        }
+
+        Ok(request)
+    }
+
+    fn sign(&self, request: &ConsentRequest, expires: i64) -> blake3::Hash {
+        let payload = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            request.id,
+            request.callable_id.as_str(),
+            request.args_digest,
+            request.level,
+            expires
+        );
+        blake3::keyed_hash(&self.key, payload.as_bytes())
+    }
+}
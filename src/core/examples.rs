@@ -0,0 +1,337 @@
+//! Example arguments for callables
+//!
+//! Most upstream tools declare no `examples`, so signatures fall back to
+//! arguments synthesized from the input schema: one with only the required
+//! fields and one that also fills in optional fields. Values come from
+//! `const`, `examples`, `default`, and `enum` where the schema has them, and
+//! otherwise from the type, `format`, and bounds. Every example is validated
+//! against the full schema before it is offered, so a schema the generator
+//! cannot satisfy (e.g. a `pattern` it cannot match) yields fewer examples
+//! rather than wrong ones.
+//!
+//! [`from_history`] picks real arguments from successful executions instead.
+
+use crate::core::persistence::{ExecutionRecord, REDACTED};
+use crate::core::validation;
+use serde_json::{Map, Value};
+
+/// Maximum number of examples offered per callable
+pub const MAX_EXAMPLES: usize = 2;
+
+/// Nesting depth below which optional fields are left out
+const MAX_DEPTH: usize = 6;
+
+/// Strings tried for unconstrained or patterned fields, after the field name
+const STRING_CANDIDATES: &[&str] = &["example", "a", "1", "A", "a1", "a-b"];
+
+/// Synthesize up to [`MAX_EXAMPLES`] valid argument objects from a schema
+pub fn synthesize(schema: &Value) -> Vec<Value> {
+    let mut examples: Vec<Value> = Vec::new();
+    for optional in [false, true] {
+        let mut generator = Generator {
+            root: schema,
+            optional,
+            followed: Vec::new(),
+        };
+        let Some(example) = generator.value(schema, None, 0) else {
+            continue;
+        };
+        if validation::is_valid(schema, &example) && !examples.contains(&example) {
+            examples.push(example);
+        }
+    }
+    examples
+}
+
+/// Arguments of successful executions that still match the schema, newest first
+///
+/// Records are expected newest first. Arguments with redacted values are
+/// skipped, since they would not be valid to send back.
+pub fn from_history(schema: &Value, records: &[ExecutionRecord], limit: usize) -> Vec<Value> {
+    let mut examples: Vec<Value> = Vec::new();
+    for record in records {
+        if examples.len() >= limit {
+            break;
+        }
+        if record.is_error
+            || contains_redacted(&record.arguments)
+            || examples.contains(&record.arguments)
+            || !validation::is_valid(schema, &record.arguments)
+        {
+            continue;
+        }
+        examples.push(record.arguments.clone());
+    }
+    examples
+}
+
+/// `preferred` examples followed by `fallback` ones, without duplicates
+pub fn merge(preferred: Vec<Value>, fallback: &[Value]) -> Vec<Value> {
+    let mut examples = preferred;
+    for example in fallback {
+        if !examples.contains(example) {
+            examples.push(example.clone());
+        }
+    }
+    examples.truncate(MAX_EXAMPLES);
+    examples
+}
+
+fn contains_redacted(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s == REDACTED,
+        Value::Array(items) => items.iter().any(contains_redacted),
+        Value::Object(map) => map.values().any(contains_redacted),
+        _ => false,
+    }
+}
+
+struct Generator<'a> {
+    root: &'a Value,
+    /// Fill in optional properties too
+    optional: bool,
+    /// `$ref`s being expanded, so recursive schemas stop
+    followed: Vec<&'a str>,
+}
+
+impl<'a> Generator<'a> {
+    /// Example value for `schema`; `name` is the property it belongs to
+    fn value(&mut self, schema: &'a Value, name: Option<&str>, depth: usize) -> Option<Value> {
+        let node = match schema {
+            Value::Bool(true) => return Some(Value::String("example".to_string())),
+            Value::Object(node) => node,
+            _ => return None,
+        };
+
+        if let Some(reference) = node.get("$ref").and_then(|r| r.as_str()) {
+            if self.followed.contains(&reference) {
+                return None;
+            }
+            let target = validation::resolve_ref(self.root, reference)?;
+            self.followed.push(reference);
+            let value = self.value(target, name, depth);
+            self.followed.pop();
+            return value;
+        }
+
+        // Values the schema spells out
+        if let Some(value) = node.get("const") {
+            return Some(value.clone());
+        }
+        if let Some(value) = node
+            .get("examples")
+            .and_then(|e| e.as_array())
+            .and_then(|e| e.first())
+        {
+            return Some(value.clone());
+        }
+        if let Some(value) = node.get("default") {
+            return Some(value.clone());
+        }
+        if let Some(value) = node
+            .get("enum")
+            .and_then(|e| e.as_array())
+            .and_then(|e| e.first())
+        {
+            return Some(value.clone());
+        }
+
+        if let Some(branches) = ["anyOf", "oneOf"]
+            .iter()
+            .find_map(|key| node.get(*key).and_then(|b| b.as_array()))
+        {
+            // Prefer a branch that is not just `null`
+            let mut values: Vec<Value> = branches
+                .iter()
+                .filter_map(|branch| self.value(branch, name, depth))
+                .collect();
+            values.sort_by_key(|v| v.is_null());
+            return values.into_iter().next();
+        }
+        if let Some(parts) = node.get("allOf").and_then(|b| b.as_array()) {
+            return self.merge(node, parts, name, depth);
+        }
+
+        match schema_type(node) {
+            "object" => self.object(node, depth),
+            "array" => self.array(node, name, depth),
+            "integer" => number(schema, true),
+            "number" => number(schema, false),
+            "boolean" => Some(Value::Bool(true)),
+            "null" => Some(Value::Null),
+            _ => string(schema, name),
+        }
+    }
+
+    fn object(&mut self, node: &'a Map<String, Value>, depth: usize) -> Option<Value> {
+        let required: Vec<&str> = node
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        let properties = node.get("properties").and_then(|p| p.as_object());
+
+        let mut object = Map::new();
+        if let Some(properties) = properties {
+            for (key, property) in properties {
+                let is_required = required.contains(&key.as_str());
+                let wanted = is_required || (self.optional && depth < MAX_DEPTH);
+                if !wanted {
+                    continue;
+                }
+                match self.value(property, Some(key), depth + 1) {
+                    Some(value) => {
+                        object.insert(key.clone(), value);
+                    }
+                    None if is_required => return None,
+                    None => {}
+                }
+            }
+        }
+        // Required fields the schema does not describe
+        for key in required {
+            if !object.contains_key(key) {
+                object.insert(key.to_string(), Value::String(key.to_string()));
+            }
+        }
+        Some(Value::Object(object))
+    }
+
+    fn array(
+        &mut self,
+        node: &'a Map<String, Value>,
+        name: Option<&str>,
+        depth: usize,
+    ) -> Option<Value> {
+        let mut items = Vec::new();
+        if let Some(prefix) = node.get("prefixItems").and_then(|p| p.as_array()) {
+            for item in prefix {
+                items.push(self.value(item, name, depth + 1)?);
+            }
+        }
+
+        let min = node.get("minItems").and_then(|m| m.as_u64()).unwrap_or(0) as usize;
+        let max = node
+            .get("maxItems")
+            .and_then(|m| m.as_u64())
+            .map_or(usize::MAX, |m| m as usize);
+        let wanted = min.max(1).min(max);
+        if items.len() < wanted {
+            match node.get("items") {
+                Some(schema) if schema.is_object() => {
+                    let item = self.value(schema, name, depth + 1)?;
+                    items.resize(wanted, item);
+                }
+                _ if items.len() < min => return None,
+                _ => {}
+            }
+        }
+        Some(Value::Array(items))
+    }
+
+    fn merge(
+        &mut self,
+        node: &'a Map<String, Value>,
+        parts: &'a [Value],
+        name: Option<&str>,
+        depth: usize,
+    ) -> Option<Value> {
+        let mut merged = match schema_type(node) {
+            "object" => self.object(node, depth)?,
+            _ => Value::Null,
+        };
+        for part in parts {
+            let value = self.value(part, name, depth)?;
+            match (&mut merged, value) {
+                (Value::Object(merged), Value::Object(value)) => merged.extend(value),
+                (Value::Null, value) => merged = value,
+                _ => {}
+            }
+        }
+        Some(merged)
+    }
+}
+
+/// The schema's `type`, inferred from its keywords when absent
+fn schema_type(node: &Map<String, Value>) -> &str {
+    match node.get("type") {
+        Some(Value::String(kind)) => kind,
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(|k| k.as_str())
+            .find(|k| *k != "null")
+            .unwrap_or("null"),
+        _ if node.contains_key("properties") || node.contains_key("required") => "object",
+        _ if node.contains_key("items") || node.contains_key("prefixItems") => "array",
+        _ if node.contains_key("minimum") || node.contains_key("maximum") => "number",
+        _ => "string",
+    }
+}
+
+fn string(schema: &Value, name: Option<&str>) -> Option<Value> {
+    let format = match schema.get("format").and_then(|f| f.as_str()) {
+        Some("date-time") => Some("2025-01-01T12:00:00Z"),
+        Some("date") => Some("2025-01-01"),
+        Some("time") => Some("12:00:00"),
+        Some("email") => Some("user@example.com"),
+        Some("uri") | Some("url") => Some("https://example.com"),
+        Some("uuid") => Some("3fa85f64-5717-4562-b3fc-2c963f66afa6"),
+        Some("ipv4") => Some("192.0.2.1"),
+        Some("ipv6") => Some("2001:db8::1"),
+        Some("hostname") => Some("example.com"),
+        _ => None,
+    };
+    let min = schema
+        .get("minLength")
+        .and_then(|m| m.as_u64())
+        .unwrap_or(0) as usize;
+    let max = schema.get("maxLength").and_then(|m| m.as_u64());
+
+    format
+        .into_iter()
+        .chain(name)
+        .chain(STRING_CANDIDATES.iter().copied())
+        .map(|candidate| {
+            let mut text = candidate.to_string();
+            while text.chars().count() < min {
+                text.push('x');
+            }
+            if let Some(max) = max {
+                text = text.chars().take(max as usize).collect();
+            }
+            Value::String(text)
+        })
+        .find(|candidate| validation::is_valid(schema, candidate))
+}
+
+fn number(schema: &Value, integer: bool) -> Option<Value> {
+    let bound = |key: &str| schema.get(key).and_then(|b| b.as_f64());
+    let step = if integer { 1.0 } else { 0.5 };
+    // Lower bounds first, then small values, then upper bounds
+    let candidates = [
+        bound("minimum"),
+        bound("exclusiveMinimum").map(|b| b + step),
+        Some(1.0),
+        Some(0.0),
+        bound("maximum"),
+        bound("exclusiveMaximum").map(|b| b - step),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .map(|mut candidate| {
+            if let Some(multiple) = bound("multipleOf").filter(|m| *m > 0.0) {
+                candidate = (candidate / multiple).ceil() * multiple;
+            }
+            if integer {
+                candidate = candidate.ceil();
+            }
+            if integer && candidate.abs() < i64::MAX as f64 {
+                Value::from(candidate as i64)
+            } else {
+                Value::from(candidate)
+            }
+        })
+        .find(|candidate| validation::is_valid(schema, candidate))
+}
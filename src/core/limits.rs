@@ -0,0 +1,396 @@
+//! Rate limits and quotas
+//!
+//! Two kinds of limits, configured under `policy.rate_limits`:
+//! - Token buckets (`rate` calls per `per`, with an optional `burst`)
+//! - Rolling quotas (`quota` calls per sliding `window`)
+//!
+//! Each rule is scoped to a callable, server, tag, or MCP session, and every
+//! distinct scope value gets its own counter. Counters live in memory and are
+//! written through to SQLite so limits survive restarts.
+
+use crate::core::persistence::{self, PersistenceLayer};
+use crate::core::policy::{PolicyError, Result};
+use crate::core::CallableRecord;
+use glob::Pattern;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Session key used when the transport has no MCP session id (stdio)
+pub const DEFAULT_SESSION: &str = "stdio";
+
+/// What a rate limit is keyed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitScope {
+    /// One counter per callable (fully qualified name)
+    Callable,
+    /// One counter per upstream server alias
+    Server,
+    /// One counter per tag
+    Tag,
+    /// One counter per MCP session
+    Session,
+}
+
+impl LimitScope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LimitScope::Callable => "callable",
+            LimitScope::Server => "server",
+            LimitScope::Tag => "tag",
+            LimitScope::Session => "session",
+        }
+    }
+}
+
+/// Rate limit rule
+///
+/// ```yaml
+/// rate_limits:
+///   - scope: server
+///     match: "paid-api"
+///     rate: 10
+///     per: 1m
+///   - scope: session
+///     quota: 500
+///     window: 24h
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitRule {
+    pub scope: LimitScope,
+
+    /// Glob over the scope value (callable name, server alias, tag, or session id)
+    #[serde(rename = "match", default = "default_match")]
+    pub pattern: String,
+
+    /// Token bucket: calls allowed per `per`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<u32>,
+
+    /// Token bucket refill period (e.g. `1s`, `1m`; default `1m`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per: Option<String>,
+
+    /// Token bucket capacity (default: `rate`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+
+    /// Rolling quota: calls allowed per `window`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<u64>,
+
+    /// Rolling quota window (e.g. `1h`, `24h`, `7d`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+}
+
+fn default_match() -> String {
+    "*".to_string()
+}
+
+/// Parse a duration like `30s`, `5m`, `1h`, `7d`, or plain seconds, into milliseconds
+pub fn parse_duration_ms(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().ok()?;
+    let unit_ms = match unit.trim() {
+        "" | "s" => 1_000,
+        "ms" => 1,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        "w" => 604_800_000,
+        _ => return None,
+    };
+    amount.checked_mul(unit_ms).filter(|ms| *ms > 0)
+}
+
+struct TokenBucket {
+    capacity: f64,
+    /// Tokens added per millisecond
+    refill_per_ms: f64,
+    description: String,
+}
+
+struct RollingQuota {
+    limit: u64,
+    window_ms: i64,
+    description: String,
+}
+
+struct CompiledLimit {
+    /// Stable id derived from the rule, so reordering rules keeps counters
+    id: String,
+    scope: LimitScope,
+    pattern: Pattern,
+    bucket: Option<TokenBucket>,
+    quota: Option<RollingQuota>,
+}
+
+impl CompiledLimit {
+    fn compile(index: usize, rule: &RateLimitRule) -> Result<Self> {
+        let error =
+            |msg: String| PolicyError::ConfigError(format!("rate_limits[{}]: {}", index, msg));
+        let duration = |field: &str, value: &str| {
+            parse_duration_ms(value).ok_or_else(|| error(format!("invalid {}: {}", field, value)))
+        };
+
+        if rule.rate.is_none() && rule.quota.is_none() {
+            return Err(error("set rate and/or quota".to_string()));
+        }
+
+        let bucket = match rule.rate {
+            Some(0) => return Err(error("rate must be positive".to_string())),
+            Some(rate) => {
+                let per = rule.per.as_deref().unwrap_or("1m");
+                let per_ms = duration("per", per)?;
+                Some(TokenBucket {
+                    capacity: f64::from(rule.burst.unwrap_or(rate).max(1)),
+                    refill_per_ms: f64::from(rate) / per_ms as f64,
+                    description: format!("{} per {}", rate, per),
+                })
+            }
+            None if rule.per.is_some() || rule.burst.is_some() => {
+                return Err(error("per and burst require rate".to_string()))
+            }
+            None => None,
+        };
+
+        let quota = match (rule.quota, rule.window.as_deref()) {
+            (Some(limit), Some(window)) => Some(RollingQuota {
+                limit,
+                window_ms: duration("window", window)?,
+                description: format!("{} per {}", limit, window),
+            }),
+            (Some(_), None) => return Err(error("quota requires window".to_string())),
+            (None, Some(_)) => return Err(error("window requires quota".to_string())),
+            (None, None) => None,
+        };
+
+        let fingerprint = serde_json::to_string(rule).unwrap_or_default();
+        Ok(CompiledLimit {
+            id: blake3::hash(fingerprint.as_bytes()).to_hex()[..8].to_string(),
+            scope: rule.scope,
+            pattern: Pattern::new(&rule.pattern)
+                .map_err(|e| error(format!("invalid match: {}", e)))?,
+            bucket,
+            quota,
+        })
+    }
+
+    /// Scope values this rule applies to for a call
+    fn scope_values<'a>(&self, callable: &'a CallableRecord, session: &'a str) -> Vec<&'a str> {
+        let candidates: Vec<&str> = match self.scope {
+            LimitScope::Callable => vec![callable.fq_name.as_str()],
+            LimitScope::Server => callable.server_alias.as_deref().into_iter().collect(),
+            LimitScope::Tag => callable.tags.iter().map(String::as_str).collect(),
+            LimitScope::Session => vec![session],
+        };
+        let mut values: Vec<&str> = candidates
+            .into_iter()
+            .filter(|value| self.pattern.matches(value))
+            .collect();
+        // A repeated tag is still one counter, charged once
+        values.sort_unstable();
+        values.dedup();
+        values
+    }
+
+    fn key(&self, value: &str) -> String {
+        format!("{}/{}/{}", self.id, self.scope.as_str(), value)
+    }
+}
+
+#[derive(Default)]
+struct LimiterState {
+    /// Key -> (tokens, last refill in ms)
+    buckets: HashMap<String, (f64, i64)>,
+    /// Key -> call timestamps in ms, oldest first
+    events: HashMap<String, VecDeque<i64>>,
+}
+
+/// Enforces `policy.rate_limits`
+pub struct RateLimiter {
+    limits: Vec<CompiledLimit>,
+    state: Mutex<LimiterState>,
+    persistence: Option<Arc<PersistenceLayer>>,
+    /// Held from counter update through its write, so writes land in update order
+    writes: tokio::sync::Mutex<()>,
+}
+
+impl RateLimiter {
+    /// Compile rate limit rules
+    pub fn new(rules: &[RateLimitRule]) -> Result<Self> {
+        let limits = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| CompiledLimit::compile(i, rule))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(RateLimiter {
+            limits,
+            state: Mutex::new(LimiterState::default()),
+            persistence: None,
+            writes: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Write usage counters through to SQLite
+    pub fn with_persistence(mut self, persistence: Arc<PersistenceLayer>) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// Load persisted counters, dropping quota events older than any window
+    pub async fn restore(&self) -> persistence::Result<usize> {
+        let Some(persistence) = &self.persistence else {
+            return Ok(0);
+        };
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let max_window = self
+            .limits
+            .iter()
+            .filter_map(|l| l.quota.as_ref().map(|q| q.window_ms))
+            .max()
+            .unwrap_or(0);
+        persistence.prune_rate_events(now - max_window).await?;
+
+        let buckets = persistence.load_rate_buckets().await?;
+        let events = persistence.load_rate_events(now - max_window).await?;
+        let restored = buckets.len() + events.len();
+
+        let mut state = self.state.lock();
+        for (key, tokens, updated_ms) in buckets {
+            state.buckets.insert(key, (tokens, updated_ms));
+        }
+        for (key, at) in events {
+            state.events.entry(key).or_default().push_back(at);
+        }
+
+        debug!("Restored {} rate limit counters", restored);
+        Ok(restored)
+    }
+
+    /// Count one call against every matching limit, or fail without counting it
+    pub async fn acquire(&self, callable: &CallableRecord, session: &str) -> Result<()> {
+        if self.limits.is_empty() {
+            return Ok(());
+        }
+
+        // Without it, a slower write could replace a newer bucket snapshot
+        let _write_order = match &self.persistence {
+            Some(_) => Some(self.writes.lock().await),
+            None => None,
+        };
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let targets: Vec<(&CompiledLimit, String, &str)> = self
+            .limits
+            .iter()
+            .flat_map(|limit| {
+                limit
+                    .scope_values(callable, session)
+                    .into_iter()
+                    .map(move |value| (limit, limit.key(value), value))
+            })
+            .collect();
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        let mut bucket_updates = Vec::new();
+        let mut event_keys = Vec::new();
+        {
+            let mut state = self.state.lock();
+
+            // Check everything before consuming anything
+            for (limit, key, value) in &targets {
+                if let Some(bucket) = &limit.bucket {
+                    let tokens = refill(state.buckets.get(key), bucket, now);
+                    if tokens < 1.0 {
+                        let wait_ms = ((1.0 - tokens) / bucket.refill_per_ms).ceil() as i64;
+                        return Err(exceeded(limit, value, &bucket.description, wait_ms));
+                    }
+                }
+                if let Some(quota) = &limit.quota {
+                    if let Some(events) = state.events.get_mut(key) {
+                        while events
+                            .front()
+                            .is_some_and(|at| *at <= now - quota.window_ms)
+                        {
+                            events.pop_front();
+                        }
+                        if events.len() as u64 >= quota.limit {
+                            let oldest = events.front().copied().unwrap_or(now);
+                            let wait_ms = oldest + quota.window_ms - now;
+                            return Err(exceeded(limit, value, &quota.description, wait_ms));
+                        }
+                    }
+                }
+            }
+
+            for (limit, key, _) in &targets {
+                if let Some(bucket) = &limit.bucket {
+                    let tokens = refill(state.buckets.get(key), bucket, now) - 1.0;
+                    state.buckets.insert(key.clone(), (tokens, now));
+                    bucket_updates.push((key.clone(), tokens, now));
+                }
+                if limit.quota.is_some() {
+                    state.events.entry(key.clone()).or_default().push_back(now);
+                    event_keys.push(key.clone());
+                }
+            }
+        }
+
+        if let Some(persistence) = &self.persistence {
+            if let Err(e) = persistence.save_rate_buckets(&bucket_updates).await {
+                warn!("Failed to persist rate limit buckets: {}", e);
+            }
+            if let Err(e) = persistence.record_rate_events(&event_keys, now).await {
+                warn!("Failed to persist rate limit events: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Current token count after refilling since the last update
+fn refill(current: Option<&(f64, i64)>, bucket: &TokenBucket, now: i64) -> f64 {
+    match current {
+        Some((tokens, updated_ms)) => {
+            let elapsed = (now - updated_ms).max(0) as f64;
+            (tokens + elapsed * bucket.refill_per_ms).min(bucket.capacity)
+        }
+        None => bucket.capacity,
+    }
+}
+
+fn exceeded(limit: &CompiledLimit, value: &str, description: &str, wait_ms: i64) -> PolicyError {
+    let retry_secs = (wait_ms.max(0) + 999) / 1000;
+    warn!(
+        "Rate limit for {} {} exceeded ({})",
+        limit.scope.as_str(),
+        value,
+        description
+    );
+    PolicyError::LimitExceeded(format!(
+        "rate limit for {} {} ({}) exceeded; retry in {}s",
+        limit.scope.as_str(),
+        value,
+        description,
+        retry_secs.max(1)
+    ))
+}
@@ -8,10 +8,18 @@
 //! - Persistence layer
 //! - Registry store for callables
 //! - Policy engine for access control
+//! - Consent grants for elevated risk tiers
+//! - Rate limits and quotas
 
+pub mod budget;
+pub mod consent;
+pub mod examples;
+pub mod limits;
 pub mod persistence;
 pub mod policy;
 pub mod registry;
+pub mod render;
+pub mod validation;
 
 use crate::execution::sandbox::SandboxConfigOverride;
 use blake3::Hash;
@@ -34,6 +42,9 @@ pub enum CoreError {
     #[error("Invalid risk tier: {0}")]
     InvalidRiskTier(String),
 
+    #[error("Invalid schema format: {0} (expected json_schema, signature, both, typescript, outline, or minimal)")]
+    InvalidSchemaFormat(String),
+
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 }
@@ -316,6 +327,9 @@ pub struct CallableRecord {
     // Metadata
     pub cost_hints: CostHints,
     pub risk_tier: RiskTier,
+    /// Behavior hints declared by the upstream server, after config overrides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
     pub last_seen: DateTime<Utc>,
 
     /// Per-tool sandbox configuration override
@@ -388,15 +402,131 @@ impl ToolResult {
         self.structured_content = Some(data);
         self
     }
+
+    /// Parse the result of an MCP `tools/call`
+    ///
+    /// Keeps text, image, and embedded resource items, `structuredContent`,
+    /// and `isError`. A result without a `content` array is kept as text.
+    pub fn from_call_result(result: serde_json::Value) -> Self {
+        let is_error = result
+            .get("isError")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let structured_content = result.get("structuredContent").cloned();
+
+        let Some(items) = result.get("content").and_then(|v| v.as_array()) else {
+            return ToolResult {
+                content: vec![ToolResultContent::Text {
+                    text: result.to_string(),
+                }],
+                structured_content,
+                is_error,
+            };
+        };
+
+        let string = |value: &serde_json::Value, key: &str| {
+            value.get(key).and_then(|v| v.as_str()).map(str::to_string)
+        };
+        let content = items
+            .iter()
+            .filter_map(|item| match item.get("type").and_then(|v| v.as_str())? {
+                "text" => Some(ToolResultContent::Text {
+                    text: string(item, "text").unwrap_or_default(),
+                }),
+                "image" => Some(ToolResultContent::Image {
+                    data: string(item, "data").unwrap_or_default(),
+                    mime_type: string(item, "mimeType").unwrap_or_else(|| "image/png".to_string()),
+                }),
+                "resource" => {
+                    // Embedded resources nest their contents under `resource`
+                    let resource = item.get("resource").unwrap_or(item);
+                    Some(ToolResultContent::Resource {
+                        resource: ResourceContent {
+                            uri: string(resource, "uri").unwrap_or_default(),
+                            mime_type: string(resource, "mimeType"),
+                            text: string(resource, "text"),
+                            blob: string(resource, "blob"),
+                        },
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+
+        ToolResult {
+            content,
+            structured_content,
+            is_error,
+        }
+    }
 }
 
 /// MCP tool definition (upstream)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolDefinition {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     pub description: Option<String>,
     #[serde(rename = "inputSchema")]
     pub input_schema: serde_json::Value,
+    #[serde(
+        rename = "outputSchema",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_schema: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
+}
+
+/// Behavior hints an MCP server declares for a tool
+///
+/// Hints are untrusted. Unset hints take the MCP defaults: not read-only,
+/// destructive, not idempotent, and open-world.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolAnnotations {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotent_hint: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_world_hint: Option<bool>,
+}
+
+impl ToolAnnotations {
+    /// Replace hints with those set in `overrides`
+    pub fn apply(&mut self, overrides: &ToolAnnotations) {
+        if overrides.title.is_some() {
+            self.title = overrides.title.clone();
+        }
+        self.read_only_hint = overrides.read_only_hint.or(self.read_only_hint);
+        self.destructive_hint = overrides.destructive_hint.or(self.destructive_hint);
+        self.idempotent_hint = overrides.idempotent_hint.or(self.idempotent_hint);
+        self.open_world_hint = overrides.open_world_hint.or(self.open_world_hint);
+    }
+
+    /// Risk tier implied by the hints
+    ///
+    /// Without a read-only or destructive hint the tier stays `Unknown`.
+    pub fn risk_tier(&self) -> RiskTier {
+        match (self.read_only_hint, self.destructive_hint) {
+            (Some(true), _) => RiskTier::ReadOnly,
+            (_, Some(false)) => RiskTier::Writes,
+            (Some(false), _) | (_, Some(true)) => RiskTier::Destructive,
+            (None, None) => RiskTier::Unknown,
+        }
+    }
+
+    /// Whether the tool reaches beyond a closed set of local resources
+    pub fn open_world(&self) -> bool {
+        self.open_world_hint.unwrap_or(true)
+    }
 }
 
 /// Distilled signature for a callable (human-readable schema summary)
@@ -410,6 +540,10 @@ pub struct CallableSignature {
 
 impl CallableSignature {
     /// Generate signature from JSON Schema
+    ///
+    /// Fields of nested objects are listed by path, `options.draft` for an
+    /// object property and `items[].name` for an array of objects, and local
+    /// `$ref`s are followed.
     pub fn from_schema(schema: &serde_json::Value) -> Self {
         let mut signature = CallableSignature {
             required: Vec::new(),
@@ -418,51 +552,256 @@ impl CallableSignature {
             examples: Vec::new(),
         };
 
-        if let Some(obj) = schema.as_object() {
-            // Extract required fields
-            if let Some(required) = obj.get("required").and_then(|r| r.as_array()) {
-                signature.required = required
-                    .iter()
-                    .filter_map(|v| v.as_str().map(String::from))
-                    .collect();
+        signature.collect(schema, schema, "", &mut Vec::new());
+
+        // Declared examples, else ones synthesized from the schema
+        signature.examples = match schema.get("examples").and_then(|e| e.as_array()) {
+            Some(examples) => examples.clone(),
+            None => examples::synthesize(schema),
+        };
+
+        signature
+    }
+
+    /// Add the fields of an object schema under `prefix`
+    ///
+    /// `followed` holds the `$ref`s being expanded, so recursive schemas stop.
+    fn collect<'a>(
+        &mut self,
+        root: &'a serde_json::Value,
+        schema: &'a serde_json::Value,
+        prefix: &str,
+        followed: &mut Vec<&'a str>,
+    ) {
+        let reference = schema.get("$ref").and_then(|r| r.as_str());
+        if let Some(reference) = reference {
+            if followed.contains(&reference) {
+                return;
             }
+            followed.push(reference);
+        }
+        self.collect_fields(root, resolve_local_ref(root, schema), prefix, followed);
+        if reference.is_some() {
+            followed.pop();
+        }
+    }
+
+    fn collect_fields<'a>(
+        &mut self,
+        root: &'a serde_json::Value,
+        schema: &'a serde_json::Value,
+        prefix: &str,
+        followed: &mut Vec<&'a str>,
+    ) {
+        let Some(obj) = schema.as_object() else {
+            return;
+        };
 
-            // Extract properties
-            if let Some(properties) = obj.get("properties").and_then(|p| p.as_object()) {
-                for (key, prop) in properties {
-                    let is_required = signature.required.contains(key);
-
-                    if !is_required {
-                        signature.optional.push(key.clone());
-                    }
-
-                    // Extract constraints
-                    if let Some(prop_obj) = prop.as_object() {
-                        let mut constraint_parts = Vec::new();
-
-                        if let Some(typ) = prop_obj.get("type").and_then(|t| t.as_str()) {
-                            constraint_parts.push(typ.to_string());
-                        }
-
-                        if let Some(desc) = prop_obj.get("description").and_then(|d| d.as_str()) {
-                            constraint_parts.push(desc.to_string());
-                        }
-
-                        if !constraint_parts.is_empty() {
-                            signature
-                                .constraints
-                                .insert(key.clone(), constraint_parts.join("; "));
-                        }
-                    }
+        // Extract required fields
+        let required: Vec<&str> = obj
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        self.required
+            .extend(required.iter().map(|name| format!("{}{}", prefix, name)));
+
+        // Extract properties
+        let Some(properties) = obj.get("properties").and_then(|p| p.as_object()) else {
+            return;
+        };
+        for (key, prop) in properties {
+            let path = format!("{}{}", prefix, key);
+            if !required.contains(&key.as_str()) {
+                self.optional.push(path.clone());
+            }
+
+            // Extract constraints
+            let resolved = resolve_local_ref(root, prop);
+            if let Some(prop_obj) = resolved.as_object() {
+                let mut constraint_parts = Vec::new();
+
+                if let Some(typ) = prop_obj.get("type").and_then(|t| t.as_str()) {
+                    constraint_parts.push(typ.to_string());
+                }
+
+                if let Some(desc) = prop_obj.get("description").and_then(|d| d.as_str()) {
+                    constraint_parts.push(desc.to_string());
+                }
+
+                if !constraint_parts.is_empty() {
+                    self.constraints
+                        .insert(path.clone(), constraint_parts.join("; "));
+                }
+            }
+
+            if path.matches('.').count() >= SIGNATURE_MAX_NESTING {
+                continue;
+            }
+            if resolved.get("properties").is_some() {
+                self.collect(root, prop, &format!("{}.", path), followed);
+            } else if let Some(items) = resolved.get("items") {
+                if resolve_local_ref(root, items).get("properties").is_some() {
+                    self.collect(root, items, &format!("{}[].", path), followed);
                 }
             }
+        }
+    }
 
-            // Extract examples
-            if let Some(examples) = obj.get("examples").and_then(|e| e.as_array()) {
-                signature.examples = examples.clone();
+    /// Render a compact, TypeScript-like one-line signature
+    ///
+    /// Required parameters come first, in `required` order, then optional
+    /// ones marked with `?`. The return type is the output schema's `title`
+    /// when it has one, and is omitted without an output schema, e.g.
+    /// `(repo: string, issue: int, labels?: string[]) -> Issue`.
+    pub fn short(
+        input_schema: &serde_json::Value,
+        output_schema: Option<&serde_json::Value>,
+    ) -> String {
+        let properties = input_schema.get("properties").and_then(|p| p.as_object());
+        let required: Vec<&str> = input_schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+
+        let mut params = Vec::new();
+        if let Some(properties) = properties {
+            for name in &required {
+                if let Some(prop) = properties.get(*name) {
+                    params.push(format!("{}: {}", name, type_expr(prop, 0)));
+                }
+            }
+            for (name, prop) in properties {
+                if !required.contains(&name.as_str()) {
+                    params.push(format!("{}?: {}", name, type_expr(prop, 0)));
+                }
             }
         }
 
+        let mut signature = format!("({})", params.join(", "));
+        if let Some(output) = output_schema {
+            let returns = output
+                .get("title")
+                .and_then(|t| t.as_str())
+                .map(String::from)
+                .unwrap_or_else(|| type_expr(output, 0));
+            signature.push_str(" -> ");
+            signature.push_str(&returns);
+        }
         signature
     }
 }
+
+/// Nested objects expanded into signature paths
+const SIGNATURE_MAX_NESTING: usize = 4;
+
+/// Target of a local `$ref` (`#/...`), or the schema itself
+fn resolve_local_ref<'a>(
+    root: &'a serde_json::Value,
+    schema: &'a serde_json::Value,
+) -> &'a serde_json::Value {
+    schema
+        .get("$ref")
+        .and_then(|r| r.as_str())
+        .and_then(|r| r.strip_prefix('#'))
+        .and_then(|pointer| root.pointer(pointer))
+        .unwrap_or(schema)
+}
+
+/// Enum values listed before the rest are elided
+const SIGNATURE_MAX_ENUM: usize = 5;
+
+/// Object nesting rendered inline before falling back to `object`
+const SIGNATURE_MAX_DEPTH: usize = 2;
+
+/// TypeScript-like type expression for a JSON Schema
+fn type_expr(schema: &serde_json::Value, depth: usize) -> String {
+    let Some(obj) = schema.as_object() else {
+        return "any".to_string();
+    };
+
+    if let Some(reference) = obj.get("$ref").and_then(|r| r.as_str()) {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or(reference)
+            .to_string();
+    }
+    if let Some(value) = obj.get("const") {
+        return value.to_string();
+    }
+    if let Some(values) = obj.get("enum").and_then(|e| e.as_array()) {
+        let mut variants: Vec<String> = values
+            .iter()
+            .take(SIGNATURE_MAX_ENUM)
+            .map(|v| v.to_string())
+            .collect();
+        if values.len() > SIGNATURE_MAX_ENUM {
+            variants.push("...".to_string());
+        }
+        return variants.join(" | ");
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(variants) = obj.get(key).and_then(|v| v.as_array()) {
+            return variants
+                .iter()
+                .map(|v| type_expr(v, depth))
+                .collect::<Vec<_>>()
+                .join(" | ");
+        }
+    }
+
+    let named = |name: &str| -> String {
+        match name {
+            "integer" => "int".to_string(),
+            "boolean" => "bool".to_string(),
+            "array" => {
+                let item = obj
+                    .get("items")
+                    .map(|items| type_expr(items, depth))
+                    .unwrap_or_else(|| "any".to_string());
+                if item.contains(' ') {
+                    format!("({})[]", item)
+                } else {
+                    format!("{}[]", item)
+                }
+            }
+            "object" => match obj.get("properties").and_then(|p| p.as_object()) {
+                Some(properties) if !properties.is_empty() && depth < SIGNATURE_MAX_DEPTH => {
+                    let required: Vec<&str> = obj
+                        .get("required")
+                        .and_then(|r| r.as_array())
+                        .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+                        .unwrap_or_default();
+                    let fields: Vec<String> = properties
+                        .iter()
+                        .map(|(name, prop)| {
+                            let optional = if required.contains(&name.as_str()) {
+                                ""
+                            } else {
+                                "?"
+                            };
+                            format!("{}{}: {}", name, optional, type_expr(prop, depth + 1))
+                        })
+                        .collect();
+                    format!("{{{}}}", fields.join(", "))
+                }
+                _ => "object".to_string(),
+            },
+            other => other.to_string(),
+        }
+    };
+
+    match obj.get("type") {
+        Some(serde_json::Value::String(name)) => named(name),
+        Some(serde_json::Value::Array(names)) => names
+            .iter()
+            .filter_map(|n| n.as_str())
+            .map(named)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ if obj.contains_key("properties") => named("object"),
+        _ => "any".to_string(),
+    }
+}
@@ -1,11 +1,18 @@
 //! Persistence layer for registry and skill state
 //!
 //! Provides SQLite-based persistence for:
-//! - Callable registry (tools and skills)
+//! - Callable registry (tools and skills), with an FTS5 search index
 //! - Skill metadata and content
 //! - Execution history
 //! - Server state
+//! - Consent requests
+//! - Rate limit counters
+//! - Cached embedding vectors for semantic search
+//! - Search click-through feedback
 
+use crate::core::consent::{ConsentRequest, ConsentStatus};
+use crate::core::policy::ConsentLevel;
+use crate::core::registry::{Registry, RegistryEvent};
 use crate::core::{
     CallableId, CallableKind, CallableRecord, CoreError, CostHints, RiskTier, SchemaDigest,
 };
@@ -15,8 +22,9 @@ use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
 use sqlx::Row;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 use thiserror::Error;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 #[derive(Error, Debug)]
 pub enum PersistenceError {
@@ -35,6 +43,26 @@ pub enum PersistenceError {
 
 pub type Result<T> = std::result::Result<T, PersistenceError>;
 
+/// Execution outcomes of a callable picked from search results for a query term
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchFeedback {
+    /// Normalized query term; empty for the callable's totals
+    pub term: String,
+    pub callable_id: String,
+    pub successes: i64,
+    pub failures: i64,
+}
+
+/// A cached embedding vector, valid while both digests still match the callable
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedEmbedding {
+    pub callable_id: String,
+    pub schema_digest: String,
+    /// Digest of the text that was embedded
+    pub content_digest: String,
+    pub vector: Vec<f32>,
+}
+
 /// Persistence layer for skills.rs
 pub struct PersistenceLayer {
     pool: SqlitePool,
@@ -95,6 +123,7 @@ impl PersistenceLayer {
                 additional_files TEXT NOT NULL,
                 cost_hints TEXT NOT NULL,
                 risk_tier TEXT NOT NULL,
+                annotations TEXT,
                 last_seen INTEGER NOT NULL,
                 created_at INTEGER NOT NULL,
                 updated_at INTEGER NOT NULL
@@ -120,6 +149,12 @@ impl PersistenceLayer {
         .execute(&self.pool)
         .await?;
 
+        // Columns added after the table was first released
+        self.add_column_if_missing("callables", "annotations", "TEXT")
+            .await?;
+
+        self.initialize_search_index().await?;
+
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS execution_history (
@@ -167,10 +202,202 @@ impl PersistenceLayer {
         .execute(&self.pool)
         .await?;
 
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS consent_requests (
+                id TEXT PRIMARY KEY,
+                callable_id TEXT NOT NULL,
+                fq_name TEXT NOT NULL,
+                args_digest TEXT NOT NULL,
+                level TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_consent_status ON consent_requests(status)
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+                key TEXT PRIMARY KEY,
+                tokens REAL NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS rate_limit_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                key TEXT NOT NULL,
+                at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_rate_limit_events_at ON rate_limit_events(at)
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS search_feedback (
+                term TEXT NOT NULL,
+                callable_id TEXT NOT NULL,
+                successes INTEGER NOT NULL DEFAULT 0,
+                failures INTEGER NOT NULL DEFAULT 0,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (term, callable_id)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS callable_embeddings (
+                callable_id TEXT NOT NULL,
+                model TEXT NOT NULL,
+                schema_digest TEXT NOT NULL,
+                content_digest TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (callable_id, model)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
         debug!("Database schema initialized");
         Ok(())
     }
 
+    /// Add a column to a table created by an older version
+    async fn add_column_if_missing(&self, table: &str, column: &str, decl: &str) -> Result<()> {
+        let columns: Vec<String> =
+            sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                .fetch_all(&self.pool)
+                .await?;
+        if !columns.iter().any(|c| c == column) {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, decl
+            ))
+            .execute(&self.pool)
+            .await?;
+            info!("Added column {}.{}", table, column);
+        }
+        Ok(())
+    }
+
+    /// Create the `callables_fts` index and the triggers that keep it in sync
+    ///
+    /// The index stores its own copy of the searchable text (tags and input
+    /// parameter names are flattened from JSON by the triggers), keyed by the
+    /// `callables` rowid. An index that has drifted from the table, e.g. in a
+    /// database created before the index existed, is rebuilt.
+    async fn initialize_search_index(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS callables_fts USING fts5(
+                name, fq_name, title, description, tags, params,
+                tokenize = 'unicode61',
+                prefix = '2 3'
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS callables_fts_insert AFTER INSERT ON callables BEGIN
+                INSERT INTO callables_fts (rowid, name, fq_name, title, description, tags, params)
+                {};
+            END
+            "#,
+            FTS_ROW_SELECT.replace("{row}", "new")
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS callables_fts_delete AFTER DELETE ON callables BEGIN
+                DELETE FROM callables_fts WHERE rowid = old.rowid;
+            END
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS callables_fts_update AFTER UPDATE ON callables BEGIN
+                DELETE FROM callables_fts WHERE rowid = old.rowid;
+                INSERT INTO callables_fts (rowid, name, fq_name, title, description, tags, params)
+                {};
+            END
+            "#,
+            FTS_ROW_SELECT.replace("{row}", "new")
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        let counts = sqlx::query(
+            "SELECT (SELECT COUNT(*) FROM callables) AS rows, (SELECT COUNT(*) FROM callables_fts) AS indexed",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if counts.get::<i64, _>("rows") != counts.get::<i64, _>("indexed") {
+            self.rebuild_search_index().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Repopulate `callables_fts` from the `callables` table
+    pub async fn rebuild_search_index(&self) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM callables_fts")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!(
+            "INSERT INTO callables_fts (rowid, name, fq_name, title, description, tags, params) {} FROM callables AS c",
+            FTS_ROW_SELECT.replace("{row}", "c")
+        ))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        info!("Rebuilt callable search index");
+        Ok(())
+    }
+
     /// Save a callable record
     pub async fn save_callable(&self, record: &CallableRecord) -> Result<()> {
         let now = Utc::now().timestamp();
@@ -187,20 +414,46 @@ impl PersistenceLayer {
         let bundled_tools_json = serde_json::to_string(&record.bundled_tools)?;
         let additional_files_json = serde_json::to_string(&record.additional_files)?;
         let cost_hints_json = serde_json::to_string(&record.cost_hints)?;
+        let annotations_json = record
+            .annotations
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
 
         sqlx::query(
             r#"
-            INSERT OR REPLACE INTO callables (
+            INSERT INTO callables (
                 id, kind, fq_name, name, title, description, tags,
                 input_schema, output_schema, schema_digest,
                 server_alias, upstream_tool_name, skill_version, uses_tools,
                 skill_directory, bundled_tools, additional_files,
-                cost_hints, risk_tier, last_seen, created_at, updated_at
+                cost_hints, risk_tier, annotations, last_seen, created_at, updated_at
             ) VALUES (
                 ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
-                COALESCE((SELECT created_at FROM callables WHERE id = ?1), ?21),
-                ?21
+                ?21, ?22, ?22
             )
+            ON CONFLICT(id) DO UPDATE SET
+                kind = excluded.kind,
+                fq_name = excluded.fq_name,
+                name = excluded.name,
+                title = excluded.title,
+                description = excluded.description,
+                tags = excluded.tags,
+                input_schema = excluded.input_schema,
+                output_schema = excluded.output_schema,
+                schema_digest = excluded.schema_digest,
+                server_alias = excluded.server_alias,
+                upstream_tool_name = excluded.upstream_tool_name,
+                skill_version = excluded.skill_version,
+                uses_tools = excluded.uses_tools,
+                skill_directory = excluded.skill_directory,
+                bundled_tools = excluded.bundled_tools,
+                additional_files = excluded.additional_files,
+                cost_hints = excluded.cost_hints,
+                risk_tier = excluded.risk_tier,
+                annotations = excluded.annotations,
+                last_seen = excluded.last_seen,
+                updated_at = excluded.updated_at
             "#,
         )
         .bind(record.id.as_str())
@@ -222,6 +475,7 @@ impl PersistenceLayer {
         .bind(additional_files_json)
         .bind(cost_hints_json)
         .bind(record.risk_tier.to_string())
+        .bind(annotations_json)
         .bind(record.last_seen.timestamp())
         .bind(now)
         .execute(&self.pool)
@@ -276,6 +530,59 @@ impl PersistenceLayer {
             .collect()
     }
 
+    /// Load cached upstream tools for the given servers
+    pub async fn load_tools_for_servers(&self, aliases: &[String]) -> Result<Vec<CallableRecord>> {
+        if aliases.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+            "SELECT * FROM callables WHERE kind = 'Tool' AND server_alias IN (",
+        );
+        let mut separated = query.separated(", ");
+        for alias in aliases {
+            separated.push_bind(alias);
+        }
+        query.push(") ORDER BY name");
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        rows.into_iter()
+            .map(|row| self.row_to_callable(row))
+            .collect()
+    }
+
+    /// Delete cached tools of servers not in `aliases`, returning how many were removed
+    pub async fn prune_tools_except(&self, aliases: &[String]) -> Result<u64> {
+        let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+            "DELETE FROM callables WHERE kind = 'Tool' AND server_alias IS NOT NULL",
+        );
+        if !aliases.is_empty() {
+            query.push(" AND server_alias NOT IN (");
+            let mut separated = query.separated(", ");
+            for alias in aliases {
+                separated.push_bind(alias);
+            }
+            query.push(")");
+        }
+
+        let removed = query.build().execute(&self.pool).await?.rows_affected();
+        if removed > 0 {
+            info!("Pruned {} cached tools of unconfigured servers", removed);
+        }
+        Ok(removed)
+    }
+
+    /// Hydrate a registry with cached tools for the given servers
+    ///
+    /// Tools cached for any other server are deleted, so servers dropped from
+    /// the config stop matching searches. The rest are marked stale until
+    /// their server connects.
+    pub async fn warm_start(&self, registry: &Registry, aliases: &[String]) -> Result<usize> {
+        self.prune_tools_except(aliases).await?;
+        let records = self.load_tools_for_servers(aliases).await?;
+        Ok(registry.hydrate(records))
+    }
+
     /// Delete a callable
     pub async fn delete_callable(&self, id: &CallableId) -> Result<()> {
         sqlx::query(
@@ -304,6 +611,7 @@ impl PersistenceLayer {
         let additional_files_json: String = row.get("additional_files");
         let cost_hints_json: String = row.get("cost_hints");
         let risk_tier_str: String = row.get("risk_tier");
+        let annotations_json: Option<String> = row.get("annotations");
         let last_seen_ts: i64 = row.get("last_seen");
 
         let kind = match kind_str.as_str() {
@@ -330,6 +638,9 @@ impl PersistenceLayer {
         let risk_tier: RiskTier = risk_tier_str
             .parse()
             .map_err(|e: CoreError| PersistenceError::InvalidData(e.to_string()))?;
+        let annotations = annotations_json
+            .map(|s| serde_json::from_str(&s))
+            .transpose()?;
 
         let skill_directory: Option<String> = row.get("skill_directory");
 
@@ -353,6 +664,7 @@ impl PersistenceLayer {
             additional_files,
             cost_hints,
             risk_tier,
+            annotations,
             last_seen: DateTime::from_timestamp(last_seen_ts, 0)
                 .ok_or_else(|| PersistenceError::InvalidData("Invalid timestamp".to_string()))?,
             sandbox_config: None,
@@ -420,33 +732,61 @@ impl PersistenceLayer {
         .fetch_all(&self.pool)
         .await?;
 
-        rows.into_iter()
-            .map(|row| {
-                let started_at_ts: i64 = row.get("started_at");
-                let completed_at_ts: Option<i64> = row.get("completed_at");
+        rows.iter().map(row_to_execution).collect()
+    }
 
-                Ok(ExecutionRecord {
-                    id: row.get("id"),
-                    execution_id: row.get("execution_id"),
-                    callable_id: row.get("callable_id"),
-                    arguments: serde_json::from_str(&row.get::<String, _>("arguments"))?,
-                    result: row
-                        .get::<Option<String>, _>("result")
-                        .map(|s| serde_json::from_str(&s))
-                        .transpose()?,
-                    is_error: row.get::<i32, _>("is_error") != 0,
-                    duration_ms: row.get::<Option<i64>, _>("duration_ms").map(|d| d as u64),
-                    started_at: DateTime::from_timestamp(started_at_ts, 0).ok_or_else(|| {
-                        PersistenceError::InvalidData("Invalid timestamp".to_string())
-                    })?,
-                    completed_at: completed_at_ts.and_then(|ts| DateTime::from_timestamp(ts, 0)),
-                    trace: row
-                        .get::<Option<String>, _>("trace")
-                        .map(|s| serde_json::from_str(&s))
-                        .transpose()?,
-                })
-            })
-            .collect()
+    /// Query execution history with filters, newest first
+    pub async fn query_execution_history(
+        &self,
+        filter: &HistoryFilter,
+    ) -> Result<Vec<ExecutionRecord>> {
+        let mut query =
+            sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT * FROM execution_history WHERE 1 = 1");
+
+        if let Some(callable) = &filter.callable {
+            match callable_patterns(callable) {
+                Some(patterns) => {
+                    query.push(" AND (");
+                    for (i, pattern) in patterns.into_iter().enumerate() {
+                        if i > 0 {
+                            query.push(" OR ");
+                        }
+                        query
+                            .push("callable_id LIKE ")
+                            .push_bind(pattern)
+                            .push(" ESCAPE '\\'");
+                    }
+                    query.push(")");
+                }
+                None => {
+                    query
+                        .push(" AND callable_id = ")
+                        .push_bind(callable.clone());
+                }
+            }
+        }
+        if let Some(since) = filter.since {
+            query
+                .push(" AND started_at >= ")
+                .push_bind(since.timestamp());
+        }
+        if let Some(until) = filter.until {
+            query
+                .push(" AND started_at <= ")
+                .push_bind(until.timestamp());
+        }
+        if let Some(status) = filter.status {
+            query
+                .push(" AND is_error = ")
+                .push_bind((status == ExecutionStatus::Error) as i32);
+        }
+
+        query
+            .push(" ORDER BY started_at DESC, id DESC LIMIT ")
+            .push_bind(filter.limit);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        rows.iter().map(row_to_execution).collect()
     }
 
     /// Save server state
@@ -485,6 +825,301 @@ impl PersistenceLayer {
         Ok(serde_json::from_str(&value_json)?)
     }
 
+    /// Save a new consent request
+    pub async fn save_consent_request(&self, request: &ConsentRequest) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO consent_requests (
+                id, callable_id, fq_name, args_digest, level, reason, status,
+                created_at, expires_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?8)
+            "#,
+        )
+        .bind(&request.id)
+        .bind(request.callable_id.as_str())
+        .bind(&request.fq_name)
+        .bind(&request.args_digest)
+        .bind(request.level.to_string())
+        .bind(&request.reason)
+        .bind(request.status.as_str())
+        .bind(request.created_at.timestamp())
+        .bind(request.expires_at.timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        debug!("Saved consent request: {}", request.id);
+        Ok(())
+    }
+
+    /// Load a consent request by ID
+    pub async fn load_consent_request(&self, id: &str) -> Result<ConsentRequest> {
+        let row = sqlx::query("SELECT * FROM consent_requests WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| PersistenceError::NotFound(format!("consent request {}", id)))?;
+
+        row_to_consent_request(&row)
+    }
+
+    /// Find an unexpired pending request for the same callable and arguments
+    pub async fn find_pending_consent_request(
+        &self,
+        callable_id: &CallableId,
+        args_digest: &str,
+    ) -> Result<Option<ConsentRequest>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM consent_requests
+            WHERE callable_id = ?1 AND args_digest = ?2 AND status = ?3 AND expires_at > ?4
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(callable_id.as_str())
+        .bind(args_digest)
+        .bind(ConsentStatus::Pending.as_str())
+        .bind(Utc::now().timestamp())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_consent_request).transpose()
+    }
+
+    /// List consent requests, newest first, optionally filtered by status
+    pub async fn list_consent_requests(
+        &self,
+        status: Option<ConsentStatus>,
+    ) -> Result<Vec<ConsentRequest>> {
+        let mut query = sqlx::QueryBuilder::new("SELECT * FROM consent_requests");
+        if let Some(status) = status {
+            query.push(" WHERE status = ").push_bind(status.as_str());
+        }
+        query.push(" ORDER BY created_at DESC");
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        rows.iter().map(row_to_consent_request).collect()
+    }
+
+    /// Move a consent request from one status to another.
+    ///
+    /// The update only applies while the request is still in `from`, so
+    /// concurrent transitions (e.g. two execs racing to consume the same
+    /// grant) succeed at most once. Returns whether the transition happened.
+    pub async fn transition_consent_request(
+        &self,
+        id: &str,
+        from: ConsentStatus,
+        to: ConsentStatus,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE consent_requests
+            SET status = ?3, expires_at = COALESCE(?4, expires_at), updated_at = ?5
+            WHERE id = ?1 AND status = ?2
+            "#,
+        )
+        .bind(id)
+        .bind(from.as_str())
+        .bind(to.as_str())
+        .bind(expires_at.map(|t| t.timestamp()))
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Save token bucket levels (`key`, tokens, updated-at in ms)
+    pub async fn save_rate_buckets(&self, buckets: &[(String, f64, i64)]) -> Result<()> {
+        for (key, tokens, updated_ms) in buckets {
+            sqlx::query(
+                r#"
+                INSERT INTO rate_limit_buckets (key, tokens, updated_at)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT(key) DO UPDATE SET
+                    tokens = excluded.tokens,
+                    updated_at = excluded.updated_at
+                "#,
+            )
+            .bind(key)
+            .bind(tokens)
+            .bind(updated_ms)
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Load all token bucket levels
+    pub async fn load_rate_buckets(&self) -> Result<Vec<(String, f64, i64)>> {
+        let rows = sqlx::query("SELECT key, tokens, updated_at FROM rate_limit_buckets")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("key"), row.get("tokens"), row.get("updated_at")))
+            .collect())
+    }
+
+    /// Record one quota event per key at the given time (ms)
+    pub async fn record_rate_events(&self, keys: &[String], at_ms: i64) -> Result<()> {
+        for key in keys {
+            sqlx::query("INSERT INTO rate_limit_events (key, at) VALUES (?1, ?2)")
+                .bind(key)
+                .bind(at_ms)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Load quota events newer than `since_ms`, oldest first
+    pub async fn load_rate_events(&self, since_ms: i64) -> Result<Vec<(String, i64)>> {
+        let rows =
+            sqlx::query("SELECT key, at FROM rate_limit_events WHERE at > ?1 ORDER BY at ASC")
+                .bind(since_ms)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("key"), row.get("at")))
+            .collect())
+    }
+
+    /// Delete quota events at or before `before_ms`
+    pub async fn prune_rate_events(&self, before_ms: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM rate_limit_events WHERE at <= ?1")
+            .bind(before_ms)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Count an execution outcome for a callable under each query term
+    ///
+    /// The empty term holds the callable's totals across all queries.
+    pub async fn record_search_feedback(
+        &self,
+        callable_id: &str,
+        terms: &[String],
+        success: bool,
+    ) -> Result<()> {
+        let (successes, failures) = if success { (1, 0) } else { (0, 1) };
+        for term in std::iter::once("").chain(terms.iter().map(String::as_str)) {
+            sqlx::query(
+                r#"
+                INSERT INTO search_feedback (term, callable_id, successes, failures, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(term, callable_id) DO UPDATE SET
+                    successes = successes + excluded.successes,
+                    failures = failures + excluded.failures,
+                    updated_at = excluded.updated_at
+                "#,
+            )
+            .bind(term)
+            .bind(callable_id)
+            .bind(successes)
+            .bind(failures)
+            .bind(Utc::now().timestamp())
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Load feedback counts for the given terms plus per-callable totals
+    pub async fn load_search_feedback(&self, terms: &[String]) -> Result<Vec<SearchFeedback>> {
+        let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+            "SELECT term, callable_id, successes, failures FROM search_feedback WHERE term IN (",
+        );
+        let mut separated = query.separated(", ");
+        separated.push_bind("");
+        for term in terms {
+            separated.push_bind(term);
+        }
+        query.push(")");
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(|row| SearchFeedback {
+                term: row.get("term"),
+                callable_id: row.get("callable_id"),
+                successes: row.get("successes"),
+                failures: row.get("failures"),
+            })
+            .collect())
+    }
+
+    /// Cache embedding vectors computed by `model`
+    pub async fn save_embeddings(&self, model: &str, embeddings: &[CachedEmbedding]) -> Result<()> {
+        for embedding in embeddings {
+            let vector: Vec<u8> = embedding
+                .vector
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect();
+            sqlx::query(
+                r#"
+                INSERT INTO callable_embeddings
+                    (callable_id, model, schema_digest, content_digest, vector, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT(callable_id, model) DO UPDATE SET
+                    schema_digest = excluded.schema_digest,
+                    content_digest = excluded.content_digest,
+                    vector = excluded.vector,
+                    updated_at = excluded.updated_at
+                "#,
+            )
+            .bind(&embedding.callable_id)
+            .bind(model)
+            .bind(&embedding.schema_digest)
+            .bind(&embedding.content_digest)
+            .bind(vector)
+            .bind(Utc::now().timestamp())
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Load all embedding vectors cached for `model`
+    pub async fn load_embeddings(&self, model: &str) -> Result<Vec<CachedEmbedding>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT callable_id, schema_digest, content_digest, vector
+            FROM callable_embeddings WHERE model = ?1
+            "#,
+        )
+        .bind(model)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let bytes: Vec<u8> = row.get("vector");
+                if bytes.len() % 4 != 0 {
+                    return Err(PersistenceError::InvalidData(
+                        "Embedding vector is not a whole number of f32 values".to_string(),
+                    ));
+                }
+                Ok(CachedEmbedding {
+                    callable_id: row.get("callable_id"),
+                    schema_digest: row.get("schema_digest"),
+                    content_digest: row.get("content_digest"),
+                    vector: bytes
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
     /// Prune old execution history
     pub async fn prune_execution_history(&self, older_than_days: i64) -> Result<u64> {
         let cutoff = Utc::now().timestamp() - (older_than_days * 86400);
@@ -536,53 +1171,310 @@ impl PersistenceLayer {
         self.pool.close().await;
         info!("Persistence layer closed");
     }
-    /// Search callables using FTS5
+
+    /// Search callables using the FTS5 index
+    ///
+    /// Every word of `query` is matched on its own (OR), words of three or
+    /// more characters also as prefixes. Results are ranked by BM25 with
+    /// name-heavy column weights and returned as `(id, score)`, best first,
+    /// where higher scores are better.
     pub async fn search_callables(
         &self,
         query: &str,
         kind_filter: Option<&str>,
         limit: i64,
-    ) -> Result<Vec<String>> {
-        // Build the FTS query
-        let fts_query = query.split_whitespace().collect::<Vec<_>>().join(" OR ");
-
-        let rows = if let Some(kind) = kind_filter {
-            sqlx::query(
-                r#"
-                SELECT c.id FROM callables c
-                JOIN callables_fts fts ON c.rowid = fts.rowid
-                WHERE callables_fts MATCH ?1 AND c.kind = ?2
-                ORDER BY rank
-                LIMIT ?3
-                "#,
-            )
-            .bind(&fts_query)
-            .bind(kind)
-            .bind(limit)
-            .fetch_all(&self.pool)
-            .await?
-        } else {
-            sqlx::query(
-                r#"
-                SELECT c.id FROM callables c
-                JOIN callables_fts fts ON c.rowid = fts.rowid
-                WHERE callables_fts MATCH ?1
-                ORDER BY rank
-                LIMIT ?2
-                "#,
-            )
-            .bind(&fts_query)
-            .bind(limit)
-            .fetch_all(&self.pool)
-            .await?
-        };
+    ) -> Result<Vec<(String, f64)>> {
+        self.search_callables_page(query, kind_filter, 0, limit)
+            .await
+    }
 
-        rows.into_iter()
-            .map(|row| {
-                let id: String = row.get("id");
-                Ok(id)
+    /// Like [`search_callables`](Self::search_callables), skipping the first `offset` hits
+    ///
+    /// Ties are broken by id, so consecutive pages neither overlap nor skip.
+    pub async fn search_callables_page(
+        &self,
+        query: &str,
+        kind_filter: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<(String, f64)>> {
+        let fts_query = query
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .map(|term| {
+                let term = term.to_lowercase();
+                if term.chars().count() >= 3 {
+                    format!("\"{}\"*", term)
+                } else {
+                    format!("\"{}\"", term)
+                }
             })
-            .collect()
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        if fts_query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut sql = sqlx::QueryBuilder::new(
+            "SELECT c.id AS id, -bm25(callables_fts, 3.0, 2.5, 2.0, 1.0, 1.5, 1.5) AS score \
+             FROM callables_fts JOIN callables c ON c.rowid = callables_fts.rowid \
+             WHERE callables_fts MATCH ",
+        );
+        sql.push_bind(fts_query);
+        if let Some(kind) = kind_filter {
+            sql.push(" AND c.kind = ").push_bind(kind.to_string());
+        }
+        sql.push(" ORDER BY score DESC, c.id LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows = sql.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("id"), row.get("score")))
+            .collect())
+    }
+}
+
+/// Searchable columns for one `callables` row, as used by the FTS triggers
+const FTS_ROW_SELECT: &str = r#"SELECT {row}.rowid, {row}.name, {row}.fq_name,
+                    COALESCE({row}.title, ''), COALESCE({row}.description, ''),
+                    COALESCE((SELECT group_concat(value, ' ') FROM json_each({row}.tags)), ''),
+                    COALESCE((SELECT group_concat(key, ' ')
+                              FROM json_each({row}.input_schema, '$.properties')), '')"#;
+
+fn row_to_execution(row: &sqlx::sqlite::SqliteRow) -> Result<ExecutionRecord> {
+    let started_at_ts: i64 = row.get("started_at");
+    let completed_at_ts: Option<i64> = row.get("completed_at");
+
+    Ok(ExecutionRecord {
+        id: row.get("id"),
+        execution_id: row.get("execution_id"),
+        callable_id: row.get("callable_id"),
+        arguments: serde_json::from_str(&row.get::<String, _>("arguments"))?,
+        result: row
+            .get::<Option<String>, _>("result")
+            .map(|s| serde_json::from_str(&s))
+            .transpose()?,
+        is_error: row.get::<i32, _>("is_error") != 0,
+        duration_ms: row.get::<Option<i64>, _>("duration_ms").map(|d| d as u64),
+        started_at: DateTime::from_timestamp(started_at_ts, 0)
+            .ok_or_else(|| PersistenceError::InvalidData("Invalid timestamp".to_string()))?,
+        completed_at: completed_at_ts.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+        trace: row
+            .get::<Option<String>, _>("trace")
+            .map(|s| serde_json::from_str(&s))
+            .transpose()?,
+    })
+}
+
+fn row_to_consent_request(row: &sqlx::sqlite::SqliteRow) -> Result<ConsentRequest> {
+    let timestamp = |column: &str| {
+        DateTime::from_timestamp(row.get::<i64, _>(column), 0)
+            .ok_or_else(|| PersistenceError::InvalidData("Invalid timestamp".to_string()))
+    };
+
+    Ok(ConsentRequest {
+        id: row.get("id"),
+        callable_id: CallableId::from(row.get::<String, _>("callable_id")),
+        fq_name: row.get("fq_name"),
+        args_digest: row.get("args_digest"),
+        level: ConsentLevel::from_str(&row.get::<String, _>("level")).unwrap_or(ConsentLevel::None),
+        reason: row.get("reason"),
+        status: ConsentStatus::from_str(&row.get::<String, _>("status"))
+            .map_err(PersistenceError::InvalidData)?,
+        created_at: timestamp("created_at")?,
+        expires_at: timestamp("expires_at")?,
+    })
+}
+
+/// LIKE patterns for a human-friendly callable reference, or `None` for an exact id
+///
+/// Accepts `server.tool`, `server/tool`, `skill@version`, or a bare tool or skill name.
+fn callable_patterns(callable: &str) -> Option<Vec<String>> {
+    if callable.starts_with("tool:") || callable.starts_with("skill:") {
+        return None;
+    }
+
+    let escape = |s: &str| {
+        s.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    };
+
+    if callable.contains('@') {
+        return Some(vec![format!("skill:{}", escape(callable))]);
+    }
+
+    Some(match callable.split_once(['.', '/']) {
+        Some((server, tool)) => vec![format!("tool:srv:{}::{}::%", escape(server), escape(tool))],
+        None => vec![
+            format!("tool:srv:%::{}::%", escape(callable)),
+            format!("skill:{}@%", escape(callable)),
+        ],
+    })
+}
+
+/// Outcome filter for execution history queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionStatus {
+    Success,
+    Error,
+}
+
+impl FromStr for ExecutionStatus {
+    type Err = PersistenceError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "success" | "ok" => Ok(ExecutionStatus::Success),
+            "error" | "failed" => Ok(ExecutionStatus::Error),
+            _ => Err(PersistenceError::InvalidData(format!(
+                "Invalid status '{}': expected success or error",
+                s
+            ))),
+        }
+    }
+}
+
+/// Filters for [`PersistenceLayer::query_execution_history`]
+#[derive(Debug, Clone)]
+pub struct HistoryFilter {
+    /// Callable id, `server.tool`, or bare tool/skill name
+    pub callable: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub status: Option<ExecutionStatus>,
+    pub limit: i64,
+}
+
+impl Default for HistoryFilter {
+    fn default() -> Self {
+        HistoryFilter {
+            callable: None,
+            since: None,
+            until: None,
+            status: None,
+            limit: 50,
+        }
+    }
+}
+
+/// Parse a history time bound: RFC 3339, `YYYY-MM-DD`, or a relative age like `30m`, `24h`, `7d`
+pub fn parse_history_time(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let invalid = || {
+        PersistenceError::InvalidData(format!(
+            "Invalid time '{}': expected RFC 3339, YYYY-MM-DD, or an age like 24h or 7d",
+            value
+        ))
+    };
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let age = match unit {
+        "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        "w" => chrono::Duration::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+
+    Ok(Utc::now() - age)
+}
+
+/// Argument keys whose values are never written to execution history
+const SENSITIVE_KEYS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "authorization",
+    "auth",
+    "credential",
+    "credentials",
+    "private_key",
+    "cookie",
+    "session",
+];
+
+/// Placeholder stored in place of sensitive argument values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Replace values of sensitive-looking keys with [`REDACTED`], recursively
+pub fn redact_arguments(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, v)| {
+                    let normalized = key.to_lowercase().replace('-', "_");
+                    let sensitive = SENSITIVE_KEYS
+                        .iter()
+                        .any(|k| normalized == *k || normalized.ends_with(&format!("_{}", k)));
+                    let v = if sensitive {
+                        serde_json::Value::String(REDACTED.to_string())
+                    } else {
+                        redact_arguments(v)
+                    };
+                    (key.clone(), v)
+                })
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(redact_arguments).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// Write registry changes through to SQLite
+///
+/// Events are applied in order; the task ends when the registry (and every
+/// clone of it) is dropped.
+pub fn spawn_registry_sync(
+    persistence: Arc<PersistenceLayer>,
+    registry: &Registry,
+) -> tokio::task::JoinHandle<()> {
+    let mut events = registry.subscribe();
+
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            let outcome = match &event {
+                RegistryEvent::Upserted(record) => persistence.save_callable(record).await,
+                RegistryEvent::Removed(id) => persistence.delete_callable(id).await,
+            };
+            if let Err(e) = outcome {
+                warn!(
+                    "Failed to persist registry change {:?}: {}",
+                    event_id(&event),
+                    e
+                );
+            }
+        }
+        debug!("Registry sync stopped");
+    })
+}
+
+fn event_id(event: &RegistryEvent) -> &str {
+    match event {
+        RegistryEvent::Upserted(record) => record.id.as_str(),
+        RegistryEvent::Removed(id) => id.as_str(),
     }
 }
 
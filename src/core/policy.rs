@@ -3,14 +3,20 @@
 //! Enforces authorization policies for callable execution:
 //! - Risk tier gating (consent requirements)
 //! - Allowlist/denylist filtering
+//! - Argument-aware rules (JSON pointer + glob/regex/range conditions)
 //! - Server trust levels
 //! - Resource limits and quotas
 
+use crate::core::limits::{RateLimitRule, RateLimiter};
+use crate::core::persistence::{self, PersistenceLayer};
 use crate::{CallableRecord, RiskTier};
 use glob::Pattern;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashSet;
 use std::str::FromStr;
+use std::sync::Arc;
 use thiserror::Error;
 use tracing::{debug, warn};
 
@@ -96,6 +102,93 @@ pub struct PolicyConfig {
     /// Deny patterns (glob-style)
     #[serde(default)]
     pub deny_patterns: Vec<String>,
+
+    /// Lifetime of consent requests and approval tokens, in seconds
+    #[serde(default = "default_consent_ttl_secs")]
+    pub consent_ttl_secs: u64,
+
+    /// Rules that match on argument values
+    #[serde(default)]
+    pub argument_rules: Vec<ArgumentRule>,
+
+    /// Token-bucket rate limits and rolling quotas
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimitRule>,
+}
+
+/// What a matching argument rule does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Waive the risk tier's consent requirement for this call
+    Allow,
+    /// Reject the call
+    Deny,
+    /// Require at least `level` consent for this call
+    RequireConsent,
+}
+
+/// Policy rule that inspects a single argument value
+///
+/// All conditions given must hold for the rule to match. A rule with no
+/// conditions matches whenever the value at `path` exists. When the value
+/// is an array, `deny` and `require_consent` rules match if any element
+/// matches, while `allow` rules require every element to match.
+///
+/// ```yaml
+/// argument_rules:
+///   - callable: "filesystem.write_file"
+///     path: /path
+///     glob: "/etc/**"
+///     action: deny
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArgumentRule {
+    /// Callable pattern (glob over the fully qualified name)
+    #[serde(default = "default_rule_callable")]
+    pub callable: String,
+
+    /// JSON pointer into the arguments (e.g. `/path`, `/options/0/name`)
+    pub path: String,
+
+    /// String glob; path-like values are normalized first so `..` cannot escape
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+
+    /// Regular expression matched anywhere in a string value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+
+    /// Inclusive numeric lower bound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+
+    /// Inclusive numeric upper bound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+
+    /// Exact value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+
+    /// Match on presence (`true`) or absence (`false`) of the value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+
+    pub action: RuleAction,
+
+    /// Consent level for `require_consent` (default: user_confirmed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<ConsentLevel>,
+
+    /// Message reported when the rule denies or escalates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+fn default_rule_callable() -> String {
+    "*".to_string()
 }
 
 fn default_risk() -> String {
@@ -110,6 +203,10 @@ fn default_max_exec_ms() -> u64 {
     120000
 }
 
+fn default_consent_ttl_secs() -> u64 {
+    crate::core::consent::DEFAULT_CONSENT_TTL_SECS
+}
+
 impl Default for PolicyConfig {
     fn default() -> Self {
         PolicyConfig {
@@ -125,6 +222,9 @@ impl Default for PolicyConfig {
             max_exec_ms: default_max_exec_ms(),
             allow_patterns: vec!["*".to_string()],
             deny_patterns: vec![],
+            consent_ttl_secs: default_consent_ttl_secs(),
+            argument_rules: vec![],
+            rate_limits: vec![],
         }
     }
 }
@@ -163,6 +263,166 @@ impl AuthorizationResult {
     }
 }
 
+/// Argument rule with its patterns compiled
+struct CompiledRule {
+    rule: ArgumentRule,
+    callable: Pattern,
+    glob: Option<Pattern>,
+    regex: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn compile(index: usize, rule: ArgumentRule) -> Result<Self> {
+        let invalid = |what: &str, e: &dyn std::fmt::Display| {
+            PolicyError::ConfigError(format!(
+                "argument_rules[{}]: invalid {}: {}",
+                index, what, e
+            ))
+        };
+
+        if !rule.path.is_empty() && !rule.path.starts_with('/') {
+            return Err(PolicyError::ConfigError(format!(
+                "argument_rules[{}]: path must be a JSON pointer starting with '/': {}",
+                index, rule.path
+            )));
+        }
+        if rule.level.is_some() && rule.action != RuleAction::RequireConsent {
+            return Err(PolicyError::ConfigError(format!(
+                "argument_rules[{}]: level only applies to require_consent",
+                index
+            )));
+        }
+
+        Ok(CompiledRule {
+            callable: Pattern::new(&rule.callable).map_err(|e| invalid("callable", &e))?,
+            glob: rule
+                .glob
+                .as_deref()
+                .map(Pattern::new)
+                .transpose()
+                .map_err(|e| invalid("glob", &e))?,
+            regex: rule
+                .regex
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| invalid("regex", &e))?,
+            rule,
+        })
+    }
+
+    fn matches(&self, callable: &CallableRecord, arguments: &Value) -> bool {
+        if !self.callable.matches(&callable.fq_name) {
+            return false;
+        }
+
+        let value = arguments.pointer(&self.rule.path);
+        if let Some(exists) = self.rule.exists {
+            if exists != value.is_some() {
+                return false;
+            }
+        }
+        let Some(value) = value else {
+            return self.rule.exists == Some(false);
+        };
+
+        match value {
+            Value::Array(items) if self.has_value_conditions() => {
+                if self.rule.action == RuleAction::Allow {
+                    !items.is_empty() && items.iter().all(|v| self.value_matches(v))
+                } else {
+                    items.iter().any(|v| self.value_matches(v))
+                }
+            }
+            _ => self.value_matches(value),
+        }
+    }
+
+    fn has_value_conditions(&self) -> bool {
+        self.glob.is_some()
+            || self.regex.is_some()
+            || self.rule.min.is_some()
+            || self.rule.max.is_some()
+            || self.rule.equals.is_some()
+    }
+
+    fn value_matches(&self, value: &Value) -> bool {
+        if let Some(expected) = &self.rule.equals {
+            if value != expected {
+                return false;
+            }
+        }
+        if let Some(glob) = &self.glob {
+            match value.as_str() {
+                Some(s) if glob.matches(&normalize_path(s)) => {}
+                _ => return false,
+            }
+        }
+        if let Some(regex) = &self.regex {
+            match value.as_str() {
+                Some(s) if regex.is_match(s) => {}
+                _ => return false,
+            }
+        }
+        if self.rule.min.is_some() || self.rule.max.is_some() {
+            let Some(n) = value.as_f64() else {
+                return false;
+            };
+            if self.rule.min.is_some_and(|min| n < min) || self.rule.max.is_some_and(|max| n > max)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn describe(&self) -> String {
+        self.rule.reason.clone().unwrap_or_else(|| {
+            format!(
+                "argument rule on {} {}",
+                self.rule.callable,
+                if self.rule.path.is_empty() {
+                    "/"
+                } else {
+                    &self.rule.path
+                }
+            )
+        })
+    }
+}
+
+/// Lexically normalize a path-like string (collapse `.`, `..`, and repeated slashes)
+///
+/// Values without a `/` are returned unchanged.
+fn normalize_path(value: &str) -> String {
+    if !value.contains('/') {
+        return value.to_string();
+    }
+
+    let absolute = value.starts_with('/');
+    let mut parts: Vec<&str> = Vec::new();
+    for part in value.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.last().is_some_and(|p| *p != "..") {
+                    parts.pop();
+                } else if !absolute {
+                    parts.push("..");
+                }
+            }
+            other => parts.push(other),
+        }
+    }
+
+    let joined = parts.join("/");
+    if absolute {
+        format!("/{}", joined)
+    } else {
+        joined
+    }
+}
+
 /// Policy engine
 pub struct PolicyEngine {
     config: PolicyConfig,
@@ -171,6 +431,8 @@ pub struct PolicyEngine {
     deny_tags: HashSet<String>,
     allow_patterns: Vec<Pattern>,
     deny_patterns: Vec<Pattern>,
+    argument_rules: Vec<CompiledRule>,
+    limiter: RateLimiter,
 }
 
 impl PolicyEngine {
@@ -197,6 +459,15 @@ impl PolicyEngine {
             .filter_map(|p| Pattern::new(p).ok())
             .collect();
 
+        let argument_rules = config
+            .argument_rules
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, rule)| CompiledRule::compile(i, rule))
+            .collect::<Result<Vec<_>>>()?;
+        let limiter = RateLimiter::new(&config.rate_limits)?;
+
         Ok(PolicyEngine {
             config,
             consent_required_tiers,
@@ -204,6 +475,8 @@ impl PolicyEngine {
             deny_tags,
             allow_patterns,
             deny_patterns,
+            argument_rules,
+            limiter,
         })
     }
 
@@ -218,6 +491,9 @@ impl PolicyEngine {
             max_exec_ms: 300000,
             allow_patterns: vec!["*".to_string()],
             deny_patterns: vec![],
+            consent_ttl_secs: default_consent_ttl_secs(),
+            argument_rules: vec![],
+            rate_limits: vec![],
         };
 
         PolicyEngine::new(config).unwrap()
@@ -227,7 +503,7 @@ impl PolicyEngine {
     pub async fn authorize(
         &self,
         callable: &CallableRecord,
-        _arguments: &serde_json::Value,
+        arguments: &serde_json::Value,
         consent: ConsentLevel,
     ) -> Result<AuthorizationResult> {
         debug!("Authorizing callable: {}", callable.id.as_str());
@@ -283,25 +559,75 @@ impl PolicyEngine {
             }
         }
 
+        // Check argument rules; deny wins, escalations take the highest level
+        let mut waived = false;
+        let mut escalation: Option<(ConsentLevel, String)> = None;
+        for rule in &self.argument_rules {
+            if !rule.matches(callable, arguments) {
+                continue;
+            }
+            match rule.rule.action {
+                RuleAction::Deny => {
+                    warn!(
+                        "Callable {} denied by {}",
+                        callable.fq_name,
+                        rule.describe()
+                    );
+                    return Ok(AuthorizationResult::deny(format!(
+                        "Denied by {}",
+                        rule.describe()
+                    )));
+                }
+                RuleAction::Allow => waived = true,
+                RuleAction::RequireConsent => {
+                    let level = rule.rule.level.unwrap_or(ConsentLevel::UserConfirmed);
+                    if escalation
+                        .as_ref()
+                        .map_or(true, |(current, _)| level > *current)
+                    {
+                        escalation = Some((level, rule.describe()));
+                    }
+                }
+            }
+        }
+
         // Check risk tier and consent
-        if self.consent_required_tiers.contains(&callable.risk_tier) {
-            let required_consent = match callable.risk_tier {
+        let mut required: Option<(ConsentLevel, String)> = None;
+        if self.consent_required_tiers.contains(&callable.risk_tier) && !waived {
+            let tier_consent = match callable.risk_tier {
                 RiskTier::Admin => ConsentLevel::AdminConfirmed,
                 RiskTier::Destructive => ConsentLevel::UserConfirmed,
                 RiskTier::Writes => ConsentLevel::UserConfirmed,
                 _ => ConsentLevel::None,
             };
+            required = Some((
+                tier_consent,
+                format!(
+                    "Risk tier {} requires consent level {}",
+                    callable.risk_tier, tier_consent
+                ),
+            ));
+        }
+        if let Some((level, reason)) = escalation {
+            if required
+                .as_ref()
+                .map_or(true, |(current, _)| level > *current)
+            {
+                required = Some((
+                    level,
+                    format!("{} requires consent level {}", reason, level),
+                ));
+            }
+        }
 
+        if let Some((required_consent, reason)) = required {
             if consent < required_consent {
                 warn!(
                     "Insufficient consent for {}: required {:?}, provided {:?}",
                     callable.fq_name, required_consent, consent
                 );
                 return Ok(AuthorizationResult::deny_with_consent(
-                    format!(
-                        "Risk tier {} requires consent level {}",
-                        callable.risk_tier, required_consent
-                    ),
+                    reason,
                     required_consent,
                 ));
             }
@@ -311,6 +637,24 @@ impl PolicyEngine {
         Ok(AuthorizationResult::allow())
     }
 
+    /// Persist rate limit counters so limits survive restarts
+    pub fn with_persistence(mut self, persistence: Arc<PersistenceLayer>) -> Self {
+        self.limiter = self.limiter.with_persistence(persistence);
+        self
+    }
+
+    /// Load persisted rate limit counters
+    pub async fn restore_usage(&self) -> persistence::Result<usize> {
+        self.limiter.restore().await
+    }
+
+    /// Count a call against the configured rate limits and quotas
+    ///
+    /// `session` is the MCP session id, or [`crate::core::limits::DEFAULT_SESSION`].
+    pub async fn check_rate_limits(&self, callable: &CallableRecord, session: &str) -> Result<()> {
+        self.limiter.acquire(callable, session).await
+    }
+
     /// Check if execution time is within limits
     pub fn check_timeout(&self, requested_ms: Option<u64>) -> Result<u64> {
         let timeout = requested_ms.unwrap_or(self.config.max_exec_ms);
@@ -328,6 +672,11 @@ impl PolicyEngine {
         self.config.max_calls_per_skill
     }
 
+    /// Lifetime of consent requests and approval tokens, in seconds
+    pub fn consent_ttl_secs(&self) -> u64 {
+        self.config.consent_ttl_secs
+    }
+
     /// Check if a server is trusted
     pub fn is_server_trusted(&self, server: &str) -> bool {
         self.trusted_servers.contains(server) || self.trusted_servers.contains("*")
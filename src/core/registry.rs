@@ -6,10 +6,12 @@
 
 use crate::core::{CallableId, CallableKind, CallableRecord, CoreError};
 use dashmap::DashMap;
-use parking_lot::RwLock;
-use std::collections::HashMap;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
 #[derive(Error, Debug)]
@@ -35,6 +37,8 @@ pub enum ServerHealth {
     Connected,
     Degraded,
     Down,
+    /// Tools were loaded from the persistent cache; the server has not connected yet
+    Stale,
 }
 
 /// Server metadata
@@ -57,6 +61,13 @@ pub struct RegistryStats {
     pub stale_servers: Vec<String>,
 }
 
+/// Change to the registry, delivered to subscribers
+#[derive(Debug, Clone)]
+pub enum RegistryEvent {
+    Upserted(Box<CallableRecord>),
+    Removed(CallableId),
+}
+
 /// Thread-safe registry store
 #[derive(Clone)]
 pub struct Registry {
@@ -71,6 +82,12 @@ pub struct Registry {
 
     /// Server metadata
     servers: Arc<RwLock<HashMap<String, ServerInfo>>>,
+
+    /// Change subscribers (e.g. persistence write-through)
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<RegistryEvent>>>>,
+
+    /// Bumped on every change to the callable set
+    generation: Arc<AtomicU64>,
 }
 
 impl Registry {
@@ -80,18 +97,94 @@ impl Registry {
             by_fq_name: Arc::new(DashMap::new()),
             by_server: Arc::new(DashMap::new()),
             servers: Arc::new(RwLock::new(HashMap::new())),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            generation: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Monotonically increasing counter of changes to the callable set
+    ///
+    /// Every insert, replacement, and removal (including hydration and
+    /// `clear`) bumps it, so equal generations mean an unchanged catalog.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Subscribe to registry changes
+    ///
+    /// Every `register` and `remove` after this call is delivered in order.
+    /// Records loaded with [`Registry::hydrate`] are not.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<RegistryEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
+    fn notify(&self, event: RegistryEvent) {
+        let mut subscribers = self.subscribers.lock();
+        if !subscribers.is_empty() {
+            subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Load cached callables without notifying subscribers
+    ///
+    /// Servers that own hydrated tools and are not yet known are marked
+    /// [`ServerHealth::Stale`] until they connect.
+    pub fn hydrate(&self, records: Vec<CallableRecord>) -> usize {
+        let mut per_server: HashMap<String, usize> = HashMap::new();
+        let count = records.len();
+
+        for record in records {
+            if let Some(server) = &record.server_alias {
+                *per_server.entry(server.clone()).or_default() += 1;
+            }
+            self.insert(record);
+        }
+
+        let mut servers = self.servers.write();
+        for (alias, tool_count) in per_server {
+            servers.entry(alias.clone()).or_insert_with(|| ServerInfo {
+                alias,
+                health: ServerHealth::Stale,
+                tool_count,
+                last_refresh: chrono::Utc::now(),
+                tags: vec![],
+            });
+        }
+
+        info!("Hydrated {} cached callables", count);
+        count
+    }
+
+    /// Whether a callable comes from a server that has not reconnected since it was cached
+    pub fn is_stale(&self, record: &CallableRecord) -> bool {
+        record
+            .server_alias
+            .as_ref()
+            .and_then(|alias| self.servers.read().get(alias).map(|s| s.health))
+            == Some(ServerHealth::Stale)
+    }
+
     /// Register a callable (tool or skill)
     pub fn register(&self, record: CallableRecord) -> Result<()> {
+        if self.callables.contains_key(&record.id) {
+            warn!("Duplicate callable ID, replacing: {}", record.id);
+        }
+        let event = RegistryEvent::Upserted(Box::new(record.clone()));
+        self.insert(record);
+        self.notify(event);
+        Ok(())
+    }
+
+    fn insert(&self, record: CallableRecord) {
         let id = record.id.clone();
         let fq_name = record.fq_name.clone();
         let server_alias = record.server_alias.clone();
 
-        // Check for duplicates
-        if self.callables.contains_key(&id) {
-            warn!("Duplicate callable ID, replacing: {}", id);
+        // Replace an existing record, dropping its stale index entries
+        if let Some((_, previous)) = self.callables.remove(&id) {
+            self.unindex(&id, &previous);
         }
 
         // Update secondary indices
@@ -106,9 +199,20 @@ impl Registry {
 
         // Insert main record
         self.callables.insert(id.clone(), record);
+        self.generation.fetch_add(1, Ordering::SeqCst);
 
         debug!("Registered callable: {} ({})", id, fq_name);
-        Ok(())
+    }
+
+    fn unindex(&self, id: &CallableId, record: &CallableRecord) {
+        self.by_fq_name
+            .remove_if(&record.fq_name, |_, current| current == id);
+
+        if let Some(server) = &record.server_alias {
+            if let Some(mut ids) = self.by_server.get_mut(server) {
+                ids.retain(|cid| cid != id);
+            }
+        }
     }
 
     /// Register multiple callables (batch operation)
@@ -143,15 +247,11 @@ impl Registry {
     pub fn remove(&self, id: &CallableId) -> Option<CallableRecord> {
         if let Some((_, record)) = self.callables.remove(id) {
             // Clean up secondary indices
-            self.by_fq_name.remove(&record.fq_name);
-
-            if let Some(server) = &record.server_alias {
-                if let Some(mut ids) = self.by_server.get_mut(server) {
-                    ids.retain(|cid| cid != id);
-                }
-            }
+            self.unindex(id, &record);
+            self.generation.fetch_add(1, Ordering::SeqCst);
 
             debug!("Removed callable: {}", id);
+            self.notify(RegistryEvent::Removed(id.clone()));
             Some(record)
         } else {
             None
@@ -176,6 +276,42 @@ impl Registry {
         count
     }
 
+    /// Replace a server's callables with a fresh listing (for refresh)
+    ///
+    /// The listing is upserted before callables missing from it are removed,
+    /// so subscribers mirroring the registry never see the server emptied
+    /// partway through. Returns how many callables were removed.
+    pub fn replace_server(&self, server_alias: &str, records: Vec<CallableRecord>) -> usize {
+        let listed: HashSet<CallableId> = records.iter().map(|r| r.id.clone()).collect();
+        for record in records {
+            let event = RegistryEvent::Upserted(Box::new(record.clone()));
+            self.insert(record);
+            self.notify(event);
+        }
+
+        let missing: Vec<CallableId> = self
+            .by_server
+            .get(server_alias)
+            .map(|ids| {
+                ids.iter()
+                    .filter(|id| !listed.contains(id))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        for id in &missing {
+            self.remove(id);
+        }
+
+        info!(
+            "Replaced callables of server {}: {} listed, {} removed",
+            server_alias,
+            listed.len(),
+            missing.len()
+        );
+        missing.len()
+    }
+
     /// Get all callables (for indexing)
     pub fn all(&self) -> Vec<CallableRecord> {
         self.callables
@@ -277,6 +413,7 @@ impl Registry {
         self.by_fq_name.clear();
         self.by_server.clear();
         self.servers.write().clear();
+        self.generation.fetch_add(1, Ordering::SeqCst);
         info!("Registry cleared");
     }
 }
@@ -0,0 +1,603 @@
+//! Compact schema renderings for LLM consumption
+//!
+//! Alternatives to raw JSON Schema for the `schema` tool:
+//! - `typescript`: a TypeScript interface, with descriptions as doc comments
+//! - `outline`: an indented YAML-ish outline, `*` marking required fields
+//! - `minimal`: JSON Schema with local `$ref`s inlined and titles, `$schema`,
+//!   and keywords that only restate defaults stripped
+//!
+//! Local `$ref`s are expanded in all three; recursive references are left
+//! as references.
+
+use crate::core::budget::{estimate_tokens, SCHEMA_MAPS, SCHEMA_VALUES};
+use crate::core::{CoreError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// Nesting rendered before falling back to a placeholder
+const MAX_DEPTH: usize = 12;
+
+/// Output format of the `schema` tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaFormat {
+    JsonSchema,
+    Signature,
+    /// `json_schema` and `signature`
+    Both,
+    #[serde(rename = "typescript")]
+    TypeScript,
+    Outline,
+    Minimal,
+}
+
+impl SchemaFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchemaFormat::JsonSchema => "json_schema",
+            SchemaFormat::Signature => "signature",
+            SchemaFormat::Both => "both",
+            SchemaFormat::TypeScript => "typescript",
+            SchemaFormat::Outline => "outline",
+            SchemaFormat::Minimal => "minimal",
+        }
+    }
+
+    /// Whether the format is a text rendering rather than JSON Schema or a signature
+    pub fn is_rendering(&self) -> bool {
+        matches!(
+            self,
+            SchemaFormat::TypeScript | SchemaFormat::Outline | SchemaFormat::Minimal
+        )
+    }
+}
+
+impl fmt::Display for SchemaFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SchemaFormat {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json_schema" | "json" => Ok(SchemaFormat::JsonSchema),
+            "signature" => Ok(SchemaFormat::Signature),
+            "both" => Ok(SchemaFormat::Both),
+            "typescript" | "ts" => Ok(SchemaFormat::TypeScript),
+            "outline" | "yaml" => Ok(SchemaFormat::Outline),
+            "minimal" => Ok(SchemaFormat::Minimal),
+            _ => Err(CoreError::InvalidSchemaFormat(s.to_string())),
+        }
+    }
+}
+
+/// A rendered schema with its size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rendering {
+    pub format: SchemaFormat,
+    pub text: String,
+    pub bytes: usize,
+    /// Estimated with `budget::estimate_tokens`
+    pub tokens: usize,
+}
+
+impl Rendering {
+    /// Render a callable's schemas; `None` for formats that are not renderings
+    ///
+    /// `name` is the callable name, used for TypeScript type names.
+    pub fn new(
+        format: SchemaFormat,
+        input_schema: &Value,
+        output_schema: Option<&Value>,
+        name: &str,
+    ) -> Option<Self> {
+        let text = match format {
+            SchemaFormat::TypeScript => {
+                let type_name = pascal_case(name);
+                let mut text = to_typescript(input_schema, &format!("{}Input", type_name));
+                if let Some(output) = output_schema {
+                    text.push('\n');
+                    text.push_str(&to_typescript(output, &format!("{}Output", type_name)));
+                }
+                text
+            }
+            SchemaFormat::Outline => {
+                let mut text = to_outline(input_schema);
+                if let Some(output) = output_schema {
+                    text.push_str("returns:\n");
+                    for line in to_outline(output).lines() {
+                        text.push_str("  ");
+                        text.push_str(line);
+                        text.push('\n');
+                    }
+                }
+                text
+            }
+            SchemaFormat::Minimal => {
+                let value = match output_schema {
+                    Some(output) => serde_json::json!({
+                        "input": minimal_schema(input_schema),
+                        "output": minimal_schema(output),
+                    }),
+                    None => minimal_schema(input_schema),
+                };
+                value.to_string()
+            }
+            _ => return None,
+        };
+
+        Some(Rendering {
+            format,
+            bytes: text.len(),
+            tokens: estimate_tokens(&text),
+            text,
+        })
+    }
+}
+
+/// Expands local `$ref`s, refusing to re-enter one already being expanded
+struct Refs<'a> {
+    root: &'a Value,
+    active: Vec<&'a str>,
+}
+
+impl<'a> Refs<'a> {
+    fn new(root: &'a Value) -> Self {
+        Refs {
+            root,
+            active: Vec::new(),
+        }
+    }
+
+    /// Call `render` with the schema `node` stands for, or `unresolved` with
+    /// the reference when it is recursive, remote, or dangling
+    fn expand<R>(
+        &mut self,
+        node: &'a Value,
+        render: &mut dyn FnMut(&mut Self, &'a Value) -> R,
+        unresolved: &dyn Fn(&str) -> R,
+    ) -> R {
+        let Some(reference) = node.get("$ref").and_then(|r| r.as_str()) else {
+            return render(self, node);
+        };
+        let target = reference
+            .strip_prefix('#')
+            .and_then(|pointer| self.root.pointer(pointer));
+        match target {
+            Some(target) if !self.active.contains(&reference) && self.active.len() < MAX_DEPTH => {
+                self.active.push(reference);
+                let result = self.expand(target, render, unresolved);
+                self.active.pop();
+                result
+            }
+            _ => unresolved(reference),
+        }
+    }
+}
+
+/// Properties of an object schema: required ones in `required` order, then
+/// the rest by name
+fn ordered_properties(schema: &Value) -> Vec<(&str, &Value, bool)> {
+    let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) else {
+        return Vec::new();
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut ordered: Vec<(&str, &Value, bool)> = required
+        .iter()
+        .filter_map(|name| properties.get_key_value(*name))
+        .map(|(name, prop)| (name.as_str(), prop, true))
+        .collect();
+    ordered.extend(
+        properties
+            .iter()
+            .filter(|(name, _)| !required.contains(&name.as_str()))
+            .map(|(name, prop)| (name.as_str(), prop, false)),
+    );
+    ordered
+}
+
+fn description(schema: &Value) -> Option<&str> {
+    schema
+        .get("description")
+        .and_then(|d| d.as_str())
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+}
+
+/// First line of a description, for one-line comments
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default().trim()
+}
+
+/// `create_issue` / `create-issue` → `CreateIssue`
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Render a schema as a TypeScript interface (or type alias for non-objects)
+pub fn to_typescript(schema: &Value, name: &str) -> String {
+    let mut refs = Refs::new(schema);
+    let body = ts_type(&mut refs, schema, 0);
+
+    let mut out = String::new();
+    if let Some(text) = description(schema) {
+        out.push_str(&format!("/** {} */\n", first_line(text)));
+    }
+    if body.starts_with('{') {
+        out.push_str(&format!("interface {} {}\n", name, body));
+    } else {
+        out.push_str(&format!("type {} = {};\n", name, body));
+    }
+    out
+}
+
+fn ts_type<'a>(refs: &mut Refs<'a>, node: &'a Value, indent: usize) -> String {
+    refs.expand(
+        node,
+        &mut |refs, node| ts_resolved(refs, node, indent),
+        &|reference| format!("unknown /* {} */", reference),
+    )
+}
+
+fn ts_resolved<'a>(refs: &mut Refs<'a>, node: &'a Value, indent: usize) -> String {
+    let Some(obj) = node.as_object() else {
+        // `true` accepts anything, `false` nothing
+        return if node == &Value::Bool(false) {
+            "never"
+        } else {
+            "unknown"
+        }
+        .to_string();
+    };
+
+    if let Some(value) = obj.get("const") {
+        return value.to_string();
+    }
+    if let Some(values) = obj.get("enum").and_then(|e| e.as_array()) {
+        return values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" | ");
+    }
+    for (key, separator) in [("oneOf", " | "), ("anyOf", " | "), ("allOf", " & ")] {
+        if let Some(variants) = obj.get(key).and_then(|v| v.as_array()) {
+            return variants
+                .iter()
+                .map(|v| ts_type(refs, v, indent))
+                .collect::<Vec<_>>()
+                .join(separator);
+        }
+    }
+
+    let types: Vec<&str> = match obj.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(|n| n.as_str()).collect(),
+        _ if obj.contains_key("properties") => vec!["object"],
+        _ if obj.contains_key("items") => vec!["array"],
+        _ => vec![],
+    };
+    if types.is_empty() {
+        return "unknown".to_string();
+    }
+
+    types
+        .into_iter()
+        .map(|name| match name {
+            "string" => "string".to_string(),
+            "integer" | "number" => "number".to_string(),
+            "boolean" => "boolean".to_string(),
+            "null" => "null".to_string(),
+            "array" => {
+                let item = obj
+                    .get("items")
+                    .map(|items| ts_type(refs, items, indent))
+                    .unwrap_or_else(|| "unknown".to_string());
+                if item.contains('\n') || item.contains(' ') {
+                    format!("Array<{}>", item)
+                } else {
+                    format!("{}[]", item)
+                }
+            }
+            "object" => ts_object(refs, node, indent),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn ts_object<'a>(refs: &mut Refs<'a>, node: &'a Value, indent: usize) -> String {
+    let properties = ordered_properties(node);
+    if properties.is_empty() {
+        return match node.get("additionalProperties") {
+            Some(value @ Value::Object(_)) => {
+                format!("Record<string, {}>", ts_type(refs, value, indent))
+            }
+            _ => "Record<string, unknown>".to_string(),
+        };
+    }
+
+    let pad = "  ".repeat(indent + 1);
+    let mut out = String::from("{\n");
+    for (name, prop, required) in properties {
+        if let Some(text) = description(prop) {
+            out.push_str(&format!("{}/** {} */\n", pad, first_line(text)));
+        }
+        let key = if is_identifier(name) {
+            name.to_string()
+        } else {
+            Value::String(name.to_string()).to_string()
+        };
+        out.push_str(&format!(
+            "{}{}{}: {};\n",
+            pad,
+            key,
+            if required { "" } else { "?" },
+            ts_type(refs, prop, indent + 1)
+        ));
+    }
+    out.push_str(&"  ".repeat(indent));
+    out.push('}');
+    out
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Render a schema as an indented outline, one field per line
+///
+/// ```text
+/// repo*: string  # owner/name
+/// labels: array of string [max 10 items]
+/// options: object
+///   draft: boolean = false
+/// ```
+pub fn to_outline(schema: &Value) -> String {
+    let mut refs = Refs::new(schema);
+    let mut out = String::new();
+    refs.expand(
+        schema,
+        &mut |refs, node| {
+            if ordered_properties(node).is_empty() {
+                out.push_str(&outline_summary(refs, node));
+                out.push('\n');
+            } else {
+                outline_fields(refs, node, 0, &mut out);
+            }
+        },
+        &|_| {},
+    );
+    out
+}
+
+fn outline_fields<'a>(refs: &mut Refs<'a>, node: &'a Value, indent: usize, out: &mut String) {
+    for (name, prop, required) in ordered_properties(node) {
+        let mut line = format!(
+            "{}{}{}: {}",
+            "  ".repeat(indent),
+            name,
+            if required { "*" } else { "" },
+            outline_summary(refs, prop)
+        );
+        if let Some(text) = description(prop) {
+            line.push_str("  # ");
+            line.push_str(first_line(text));
+        }
+        out.push_str(&line);
+        out.push('\n');
+
+        // Fields of nested objects and arrays of objects
+        refs.expand(
+            prop,
+            &mut |refs, prop| {
+                let nested = match prop.get("items") {
+                    Some(items) if prop.get("properties").is_none() => items,
+                    _ => prop,
+                };
+                refs.expand(
+                    nested,
+                    &mut |refs, nested| outline_fields(refs, nested, indent + 1, out),
+                    &|_| {},
+                );
+            },
+            &|_| {},
+        );
+    }
+}
+
+/// One-line type summary with constraints, e.g. `string [format: email] = "a@b.c"`
+fn outline_summary<'a>(refs: &mut Refs<'a>, node: &'a Value) -> String {
+    refs.expand(
+        node,
+        &mut |refs, node| {
+            let Some(obj) = node.as_object() else {
+                return "any".to_string();
+            };
+
+            let mut summary = if let Some(value) = obj.get("const") {
+                format!("const {}", value)
+            } else if let Some(values) = obj.get("enum").and_then(|e| e.as_array()) {
+                format!(
+                    "one of {}",
+                    values
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            } else if let Some(variants) = obj
+                .get("oneOf")
+                .or_else(|| obj.get("anyOf"))
+                .and_then(|v| v.as_array())
+            {
+                variants
+                    .iter()
+                    .map(|v| outline_summary(refs, v))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            } else {
+                match obj.get("type") {
+                    Some(Value::String(name)) if name == "array" => match obj.get("items") {
+                        Some(items) => format!("array of {}", outline_summary(refs, items)),
+                        None => "array".to_string(),
+                    },
+                    Some(Value::String(name)) => name.clone(),
+                    Some(Value::Array(names)) => names
+                        .iter()
+                        .filter_map(|n| n.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                    _ if obj.contains_key("properties") => "object".to_string(),
+                    _ => "any".to_string(),
+                }
+            };
+
+            let constraints: Vec<String> = [
+                ("format", "format: "),
+                ("pattern", "pattern: "),
+                ("minimum", "min "),
+                ("maximum", "max "),
+                ("exclusiveMinimum", "> "),
+                ("exclusiveMaximum", "< "),
+                ("minLength", "min length "),
+                ("maxLength", "max length "),
+                ("minItems", "min "),
+                ("maxItems", "max "),
+            ]
+            .iter()
+            .filter_map(|(key, label)| {
+                let value = obj.get(*key)?;
+                let value = value
+                    .as_str()
+                    .map(String::from)
+                    .unwrap_or_else(|| value.to_string());
+                let suffix = if key.ends_with("Items") { " items" } else { "" };
+                Some(format!("{}{}{}", label, value, suffix))
+            })
+            .collect();
+            if !constraints.is_empty() {
+                summary.push_str(&format!(" [{}]", constraints.join(", ")));
+            }
+            if let Some(default) = obj.get("default") {
+                summary.push_str(&format!(" = {}", default));
+            }
+            summary
+        },
+        &|reference| format!("see {}", reference),
+    )
+}
+
+/// Whether a keyword's value only restates the JSON Schema default
+fn restates_default(key: &str, value: &Value) -> bool {
+    match key {
+        "additionalProperties" | "additionalItems" | "unevaluatedProperties" => {
+            value == &Value::Bool(true)
+        }
+        "deprecated" | "readOnly" | "writeOnly" | "uniqueItems" | "nullable" => {
+            value == &Value::Bool(false)
+        }
+        "minLength" | "minItems" | "minProperties" | "minContains" => value == &Value::from(0),
+        "required" | "enum" => value.as_array().is_some_and(|a| a.is_empty()),
+        "properties" | "patternProperties" => value.as_object().is_some_and(|m| m.is_empty()),
+        _ => false,
+    }
+}
+
+/// Minimal JSON Schema for the same instances
+///
+/// Local `$ref`s are inlined (recursive ones are kept), definitions no longer
+/// referenced are dropped, and `$schema`, `$id`, `$comment`, `title`, and
+/// keywords that restate their default are stripped.
+pub fn minimal_schema(schema: &Value) -> Value {
+    let mut refs = Refs::new(schema);
+    let mut minimal = minimize(&mut refs, schema);
+
+    // Keep only definitions that recursive references still point at
+    let text = minimal.to_string();
+    if let Some(obj) = minimal.as_object_mut() {
+        for key in ["$defs", "definitions"] {
+            if let Some(Value::Object(defs)) = obj.get_mut(key) {
+                defs.retain(|name, _| text.contains(&format!("\"#/{}/{}\"", key, name)));
+                if defs.is_empty() {
+                    obj.remove(key);
+                }
+            }
+        }
+    }
+    minimal
+}
+
+fn minimize<'a>(refs: &mut Refs<'a>, node: &'a Value) -> Value {
+    // Keywords next to a `$ref` (like `description`) apply alongside it
+    let siblings: Vec<(&'a String, &'a Value)> = match node.as_object() {
+        Some(obj) if obj.contains_key("$ref") => obj
+            .iter()
+            .filter(|(key, _)| key.as_str() != "$ref")
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    refs.expand(
+        node,
+        &mut |refs, target| {
+            let Some(obj) = target.as_object() else {
+                return target.clone();
+            };
+            let mut out = Map::new();
+            for (key, value) in obj.iter().chain(siblings.iter().copied()) {
+                if matches!(key.as_str(), "$schema" | "$id" | "$comment" | "title")
+                    || restates_default(key, value)
+                {
+                    continue;
+                }
+                let value = if SCHEMA_MAPS.contains(&key.as_str()) {
+                    match value.as_object() {
+                        Some(map) => Value::Object(
+                            map.iter()
+                                .map(|(name, sub)| (name.clone(), minimize(refs, sub)))
+                                .collect(),
+                        ),
+                        None => value.clone(),
+                    }
+                } else if SCHEMA_VALUES.contains(&key.as_str()) {
+                    match value {
+                        Value::Array(items) => {
+                            Value::Array(items.iter().map(|sub| minimize(refs, sub)).collect())
+                        }
+                        Value::Object(_) => minimize(refs, value),
+                        other => other.clone(),
+                    }
+                } else {
+                    value.clone()
+                };
+                out.insert(key.clone(), value);
+            }
+            Value::Object(out)
+        },
+        &|_| node.clone(),
+    )
+}
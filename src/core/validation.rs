@@ -0,0 +1,708 @@
+//! JSON Schema validation
+//!
+//! Validates instances (exec arguments, structured results) against the JSON
+//! Schemas advertised by callables. Supports the keywords MCP servers use in
+//! practice:
+//! - `type`, `enum`, `const`, `format`
+//! - Object keywords (`properties`, `required`, `additionalProperties`, ...)
+//! - Array keywords (`items`, `prefixItems`, `minItems`, `uniqueItems`, ...)
+//! - String and numeric bounds, `pattern`, `multipleOf`
+//! - Composition (`allOf`, `anyOf`, `oneOf`, `not`, `if`/`then`/`else`)
+//! - Local `$ref` into `#/$defs` and `#/definitions`
+//!
+//! Errors are reported per location as JSON pointers into the instance, e.g.
+//! `/items/2/name: expected string, got number`.
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Maximum number of `$ref` resolutions in a row on one instance value before giving up
+const MAX_REF_DEPTH: usize = 64;
+
+/// A single validation failure at a location in the instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value (empty for the root)
+    pub path: String,
+    /// Human-readable description of the failure
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Validate `instance` against `schema`, returning every violation found
+pub fn validate(schema: &Value, instance: &Value) -> Vec<SchemaViolation> {
+    let mut validator = Validator::new(schema);
+    let mut violations = Vec::new();
+    validator.validate(schema, instance, "", 0, &mut violations);
+    violations
+}
+
+/// Check whether `instance` conforms to `schema`
+pub fn is_valid(schema: &Value, instance: &Value) -> bool {
+    validate(schema, instance).is_empty()
+}
+
+/// Join violations into a single `; `-separated message
+pub fn format_violations(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Name of the JSON type of a value, as used by the `type` keyword
+pub fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) => {
+            if is_integer(n) {
+                "integer"
+            } else {
+                "number"
+            }
+        }
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Resolve a local `$ref` (`#` or `#/json/pointer`) against a root schema
+pub fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    if pointer.is_empty() {
+        return Some(root);
+    }
+    root.pointer(pointer)
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    regexes: HashMap<String, Option<Regex>>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(root: &'a Value) -> Self {
+        Validator {
+            root,
+            regexes: HashMap::new(),
+        }
+    }
+
+    fn validate(
+        &mut self,
+        schema: &'a Value,
+        instance: &Value,
+        path: &str,
+        ref_depth: usize,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                out.push(violation(path, "no value is allowed here"));
+                return;
+            }
+            Value::Object(map) => map,
+            // Malformed schema: nothing to check against
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+            if ref_depth >= MAX_REF_DEPTH {
+                out.push(violation(path, "schema $ref nesting too deep"));
+                return;
+            }
+            match resolve_ref(self.root, reference) {
+                Some(target) => self.validate(target, instance, path, ref_depth + 1, out),
+                None => out.push(violation(
+                    path,
+                    format!("unresolvable schema reference '{}'", reference),
+                )),
+            }
+        }
+
+        // A type mismatch makes the remaining keywords noise
+        if !self.check_type(schema, instance, path, out) {
+            return;
+        }
+
+        self.check_enum_const(schema, instance, path, out);
+
+        // Child values restart the `$ref` count: only a reference cycle that never
+        // descends into the instance can recurse without end
+        match instance {
+            Value::Object(object) => self.check_object(schema, object, path, out),
+            Value::Array(items) => self.check_array(schema, items, path, out),
+            Value::String(s) => self.check_string(schema, s, path, out),
+            Value::Number(n) => check_number(schema, n, path, out),
+            _ => {}
+        }
+
+        self.check_composition(schema, instance, path, ref_depth, out);
+    }
+
+    fn check_type(
+        &self,
+        schema: &Map<String, Value>,
+        instance: &Value,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) -> bool {
+        let expected: Vec<&str> = match schema.get("type") {
+            Some(Value::String(t)) => vec![t.as_str()],
+            Some(Value::Array(types)) => types.iter().filter_map(|t| t.as_str()).collect(),
+            _ => return true,
+        };
+
+        if expected.iter().any(|t| type_matches(t, instance)) {
+            return true;
+        }
+
+        out.push(violation(
+            path,
+            format!(
+                "expected {}, got {}",
+                expected.join(" or "),
+                json_type_name(instance)
+            ),
+        ));
+        false
+    }
+
+    fn check_enum_const(
+        &self,
+        schema: &Map<String, Value>,
+        instance: &Value,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        if let Some(Value::Array(options)) = schema.get("enum") {
+            if !options.iter().any(|o| json_equal(o, instance)) {
+                out.push(violation(
+                    path,
+                    format!(
+                        "expected one of {}, got {}",
+                        Value::Array(options.clone()),
+                        instance
+                    ),
+                ));
+            }
+        }
+
+        if let Some(constant) = schema.get("const") {
+            if !json_equal(constant, instance) {
+                out.push(violation(path, format!("expected constant {}", constant)));
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(|k| k.as_str()) {
+                if !object.contains_key(key) {
+                    out.push(violation(
+                        &child_path(path, key),
+                        "missing required property",
+                    ));
+                }
+            }
+        }
+
+        if let Some(Value::Object(dependent)) = schema.get("dependentRequired") {
+            for (trigger, deps) in dependent {
+                if !object.contains_key(trigger) {
+                    continue;
+                }
+                for dep in deps.as_array().into_iter().flatten() {
+                    if let Some(dep) = dep.as_str() {
+                        if !object.contains_key(dep) {
+                            out.push(violation(
+                                &child_path(path, dep),
+                                format!("required when '{}' is present", trigger),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(min) = schema.get("minProperties").and_then(|v| v.as_u64()) {
+            if (object.len() as u64) < min {
+                out.push(violation(
+                    path,
+                    format!("expected at least {} properties", min),
+                ));
+            }
+        }
+        if let Some(max) = schema.get("maxProperties").and_then(|v| v.as_u64()) {
+            if (object.len() as u64) > max {
+                out.push(violation(
+                    path,
+                    format!("expected at most {} properties", max),
+                ));
+            }
+        }
+
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        let pattern_properties = schema.get("patternProperties").and_then(|p| p.as_object());
+        let additional = schema.get("additionalProperties");
+
+        for (key, value) in object {
+            let value_path = child_path(path, key);
+            let mut matched = false;
+
+            if let Some(property_schema) = properties.and_then(|p| p.get(key)) {
+                matched = true;
+                self.validate(property_schema, value, &value_path, 0, out);
+            }
+
+            if let Some(patterns) = pattern_properties {
+                for (pattern, property_schema) in patterns {
+                    if self.regex(pattern).is_some_and(|re| re.is_match(key)) {
+                        matched = true;
+                        self.validate(property_schema, value, &value_path, 0, out);
+                    }
+                }
+            }
+
+            if matched {
+                continue;
+            }
+
+            match additional {
+                Some(Value::Bool(false)) => {
+                    out.push(violation(&value_path, "unexpected property"));
+                }
+                Some(additional_schema @ Value::Object(_)) => {
+                    self.validate(additional_schema, value, &value_path, 0, out);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(names_schema) = schema.get("propertyNames") {
+            for key in object.keys() {
+                let mut errors = Vec::new();
+                self.validate(
+                    names_schema,
+                    &Value::String(key.clone()),
+                    path,
+                    0,
+                    &mut errors,
+                );
+                if !errors.is_empty() {
+                    out.push(violation(
+                        &child_path(path, key),
+                        "property name is not allowed",
+                    ));
+                }
+            }
+        }
+    }
+
+    fn check_array(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        items: &[Value],
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        if let Some(min) = schema.get("minItems").and_then(|v| v.as_u64()) {
+            if (items.len() as u64) < min {
+                out.push(violation(path, format!("expected at least {} items", min)));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(|v| v.as_u64()) {
+            if (items.len() as u64) > max {
+                out.push(violation(path, format!("expected at most {} items", max)));
+            }
+        }
+
+        if schema.get("uniqueItems").and_then(|v| v.as_bool()) == Some(true) {
+            'outer: for (i, a) in items.iter().enumerate() {
+                for b in &items[i + 1..] {
+                    if json_equal(a, b) {
+                        out.push(violation(path, "expected unique items"));
+                        break 'outer;
+                    }
+                }
+            }
+        }
+
+        // Tuple validation: `prefixItems` (2020-12) or array-form `items` (draft 7)
+        let (prefix, rest): (&[Value], Option<&'a Value>) =
+            match (schema.get("prefixItems"), schema.get("items")) {
+                (Some(Value::Array(prefix)), rest) => (prefix, rest),
+                (None, Some(Value::Array(prefix))) => (prefix, schema.get("additionalItems")),
+                (_, rest) => (&[], rest),
+            };
+
+        for (index, item) in items.iter().enumerate() {
+            let item_path = child_path(path, &index.to_string());
+            if let Some(item_schema) = prefix.get(index) {
+                self.validate(item_schema, item, &item_path, 0, out);
+            } else if let Some(item_schema) = rest {
+                if item_schema == &Value::Bool(false) {
+                    out.push(violation(&item_path, "unexpected item"));
+                } else {
+                    self.validate(item_schema, item, &item_path, 0, out);
+                }
+            }
+        }
+
+        if let Some(contains) = schema.get("contains") {
+            let any = items.iter().any(|item| self.matches(contains, item, 0));
+            if !any {
+                out.push(violation(
+                    path,
+                    "expected at least one item matching 'contains'",
+                ));
+            }
+        }
+    }
+
+    fn check_string(
+        &mut self,
+        schema: &Map<String, Value>,
+        s: &str,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        let length = s.chars().count() as u64;
+
+        if let Some(min) = schema.get("minLength").and_then(|v| v.as_u64()) {
+            if length < min {
+                out.push(violation(
+                    path,
+                    format!("expected at least {} characters", min),
+                ));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(|v| v.as_u64()) {
+            if length > max {
+                out.push(violation(
+                    path,
+                    format!("expected at most {} characters", max),
+                ));
+            }
+        }
+
+        if let Some(pattern) = schema.get("pattern").and_then(|p| p.as_str()) {
+            if let Some(re) = self.regex(pattern) {
+                if !re.is_match(s) {
+                    out.push(violation(
+                        path,
+                        format!("does not match pattern '{}'", pattern),
+                    ));
+                }
+            }
+        }
+
+        if let Some(format) = schema.get("format").and_then(|f| f.as_str()) {
+            if !check_format(format, s) {
+                out.push(violation(path, format!("expected format '{}'", format)));
+            }
+        }
+    }
+
+    fn check_composition(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        instance: &Value,
+        path: &str,
+        ref_depth: usize,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            for sub in all {
+                self.validate(sub, instance, path, ref_depth, out);
+            }
+        }
+
+        if let Some(Value::Array(any)) = schema.get("anyOf") {
+            let results: Vec<Vec<SchemaViolation>> = any
+                .iter()
+                .map(|sub| self.collect(sub, instance, path, ref_depth))
+                .collect();
+            if !results.iter().any(|r| r.is_empty()) {
+                out.extend(self.closest_branch(any, results, instance, path, "anyOf"));
+            }
+        }
+
+        if let Some(Value::Array(one)) = schema.get("oneOf") {
+            let results: Vec<Vec<SchemaViolation>> = one
+                .iter()
+                .map(|sub| self.collect(sub, instance, path, ref_depth))
+                .collect();
+            let matched = results.iter().filter(|r| r.is_empty()).count();
+            match matched {
+                1 => {}
+                0 => out.extend(self.closest_branch(one, results, instance, path, "oneOf")),
+                n => out.push(violation(
+                    path,
+                    format!("expected exactly one oneOf schema to match, but {} did", n),
+                )),
+            }
+        }
+
+        if let Some(not) = schema.get("not") {
+            if self.matches(not, instance, ref_depth) {
+                out.push(violation(path, "must not match the 'not' schema"));
+            }
+        }
+
+        if let Some(condition) = schema.get("if") {
+            let branch = if self.matches(condition, instance, ref_depth) {
+                schema.get("then")
+            } else {
+                schema.get("else")
+            };
+            if let Some(branch) = branch {
+                self.validate(branch, instance, path, ref_depth, out);
+            }
+        }
+    }
+
+    /// Report the violations of the branch that came closest to matching
+    ///
+    /// Branches whose declared type does not fit the instance are rarely what
+    /// the caller meant, so they only produce a summary of the accepted types.
+    fn closest_branch(
+        &self,
+        branches: &[Value],
+        results: Vec<Vec<SchemaViolation>>,
+        instance: &Value,
+        path: &str,
+        keyword: &str,
+    ) -> Vec<SchemaViolation> {
+        let best = branches
+            .iter()
+            .zip(results)
+            .filter(|(branch, _)| {
+                self.declared_types(branch).map_or(true, |types| {
+                    types.iter().any(|t| type_matches(t, instance))
+                })
+            })
+            .map(|(_, errors)| errors)
+            .min_by_key(|errors| errors.len());
+
+        if let Some(errors) = best {
+            return errors;
+        }
+
+        let mut expected: Vec<&str> = Vec::new();
+        for types in branches.iter().filter_map(|b| self.declared_types(b)) {
+            for t in types {
+                if !expected.contains(&t) {
+                    expected.push(t);
+                }
+            }
+        }
+
+        let message = if expected.is_empty() {
+            format!("does not match any {} schema", keyword)
+        } else {
+            format!(
+                "expected {}, got {}",
+                expected.join(" or "),
+                json_type_name(instance)
+            )
+        };
+        vec![violation(path, message)]
+    }
+
+    /// Types a schema declares, following local `$ref`s
+    fn declared_types(&self, schema: &'a Value) -> Option<Vec<&'a str>> {
+        let mut schema = schema;
+        for _ in 0..MAX_REF_DEPTH {
+            match schema.get("type") {
+                Some(Value::String(t)) => return Some(vec![t.as_str()]),
+                Some(Value::Array(types)) => {
+                    return Some(types.iter().filter_map(|t| t.as_str()).collect())
+                }
+                _ => {}
+            }
+            let reference = schema.get("$ref").and_then(|r| r.as_str())?;
+            schema = resolve_ref(self.root, reference)?;
+        }
+        None
+    }
+
+    fn collect(
+        &mut self,
+        schema: &'a Value,
+        instance: &Value,
+        path: &str,
+        ref_depth: usize,
+    ) -> Vec<SchemaViolation> {
+        let mut errors = Vec::new();
+        self.validate(schema, instance, path, ref_depth, &mut errors);
+        errors
+    }
+
+    fn matches(&mut self, schema: &'a Value, instance: &Value, ref_depth: usize) -> bool {
+        self.collect(schema, instance, "", ref_depth).is_empty()
+    }
+
+    fn regex(&mut self, pattern: &str) -> Option<&Regex> {
+        self.regexes
+            .entry(pattern.to_string())
+            .or_insert_with(|| Regex::new(pattern).ok())
+            .as_ref()
+    }
+}
+
+fn check_number(
+    schema: &Map<String, Value>,
+    n: &serde_json::Number,
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let Some(value) = n.as_f64() else {
+        return;
+    };
+
+    let exclusive_flag = |key: &str| schema.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+    if let Some(min) = schema.get("minimum").and_then(|v| v.as_f64()) {
+        // Draft 4 spells exclusivity as a boolean modifier
+        if exclusive_flag("exclusiveMinimum") {
+            if value <= min {
+                out.push(violation(path, format!("expected a value > {}", min)));
+            }
+        } else if value < min {
+            out.push(violation(path, format!("expected a value >= {}", min)));
+        }
+    }
+    if let Some(max) = schema.get("maximum").and_then(|v| v.as_f64()) {
+        if exclusive_flag("exclusiveMaximum") {
+            if value >= max {
+                out.push(violation(path, format!("expected a value < {}", max)));
+            }
+        } else if value > max {
+            out.push(violation(path, format!("expected a value <= {}", max)));
+        }
+    }
+    if let Some(min) = schema.get("exclusiveMinimum").and_then(|v| v.as_f64()) {
+        if value <= min {
+            out.push(violation(path, format!("expected a value > {}", min)));
+        }
+    }
+    if let Some(max) = schema.get("exclusiveMaximum").and_then(|v| v.as_f64()) {
+        if value >= max {
+            out.push(violation(path, format!("expected a value < {}", max)));
+        }
+    }
+    if let Some(step) = schema.get("multipleOf").and_then(|v| v.as_f64()) {
+        if step > 0.0 {
+            let quotient = value / step;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+                out.push(violation(path, format!("expected a multiple of {}", step)));
+            }
+        }
+    }
+}
+
+/// Validate well-known `format` values; unknown formats are accepted
+fn check_format(format: &str, s: &str) -> bool {
+    match format {
+        "date-time" => chrono::DateTime::parse_from_rfc3339(s).is_ok(),
+        "date" => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+        "time" => {
+            chrono::DateTime::parse_from_rfc3339(&format!("1970-01-01T{}", s)).is_ok()
+                || chrono::NaiveTime::parse_from_str(s, "%H:%M:%S").is_ok()
+        }
+        "email" => {
+            let mut parts = s.splitn(2, '@');
+            let local = parts.next().unwrap_or("");
+            let domain = parts.next().unwrap_or("");
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !s.chars().any(char::is_whitespace)
+        }
+        "uri" | "url" => reqwest::Url::parse(s).is_ok(),
+        "uuid" => uuid::Uuid::parse_str(s).is_ok(),
+        "ipv4" => s.parse::<std::net::Ipv4Addr>().is_ok(),
+        "ipv6" => s.parse::<std::net::Ipv6Addr>().is_ok(),
+        "hostname" => {
+            !s.is_empty()
+                && s.len() <= 253
+                && s.split('.').all(|label| {
+                    !label.is_empty()
+                        && label.len() <= 63
+                        && !label.starts_with('-')
+                        && !label.ends_with('-')
+                        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+        }
+        "regex" => Regex::new(s).is_ok(),
+        _ => true,
+    }
+}
+
+fn type_matches(expected: &str, instance: &Value) -> bool {
+    match expected {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => instance.as_number().is_some_and(is_integer),
+        _ => true,
+    }
+}
+
+fn is_integer(n: &serde_json::Number) -> bool {
+    n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+}
+
+/// Equality with numeric comparison (`1` equals `1.0`)
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(a, b)| json_equal(a, b))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|w| json_equal(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    format!("{}/{}", parent, key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn violation(path: &str, message: impl Into<String>) -> SchemaViolation {
+    SchemaViolation {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
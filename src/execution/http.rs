@@ -0,0 +1,643 @@
+//! HTTP transports for upstream MCP servers
+//!
+//! Implements the client side of the MCP Streamable HTTP transport:
+//! - `initialize` handshake with `Mcp-Session-Id` tracking
+//! - Responses delivered either as plain JSON or as an SSE stream
+//! - Transparent re-initialization when the server expires a session (HTTP 404)
+//!
+//! It also implements the legacy HTTP+SSE transport, where the client holds a
+//! GET event stream open and POSTs messages to the endpoint the server
+//! announces on it.
+
+use crate::execution::rpc::MessageWriter;
+use crate::execution::upstream::{
+    McpRequest, McpResponse, Result, UpstreamError, PROTOCOL_VERSION,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde_json::Value as JsonValue;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const SESSION_ID_HEADER: &str = "mcp-session-id";
+const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+const ACCEPT_HEADER_VALUE: &str = "application/json, text/event-stream";
+
+/// A single Server-Sent Event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type (`None` means the default `message` type)
+    pub event: Option<String>,
+    /// Event payload; multiple `data:` lines are joined with `\n`
+    pub data: String,
+    /// Last event id, if the server sent one
+    pub id: Option<String>,
+}
+
+impl SseEvent {
+    /// Event type with the SSE default applied
+    pub fn event_type(&self) -> &str {
+        self.event.as_deref().unwrap_or("message")
+    }
+}
+
+/// Incremental `text/event-stream` decoder
+///
+/// Feed it arbitrary byte chunks as they arrive; it buffers partial lines and
+/// yields complete events once their terminating blank line is seen.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of bytes, returning every event completed by it
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            let line = String::from_utf8_lossy(&line);
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // Comment line
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" => self.id = Some(value.to_string()),
+            // `retry` and unknown fields are ignored
+            _ => {}
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+
+        Some(SseEvent {
+            event,
+            data: std::mem::take(&mut self.data).join("\n"),
+            id: self.id.clone(),
+        })
+    }
+}
+
+/// Session state negotiated during `initialize`
+#[derive(Debug, Default)]
+struct HttpSessionState {
+    session_id: Option<String>,
+    protocol_version: Option<String>,
+}
+
+/// Client for the MCP Streamable HTTP transport
+///
+/// Every JSON-RPC message is POSTed to a single endpoint. The server answers
+/// either with `application/json` or with a `text/event-stream` carrying the
+/// response (possibly preceded by notifications).
+pub struct StreamableHttpClient {
+    alias: String,
+    url: String,
+    bearer_token: Option<String>,
+    http_client: reqwest::Client,
+    session: Mutex<HttpSessionState>,
+    // Serializes re-initialization so concurrent 404s only re-handshake once
+    reinit_lock: tokio::sync::Mutex<()>,
+    next_id: AtomicU64,
+}
+
+impl StreamableHttpClient {
+    pub fn new(
+        alias: impl Into<String>,
+        url: impl Into<String>,
+        bearer_token: Option<String>,
+        http_client: reqwest::Client,
+    ) -> Self {
+        StreamableHttpClient {
+            alias: alias.into(),
+            url: url.into(),
+            bearer_token,
+            http_client,
+            session: Mutex::new(HttpSessionState::default()),
+            reinit_lock: tokio::sync::Mutex::new(()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Endpoint URL this client talks to
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Session id assigned by the server, if any
+    pub fn session_id(&self) -> Option<String> {
+        self.session.lock().session_id.clone()
+    }
+
+    /// Protocol version negotiated with the server
+    pub fn protocol_version(&self) -> Option<String> {
+        self.session.lock().protocol_version.clone()
+    }
+
+    /// Perform the `initialize` handshake, replacing any existing session
+    ///
+    /// Returns the server's `InitializeResult`.
+    pub async fn initialize(&self) -> Result<JsonValue> {
+        *self.session.lock() = HttpSessionState::default();
+
+        let request = self.build_request(
+            "initialize",
+            Some(serde_json::json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {
+                    "name": "skills.rs",
+                    "version": env!("CARGO_PKG_VERSION")
+                }
+            })),
+        );
+
+        let response = self.post(&request).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(UpstreamError::ConnectionFailed(format!(
+                "initialize returned HTTP {} from {}",
+                status, self.url
+            )));
+        }
+
+        let session_id = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
+        let result = self.read_response(response, &request.id).await?;
+
+        let protocol_version = result
+            .get("protocolVersion")
+            .and_then(|v| v.as_str())
+            .unwrap_or(PROTOCOL_VERSION)
+            .to_string();
+
+        {
+            let mut session = self.session.lock();
+            session.session_id = session_id.clone();
+            session.protocol_version = Some(protocol_version);
+        }
+
+        debug!(
+            "MCP initialize succeeded for {} (session: {:?})",
+            self.alias, session_id
+        );
+
+        self.notify("notifications/initialized", None).await?;
+
+        Ok(result)
+    }
+
+    /// Send a JSON-RPC request and wait for its result
+    ///
+    /// If the server reports the session as expired (HTTP 404 while a session
+    /// id is held), the client re-initializes and retries the request once.
+    pub async fn request(&self, method: &str, params: Option<JsonValue>) -> Result<JsonValue> {
+        let request = self.build_request(method, params);
+        let sent_session = self.session_id();
+
+        let response = self.post(&request).await?;
+
+        let response =
+            if response.status() == reqwest::StatusCode::NOT_FOUND && sent_session.is_some() {
+                info!(
+                    "Session {:?} expired on {}, re-initializing",
+                    sent_session, self.alias
+                );
+                self.reinitialize(sent_session.as_deref()).await?;
+                self.post(&request).await?
+            } else {
+                response
+            };
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(UpstreamError::RequestFailed(format!(
+                "HTTP {} for {}: {}",
+                status,
+                method,
+                body.chars().take(200).collect::<String>()
+            )));
+        }
+
+        self.read_response(response, &request.id).await
+    }
+
+    /// Send a JSON-RPC notification (no response expected)
+    pub async fn notify(&self, method: &str, params: Option<JsonValue>) -> Result<()> {
+        let notification = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: JsonValue::Null,
+            method: method.to_string(),
+            params,
+        };
+
+        let response = self.post(&notification).await?;
+        if !response.status().is_success() {
+            return Err(UpstreamError::RequestFailed(format!(
+                "HTTP {} for {}",
+                response.status(),
+                method
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Terminate the session on the server (best effort)
+    pub async fn terminate(&self) {
+        let Some(session_id) = self.session_id() else {
+            return;
+        };
+
+        let mut request = self
+            .http_client
+            .delete(&self.url)
+            .header(SESSION_ID_HEADER, &session_id);
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        match request.send().await {
+            Ok(response) => debug!(
+                "Terminated session {} on {}: HTTP {}",
+                session_id,
+                self.alias,
+                response.status()
+            ),
+            Err(e) => debug!("Failed to terminate session on {}: {}", self.alias, e),
+        }
+
+        *self.session.lock() = HttpSessionState::default();
+    }
+
+    async fn reinitialize(&self, expired_session: Option<&str>) -> Result<()> {
+        let _guard = self.reinit_lock.lock().await;
+
+        // Another request may have already re-initialized while we waited
+        if self.session_id().as_deref() != expired_session {
+            return Ok(());
+        }
+
+        self.initialize().await.map(|_| ())
+    }
+
+    fn build_request(&self, method: &str, params: Option<JsonValue>) -> McpRequest {
+        McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: JsonValue::from(self.next_id.fetch_add(1, Ordering::Relaxed)),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    async fn post(&self, message: &McpRequest) -> Result<reqwest::Response> {
+        let (session_id, protocol_version) = {
+            let session = self.session.lock();
+            (session.session_id.clone(), session.protocol_version.clone())
+        };
+
+        let mut request = self
+            .http_client
+            .post(&self.url)
+            .header(reqwest::header::ACCEPT, ACCEPT_HEADER_VALUE)
+            .json(message);
+
+        if let Some(session_id) = session_id {
+            request = request.header(SESSION_ID_HEADER, session_id);
+        }
+        if let Some(version) = protocol_version {
+            request = request.header(PROTOCOL_VERSION_HEADER, version);
+        }
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        request
+            .send()
+            .await
+            .map_err(|e| UpstreamError::RequestFailed(format!("HTTP request failed: {}", e)))
+    }
+
+    /// Extract the response for `id` from a JSON or SSE response body
+    async fn read_response(
+        &self,
+        response: reqwest::Response,
+        id: &JsonValue,
+    ) -> Result<JsonValue> {
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        if content_type.starts_with("text/event-stream") {
+            return self.read_sse_response(response, id).await;
+        }
+
+        let body: JsonValue = response.json().await.map_err(|e| {
+            UpstreamError::ProtocolError(format!("Failed to parse response: {}", e))
+        })?;
+
+        // A batch response is an array of individual responses
+        let messages = match body {
+            JsonValue::Array(items) => items,
+            other => vec![other],
+        };
+
+        for message in messages {
+            if message.get("id") == Some(id) {
+                return response_result(message);
+            }
+            self.log_unsolicited(&message);
+        }
+
+        Err(UpstreamError::ProtocolError(format!(
+            "Response did not contain a reply to request {}",
+            id
+        )))
+    }
+
+    async fn read_sse_response(
+        &self,
+        response: reqwest::Response,
+        id: &JsonValue,
+    ) -> Result<JsonValue> {
+        let mut stream = response.bytes_stream();
+        let mut decoder = SseDecoder::new();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk
+                .map_err(|e| UpstreamError::ConnectionFailed(format!("SSE stream error: {}", e)))?;
+
+            for event in decoder.feed(&chunk) {
+                if event.event_type() != "message" {
+                    continue;
+                }
+
+                let message: JsonValue = match serde_json::from_str(&event.data) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        warn!("Invalid SSE message from {}: {}", self.alias, e);
+                        continue;
+                    }
+                };
+
+                if message.get("id") == Some(id) {
+                    return response_result(message);
+                }
+                self.log_unsolicited(&message);
+            }
+        }
+
+        Err(UpstreamError::ProtocolError(format!(
+            "SSE stream closed before response to request {}",
+            id
+        )))
+    }
+
+    fn log_unsolicited(&self, message: &JsonValue) {
+        if let Some(method) = message.get("method").and_then(|m| m.as_str()) {
+            debug!("Ignoring server message from {}: {}", self.alias, method);
+        } else {
+            debug!("Ignoring unrelated message from {}", self.alias);
+        }
+    }
+}
+
+/// Writer that POSTs messages to a legacy HTTP+SSE message endpoint
+pub(crate) struct SsePostWriter {
+    endpoint: String,
+    bearer_token: Option<String>,
+    http_client: reqwest::Client,
+}
+
+#[async_trait]
+impl MessageWriter for SsePostWriter {
+    async fn write_message(&mut self, message: &str) -> Result<()> {
+        let mut request = self
+            .http_client
+            .post(&self.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(message.to_string());
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| UpstreamError::RequestFailed(format!("HTTP request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(UpstreamError::RequestFailed(format!(
+                "HTTP {} from message endpoint",
+                response.status()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// An open legacy HTTP+SSE connection
+pub(crate) struct LegacySseConnection {
+    /// Writer for the announced message endpoint
+    pub(crate) writer: SsePostWriter,
+    /// JSON-RPC messages received on the event stream
+    pub(crate) messages: mpsc::UnboundedReceiver<JsonValue>,
+    /// Task reading the event stream; abort it to close the connection
+    pub(crate) reader: JoinHandle<()>,
+}
+
+/// Open the GET event stream and wait for the server's `endpoint` event
+pub(crate) async fn open_legacy_sse(
+    alias: &str,
+    url: &str,
+    bearer_token: Option<String>,
+    http_client: reqwest::Client,
+    timeout: Duration,
+) -> Result<LegacySseConnection> {
+    let base = reqwest::Url::parse(url)
+        .map_err(|e| UpstreamError::ConnectionFailed(format!("Invalid URL {}: {}", url, e)))?;
+
+    let mut request = http_client
+        .get(base.clone())
+        .header(reqwest::header::ACCEPT, "text/event-stream");
+    if let Some(token) = &bearer_token {
+        request = request.bearer_auth(token);
+    }
+
+    let response = with_timeout(timeout, "SSE connect", async {
+        request.send().await.map_err(|e| {
+            UpstreamError::ConnectionFailed(format!("Failed to open event stream: {}", e))
+        })
+    })
+    .await?;
+
+    if !response.status().is_success() {
+        return Err(UpstreamError::ConnectionFailed(format!(
+            "Event stream returned HTTP {}",
+            response.status()
+        )));
+    }
+
+    let (endpoint_tx, endpoint_rx) = oneshot::channel::<String>();
+    let (message_tx, message_rx) = mpsc::unbounded_channel::<JsonValue>();
+
+    let reader_alias = alias.to_string();
+    let reader = tokio::spawn(async move {
+        let mut stream = response.bytes_stream();
+        let mut decoder = SseDecoder::new();
+        let mut endpoint_tx = Some(endpoint_tx);
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    warn!("SSE stream error from {}: {}", reader_alias, e);
+                    break;
+                }
+            };
+
+            for event in decoder.feed(&chunk) {
+                match event.event_type() {
+                    "endpoint" => {
+                        if let Some(tx) = endpoint_tx.take() {
+                            let _ = tx.send(event.data);
+                        }
+                    }
+                    "message" => match serde_json::from_str::<JsonValue>(&event.data) {
+                        Ok(message) => {
+                            if message_tx.send(message).is_err() {
+                                debug!("Message channel closed for {}", reader_alias);
+                                return;
+                            }
+                        }
+                        Err(e) => warn!("Invalid SSE message from {}: {}", reader_alias, e),
+                    },
+                    other => debug!("Ignoring SSE event '{}' from {}", other, reader_alias),
+                }
+            }
+        }
+
+        debug!("SSE stream finished for {}", reader_alias);
+    });
+
+    let endpoint = match tokio::time::timeout(timeout, endpoint_rx).await {
+        Ok(Ok(endpoint)) => endpoint,
+        Ok(Err(_)) => {
+            reader.abort();
+            return Err(UpstreamError::ProtocolError(
+                "Event stream closed before sending an endpoint event".to_string(),
+            ));
+        }
+        Err(_) => {
+            reader.abort();
+            return Err(UpstreamError::Timeout(
+                "No endpoint event received".to_string(),
+            ));
+        }
+    };
+
+    // The endpoint may be relative to the stream URL
+    let endpoint = match base.join(endpoint.trim()) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            reader.abort();
+            return Err(UpstreamError::ProtocolError(format!(
+                "Invalid endpoint URL: {}",
+                e
+            )));
+        }
+    };
+
+    debug!("HTTP+SSE endpoint for {}: {}", alias, endpoint);
+
+    Ok(LegacySseConnection {
+        writer: SsePostWriter {
+            endpoint: endpoint.to_string(),
+            bearer_token,
+            http_client,
+        },
+        messages: message_rx,
+        reader,
+    })
+}
+
+/// Convert a JSON-RPC response object into its result
+fn response_result(message: JsonValue) -> Result<JsonValue> {
+    let response: McpResponse = serde_json::from_value(message)
+        .map_err(|e| UpstreamError::ProtocolError(format!("Invalid JSON-RPC response: {}", e)))?;
+
+    if let Some(error) = response.error {
+        return Err(UpstreamError::RequestFailed(format!(
+            "Server error: {}",
+            error
+        )));
+    }
+
+    response
+        .result
+        .ok_or_else(|| UpstreamError::ProtocolError("No result in response".to_string()))
+}
+
+/// Apply a timeout to an upstream operation
+pub(crate) async fn with_timeout<T>(
+    duration: Duration,
+    what: &str,
+    fut: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::time::timeout(duration, fut)
+        .await
+        .map_err(|_| UpstreamError::Timeout(format!("{} request timed out", what)))?
+}
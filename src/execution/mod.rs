@@ -6,11 +6,18 @@
 //! - Validation and tracing
 //! - Timeout enforcement
 
+pub mod http;
+pub mod rpc;
 pub mod sandbox;
+pub mod supervisor;
 pub mod upstream;
 pub mod wasm;
+pub mod workflow;
 
+use crate::core::persistence::{self, PersistenceLayer};
+use crate::core::policy::PolicyEngine;
 use crate::core::registry::Registry;
+use crate::core::validation;
 use crate::core::{BundledTool, CallableId, CallableKind, ToolResult, ToolResultContent};
 use crate::execution::sandbox::SandboxConfigOverride;
 use serde::{Deserialize, Serialize};
@@ -22,6 +29,8 @@ use tracing::{debug, error, info, warn};
 use upstream::UpstreamManager;
 use uuid::Uuid;
 
+pub use workflow::{CallableExecutor, WorkflowDefinition, WorkflowEngine};
+
 #[derive(Error, Debug)]
 pub enum RuntimeError {
     #[error("Callable not found: {0}")]
@@ -58,6 +67,8 @@ pub struct ExecContext {
     pub arguments: serde_json::Value,
     pub timeout_ms: Option<u64>,
     pub trace_enabled: bool,
+    /// MCP session charged for rate limits; `None` for the default session
+    pub session: Option<String>,
 }
 
 /// Execution trace step
@@ -107,14 +118,18 @@ pub struct Runtime {
     registry: Arc<Registry>,
     upstream_manager: Arc<UpstreamManager>,
     sandbox_config: sandbox::SandboxConfig,
+    workflow_engine: WorkflowEngine,
+    persistence: Option<Arc<PersistenceLayer>>,
 }
 
 impl Runtime {
     pub fn new(registry: Arc<Registry>, upstream_manager: Arc<UpstreamManager>) -> Self {
         Runtime {
+            workflow_engine: WorkflowEngine::new(registry.clone()),
             registry,
             upstream_manager,
             sandbox_config: sandbox::SandboxConfig::default(),
+            persistence: None,
         }
     }
 
@@ -125,20 +140,74 @@ impl Runtime {
         sandbox_config: sandbox::SandboxConfig,
     ) -> Self {
         Runtime {
+            workflow_engine: WorkflowEngine::new(registry.clone()),
             registry,
             upstream_manager,
             sandbox_config,
+            persistence: None,
         }
     }
 
+    /// Limit the number of calls a single workflow skill run may make
+    pub fn with_max_calls_per_skill(mut self, max_calls: usize) -> Self {
+        self.workflow_engine = self.workflow_engine.with_max_calls(max_calls);
+        self
+    }
+
+    /// Apply the server policy to calls made by workflow skills
+    pub fn with_policy_engine(mut self, policy_engine: Arc<PolicyEngine>) -> Self {
+        self.workflow_engine = self.workflow_engine.with_policy_engine(policy_engine);
+        self
+    }
+
+    /// Record every execution in the persistence layer's history
+    pub fn with_persistence(mut self, persistence: Arc<PersistenceLayer>) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
     /// Execute a callable
     pub async fn execute(&self, ctx: ExecContext) -> Result<ToolResult> {
+        let started_at = chrono::Utc::now();
         let mut trace = if ctx.trace_enabled {
             Some(ExecutionTrace::new())
         } else {
             None
         };
 
+        let outcome = self.execute_inner(&ctx, trace.as_mut()).await;
+
+        // Complete trace
+        if let Some(ref mut trace) = trace {
+            trace.complete();
+        }
+
+        self.record_history(&ctx, &outcome, started_at, trace.as_ref())
+            .await;
+
+        let result = outcome?;
+
+        // Add trace to result if enabled
+        if let Some(trace) = trace {
+            let mut result_with_trace = result;
+            if let Some(ref mut structured) = result_with_trace.structured_content {
+                if let Some(obj) = structured.as_object_mut() {
+                    obj.insert("trace".to_string(), serde_json::to_value(trace).unwrap());
+                }
+            } else {
+                result_with_trace.structured_content = Some(serde_json::json!({ "trace": trace }));
+            }
+            return Ok(result_with_trace);
+        }
+
+        Ok(result)
+    }
+
+    async fn execute_inner(
+        &self,
+        ctx: &ExecContext,
+        trace: Option<&mut ExecutionTrace>,
+    ) -> Result<ToolResult> {
         info!("Executing callable: {}", ctx.callable_id.as_str());
 
         // Get callable record
@@ -154,77 +223,105 @@ impl Runtime {
         let timeout_duration = ctx.timeout_ms.map(Duration::from_millis);
 
         // Execute based on callable kind
-        let result = match record.kind {
+        match record.kind {
             CallableKind::Tool => {
                 if let Some(timeout) = timeout_duration {
-                    tokio::time::timeout(timeout, self.execute_tool(&ctx, &record))
+                    tokio::time::timeout(timeout, self.execute_tool(ctx, &record))
                         .await
                         .map_err(|_| RuntimeError::Timeout(ctx.timeout_ms.unwrap()))?
                 } else {
-                    self.execute_tool(&ctx, &record).await
+                    self.execute_tool(ctx, &record).await
                 }
             }
             CallableKind::Skill => {
                 if let Some(timeout) = timeout_duration {
-                    tokio::time::timeout(timeout, self.execute_skill(&ctx, &record, trace.as_mut()))
+                    tokio::time::timeout(timeout, self.execute_skill(ctx, &record, trace))
                         .await
                         .map_err(|_| RuntimeError::Timeout(ctx.timeout_ms.unwrap()))?
                 } else {
-                    self.execute_skill(&ctx, &record, trace.as_mut()).await
+                    self.execute_skill(ctx, &record, trace).await
                 }
             }
-        }?;
-
-        // Complete trace
-        if let Some(ref mut trace) = trace {
-            trace.complete();
         }
+    }
 
-        // Add trace to result if enabled
-        if let Some(trace) = trace {
-            let mut result_with_trace = result;
-            if let Some(ref mut structured) = result_with_trace.structured_content {
-                if let Some(obj) = structured.as_object_mut() {
-                    obj.insert("trace".to_string(), serde_json::to_value(trace).unwrap());
-                }
-            } else {
-                result_with_trace.structured_content = Some(serde_json::json!({ "trace": trace }));
-            }
-            return Ok(result_with_trace);
-        }
+    /// Write an execution to history; failures are logged, never surfaced
+    async fn record_history(
+        &self,
+        ctx: &ExecContext,
+        outcome: &Result<ToolResult>,
+        started_at: chrono::DateTime<chrono::Utc>,
+        trace: Option<&ExecutionTrace>,
+    ) {
+        let Some(persistence) = &self.persistence else {
+            return;
+        };
 
-        Ok(result)
+        let completed_at = chrono::Utc::now();
+        let (result, is_error) = match outcome {
+            Ok(result) => (serde_json::to_value(result).ok(), result.is_error),
+            Err(e) => (Some(serde_json::json!({ "error": e.to_string() })), true),
+        };
+        let execution_id = trace
+            .map(|t| t.execution_id.clone())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let trace = trace.and_then(|t| serde_json::to_value(t).ok());
+
+        if let Err(e) = persistence
+            .record_execution(
+                &execution_id,
+                &ctx.callable_id,
+                &persistence::redact_arguments(&ctx.arguments),
+                result.as_ref(),
+                is_error,
+                Some((completed_at - started_at).num_milliseconds().max(0) as u64),
+                started_at,
+                Some(completed_at),
+                trace.as_ref(),
+            )
+            .await
+        {
+            warn!("Failed to record execution history: {}", e);
+        }
     }
 
     /// Validate arguments against JSON schema
-    fn validate_arguments(
+    pub fn validate_arguments(
         &self,
         schema: &serde_json::Value,
         arguments: &serde_json::Value,
     ) -> Result<()> {
-        // Basic validation: check required fields
-        if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
-            if let Some(args_obj) = arguments.as_object() {
-                for req in required {
-                    if let Some(req_str) = req.as_str() {
-                        if !args_obj.contains_key(req_str) {
-                            return Err(RuntimeError::ValidationFailed(format!(
-                                "Missing required argument: {}",
-                                req_str
-                            )));
-                        }
-                    }
-                }
-            } else {
-                return Err(RuntimeError::ValidationFailed(
-                    "Arguments must be an object".to_string(),
-                ));
-            }
+        validate_arguments(schema, arguments)
+    }
+
+    /// Validate structured content against a tool's output schema
+    ///
+    /// Error results are left alone. Otherwise a missing or non-conforming
+    /// `structured_content` turns the result into an error that keeps the
+    /// upstream content and lists the violations first.
+    pub fn validate_output(&self, schema: &serde_json::Value, result: &mut ToolResult) {
+        if result.is_error {
+            return;
         }
 
-        // In a full implementation, use jsonschema crate for complete validation
-        debug!("Arguments validated successfully");
-        Ok(())
+        let message = match &result.structured_content {
+            None => "no structured content".to_string(),
+            Some(structured) => {
+                let violations = validation::validate(schema, structured);
+                if violations.is_empty() {
+                    return;
+                }
+                validation::format_violations(&violations)
+            }
+        };
+        warn!("Tool output does not match its output schema: {}", message);
+        result.content.insert(
+            0,
+            ToolResultContent::Text {
+                text: format!("Output schema validation failed: {}", message),
+            },
+        );
+        result.is_error = true;
     }
 
     /// Execute a tool (proxy to upstream)
@@ -251,90 +348,25 @@ impl Runtime {
         );
 
         // Call upstream server
+        let timeout = ctx
+            .timeout_ms
+            .map_or(upstream::DEFAULT_CALL_TIMEOUT, Duration::from_millis);
         let result = self
             .upstream_manager
-            .call_tool(server, tool_name, ctx.arguments.clone())
+            .call_tool_with_timeout(server, tool_name, ctx.arguments.clone(), timeout)
             .await
             .map_err(|e| RuntimeError::UpstreamError(e.to_string()))?;
 
-        // Convert upstream JSON response to ToolResult
-        // MCP tools/call returns { content: [...], isError?: bool }
-        let is_error = result
-            .get("isError")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-
-        let content = if let Some(content_array) = result.get("content").and_then(|v| v.as_array())
-        {
-            content_array
-                .iter()
-                .filter_map(|item| {
-                    if let Some(item_type) = item.get("type").and_then(|v| v.as_str()) {
-                        match item_type {
-                            "text" => {
-                                let text = item.get("text").and_then(|v| v.as_str()).unwrap_or("");
-                                Some(ToolResultContent::Text {
-                                    text: text.to_string(),
-                                })
-                            }
-                            "image" => {
-                                let data = item.get("data").and_then(|v| v.as_str()).unwrap_or("");
-                                let mime_type = item
-                                    .get("mimeType")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("image/png")
-                                    .to_string();
-                                Some(ToolResultContent::Image {
-                                    data: data.to_string(),
-                                    mime_type,
-                                })
-                            }
-                            "resource" => {
-                                let uri = item.get("uri").and_then(|v| v.as_str()).unwrap_or("");
-                                let mime_type: Option<&str> =
-                                    item.get("mimeType").and_then(|v| v.as_str());
-                                let text: Option<&str> = item.get("text").and_then(|v| v.as_str());
-                                let blob: Option<&str> = item.get("blob").and_then(|v| v.as_str());
-
-                                Some(ToolResultContent::Resource {
-                                    resource: crate::core::ResourceContent {
-                                        uri: uri.to_string(),
-                                        mime_type: mime_type.map(|s: &str| s.to_string()),
-                                        text: text.map(|s: &str| s.to_string()),
-                                        blob: blob.map(|s: &str| s.to_string()),
-                                    },
-                                })
-                            }
-                            _ => None,
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        } else {
-            // Fallback: wrap entire result as text
-            vec![ToolResultContent::Text {
-                text: result.to_string(),
-            }]
-        };
-
-        if content.is_empty() && !is_error {
+        let mut result = ToolResult::from_call_result(result);
+        if result.content.is_empty() && result.structured_content.is_none() && !result.is_error {
             error!("Tool returned empty content, treating as error");
-            return Ok(ToolResult {
-                content: vec![ToolResultContent::Text {
-                    text: "Tool returned no content".to_string(),
-                }],
-                structured_content: Some(result),
-                is_error: true,
-            });
+            return Ok(ToolResult::error("Tool returned no content".to_string()));
         }
 
-        Ok(ToolResult {
-            content,
-            structured_content: Some(result),
-            is_error,
-        })
+        if let Some(schema) = &record.output_schema {
+            self.validate_output(schema, &mut result);
+        }
+        Ok(result)
     }
 
     /// Execute a skill (workflow orchestration)
@@ -354,6 +386,29 @@ impl Runtime {
 
         info!("Executing skill workflow: {}", record.fq_name);
 
+        // Workflow skills run their steps directly
+        if let Some(dir) = record.skill_directory.as_deref() {
+            if workflow::find_workflow_file(dir).is_some() {
+                let skill = crate::storage::agent_skills::AgentSkill::from_directory(dir)
+                    .await
+                    .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+                if let Some(definition) = &skill.workflow {
+                    let policy = skill.to_skill_manifest().tool_policy;
+                    return self
+                        .workflow_engine
+                        .execute(
+                            self,
+                            definition,
+                            &ctx.arguments,
+                            &policy,
+                            ctx.session.as_deref(),
+                            trace,
+                        )
+                        .await;
+                }
+            }
+        }
+
         // Check if this skill has bundled tools
         if !record.bundled_tools.is_empty() {
             // For now, execute the first bundled tool as the entrypoint
@@ -572,36 +627,33 @@ impl Runtime {
     }
 }
 
-/// Workflow DSL interpreter (placeholder)
-pub struct WorkflowEngine {
-    #[allow(dead_code)]
-    registry: Arc<Registry>,
+#[async_trait::async_trait]
+impl CallableExecutor for Runtime {
+    async fn execute_callable(&self, ctx: ExecContext) -> Result<ToolResult> {
+        self.execute(ctx).await
+    }
 }
 
-impl WorkflowEngine {
-    pub fn new(registry: Arc<Registry>) -> Self {
-        WorkflowEngine { registry }
+/// Validate tool arguments against a callable's input schema
+pub(crate) fn validate_arguments(
+    schema: &serde_json::Value,
+    arguments: &serde_json::Value,
+) -> Result<()> {
+    // Tool arguments are always an object, whatever the schema says
+    if !arguments.is_object() {
+        return Err(RuntimeError::ValidationFailed(format!(
+            "/: expected object, got {}",
+            validation::json_type_name(arguments)
+        )));
     }
 
-    /// Execute a workflow definition
-    pub async fn execute(
-        &self,
-        _workflow: &serde_json::Value,
-        _arguments: &serde_json::Value,
-    ) -> Result<ToolResult> {
-        // Full implementation would:
-        // - Parse workflow YAML/JSON
-        // - Execute steps in order
-        // - Handle call, assign, map, branch, retry, validate, emit constructs
-        // - Maintain variable context
-        // - Enforce tool allowlists
-
-        Ok(ToolResult {
-            content: vec![ToolResultContent::Text {
-                text: "Workflow executed (placeholder)".to_string(),
-            }],
-            structured_content: None,
-            is_error: false,
-        })
+    let violations = validation::validate(schema, arguments);
+    if !violations.is_empty() {
+        return Err(RuntimeError::ValidationFailed(
+            validation::format_violations(&violations),
+        ));
     }
+
+    debug!("Arguments validated successfully");
+    Ok(())
 }
@@ -0,0 +1,345 @@
+//! JSON-RPC client for message-oriented upstream connections
+//!
+//! Stdio and HTTP+SSE upstreams carry every request and response over a single
+//! message stream. [`RpcClient`] multiplexes concurrent requests over it:
+//! - Ids come from a per-connection counter, so concurrent requests never collide
+//! - A router task matches responses to pending requests by id, and a writer
+//!   task sends messages in order, so a slow write never stalls routing
+//! - A request that times out, or whose caller stops waiting, is dropped from
+//!   the pending map and cancelled upstream with `notifications/cancelled`
+//! - At most `max_concurrency` requests are in flight; the rest queue in
+//!   arrival order, and time spent queued counts toward their timeout
+
+use crate::execution::upstream::{McpRequest, McpResponse, Result, UpstreamError};
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::ChildStdin;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// Requests in flight per connection unless the upstream sets `max_concurrency`
+pub const DEFAULT_MAX_CONCURRENCY: usize = 16;
+
+/// Request tracker for correlating responses
+type ResponseSender = oneshot::Sender<Result<JsonValue>>;
+
+/// Outgoing half of a message-oriented upstream connection
+///
+/// Implemented by the stdio pipe and the HTTP+SSE POST endpoint so both share
+/// the same request router.
+#[async_trait]
+pub(crate) trait MessageWriter: Send + 'static {
+    async fn write_message(&mut self, message: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl MessageWriter for ChildStdin {
+    async fn write_message(&mut self, message: &str) -> Result<()> {
+        self.write_all(message.as_bytes()).await.map_err(|e| {
+            UpstreamError::ConnectionFailed(format!("Failed to write to stdin: {}", e))
+        })?;
+        self.write_all(b"\n").await.map_err(|e| {
+            UpstreamError::ConnectionFailed(format!("Failed to write newline: {}", e))
+        })?;
+        self.flush()
+            .await
+            .map_err(|e| UpstreamError::ConnectionFailed(format!("Failed to flush: {}", e)))
+    }
+}
+
+/// Work for the router task
+enum Command {
+    Request(McpRequest, ResponseSender),
+    Notify(McpRequest),
+    /// Forget a pending request, telling the server why if `reason` is set
+    Cancel {
+        id: JsonValue,
+        reason: Option<&'static str>,
+    },
+    /// Fail a pending request whose message could not be written
+    Failed {
+        id: JsonValue,
+        error: UpstreamError,
+    },
+}
+
+/// A serialized message for the writer task
+struct Outgoing {
+    json: String,
+    /// Request id to fail if the write fails; other messages only log
+    request: Option<JsonValue>,
+}
+
+/// Client half of a JSON-RPC connection
+///
+/// Clones share the id counter, the pending requests, and the concurrency limit.
+#[derive(Clone)]
+pub(crate) struct RpcClient {
+    commands: mpsc::UnboundedSender<Command>,
+    next_id: Arc<AtomicU64>,
+    permits: Arc<Semaphore>,
+    max_concurrency: usize,
+}
+
+impl RpcClient {
+    /// Spawn the router task for a connection and return a client for it
+    ///
+    /// The connection closes when `messages` ends; pending requests then fail.
+    pub(crate) fn spawn<W: MessageWriter>(
+        alias: String,
+        writer: W,
+        messages: mpsc::UnboundedReceiver<JsonValue>,
+        max_concurrency: usize,
+    ) -> Self {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (outgoing, outgoing_rx) = mpsc::unbounded_channel();
+        tokio::spawn(write(alias.clone(), writer, outgoing_rx, commands.clone()));
+        tokio::spawn(route(alias, outgoing, command_rx, messages));
+
+        let max_concurrency = max_concurrency.max(1);
+        RpcClient {
+            commands,
+            next_id: Arc::new(AtomicU64::new(1)),
+            permits: Arc::new(Semaphore::new(max_concurrency)),
+            max_concurrency,
+        }
+    }
+
+    /// Send a request and wait up to `timeout` for its response
+    pub(crate) async fn request(
+        &self,
+        method: &str,
+        params: Option<JsonValue>,
+        timeout: Duration,
+    ) -> Result<JsonValue> {
+        let deadline = Instant::now() + timeout;
+
+        let _permit = tokio::time::timeout_at(deadline, self.permits.acquire())
+            .await
+            .map_err(|_| {
+                UpstreamError::Timeout(format!(
+                    "{} request timed out queued behind {} requests in flight",
+                    method, self.max_concurrency
+                ))
+            })?
+            .map_err(|_| connection_closed())?;
+
+        let id = JsonValue::from(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let (response_tx, response_rx) = oneshot::channel();
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: id.clone(),
+            method: method.to_string(),
+            params,
+        };
+        self.commands
+            .send(Command::Request(request, response_tx))
+            .map_err(|_| connection_closed())?;
+
+        // Servers must not be asked to cancel `initialize`
+        let mut pending = PendingRequest {
+            commands: &self.commands,
+            id: Some(id),
+            reason: (method != "initialize").then_some("request cancelled by client"),
+        };
+        match tokio::time::timeout_at(deadline, response_rx).await {
+            Ok(response) => {
+                pending.id = None;
+                response.map_err(|_| connection_closed())?
+            }
+            Err(_) => {
+                if pending.reason.is_some() {
+                    pending.reason = Some("request timed out");
+                }
+                Err(UpstreamError::Timeout(format!(
+                    "{} request timed out",
+                    method
+                )))
+            }
+        }
+    }
+
+    /// Send a notification; it has no response to wait for
+    pub(crate) fn notify(&self, method: &str, params: Option<JsonValue>) -> Result<()> {
+        let notification = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: JsonValue::Null,
+            method: method.to_string(),
+            params,
+        };
+        self.commands
+            .send(Command::Notify(notification))
+            .map_err(|_| connection_closed())
+    }
+}
+
+fn connection_closed() -> UpstreamError {
+    UpstreamError::ConnectionFailed("Connection closed".to_string())
+}
+
+/// Cancels a request when dropped before its response arrived
+struct PendingRequest<'a> {
+    commands: &'a mpsc::UnboundedSender<Command>,
+    id: Option<JsonValue>,
+    reason: Option<&'static str>,
+}
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            let _ = self.commands.send(Command::Cancel {
+                id,
+                reason: self.reason,
+            });
+        }
+    }
+}
+
+/// Write queued messages in order until the router stops
+///
+/// A request whose write fails is reported back to the router, which fails
+/// its caller.
+async fn write<W: MessageWriter>(
+    alias: String,
+    mut writer: W,
+    mut outgoing: mpsc::UnboundedReceiver<Outgoing>,
+    commands: mpsc::UnboundedSender<Command>,
+) {
+    while let Some(message) = outgoing.recv().await {
+        let Err(error) = writer.write_message(&message.json).await else {
+            continue;
+        };
+        match message.request {
+            Some(id) => {
+                let _ = commands.send(Command::Failed { id, error });
+            }
+            None => warn!("Failed to write to {}: {}", alias, error),
+        }
+    }
+}
+
+/// Queue requests and route incoming messages until the connection closes
+///
+/// Responses are matched to pending requests by id. Server-initiated requests
+/// are answered (`ping`) or rejected, and notifications are logged.
+async fn route(
+    alias: String,
+    outgoing: mpsc::UnboundedSender<Outgoing>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    mut messages: mpsc::UnboundedReceiver<JsonValue>,
+) {
+    let mut pending_requests: HashMap<JsonValue, ResponseSender> = HashMap::new();
+    // The writer runs until the router drops `outgoing`, so sends do not fail
+    let send = |json: String, request: Option<JsonValue>| {
+        let _ = outgoing.send(Outgoing { json, request });
+    };
+
+    loop {
+        tokio::select! {
+            Some(command) = commands.recv() => match command {
+                Command::Request(request, response_tx) => {
+                    let json = match serde_json::to_string(&request) {
+                        Ok(j) => j,
+                        Err(e) => {
+                            let _ = response_tx.send(Err(UpstreamError::ProtocolError(
+                                format!("Failed to serialize request: {}", e)
+                            )));
+                            continue;
+                        }
+                    };
+
+                    send(json, Some(request.id.clone()));
+                    pending_requests.insert(request.id, response_tx);
+                }
+                Command::Notify(notification) => match serde_json::to_string(&notification) {
+                    Ok(json) => send(json, None),
+                    Err(e) => warn!("Failed to send {} to {}: {}", notification.method, alias, e),
+                },
+                Command::Cancel { id, reason } => {
+                    // Already answered; nothing to cancel
+                    if pending_requests.remove(&id).is_none() {
+                        continue;
+                    }
+                    debug!("Cancelled request {} to {}", id, alias);
+                    let Some(reason) = reason else {
+                        continue;
+                    };
+                    let notification = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/cancelled",
+                        "params": { "requestId": id, "reason": reason }
+                    });
+                    send(notification.to_string(), None);
+                }
+                Command::Failed { id, error } => {
+                    if let Some(sender) = pending_requests.remove(&id) {
+                        let _ = sender.send(Err(error));
+                    }
+                }
+            },
+            message = messages.recv() => {
+                // The connection is gone; dropping pending senders fails their calls
+                let Some(message) = message else {
+                    break;
+                };
+                let method = message.get("method").and_then(|m| m.as_str());
+                let id = message.get("id").cloned().unwrap_or(JsonValue::Null);
+
+                match (method, id.is_null()) {
+                    // Server-initiated request
+                    (Some(method), false) => {
+                        let reply = if method == "ping" {
+                            serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+                        } else {
+                            debug!("Rejecting server request from {}: {}", alias, method);
+                            serde_json::json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "error": { "code": -32601, "message": "Method not found" }
+                            })
+                        };
+                        send(reply.to_string(), None);
+                    }
+                    // Notification
+                    (Some(method), true) => {
+                        debug!("Notification from {}: {}", alias, method);
+                    }
+                    // Response to one of our requests
+                    (None, _) => {
+                        let response = match serde_json::from_value::<McpResponse>(message) {
+                            Ok(response) => response,
+                            Err(e) => {
+                                warn!("Failed to parse response from {}: {}", alias, e);
+                                continue;
+                            }
+                        };
+
+                        let Some(sender) = pending_requests.remove(&response.id) else {
+                            debug!("Dropping response to unknown request {} from {}", response.id, alias);
+                            continue;
+                        };
+                        if let Some(error) = response.error {
+                            let _ = sender.send(Err(UpstreamError::RequestFailed(
+                                error.to_string()
+                            )));
+                        } else if let Some(result) = response.result {
+                            let _ = sender.send(Ok(result));
+                        } else {
+                            let _ = sender.send(Err(UpstreamError::ProtocolError(
+                                "Response has no result or error".to_string()
+                            )));
+                        }
+                    }
+                }
+            }
+            else => break,
+        }
+    }
+    debug!("Request handler finished for {}", alias);
+}
@@ -0,0 +1,128 @@
+//! Supervision of stdio upstream processes
+//!
+//! A stdio upstream is a child process the manager spawned. When it exits or
+//! closes its stdout, the session is marked `Degraded` and the process is
+//! restarted with exponential backoff. Crashing more than
+//! [`RestartPolicy::max_restarts`] times within [`RestartPolicy::window_secs`]
+//! is treated as a crash loop: the session is marked `Failed` and left alone
+//! until it is reconnected by hand.
+//!
+//! The child's stderr is kept in a bounded [`StderrTail`] that survives
+//! restarts, so the output leading up to a crash can be inspected.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Lines of stderr kept per upstream
+pub const STDERR_TAIL_LINES: usize = 50;
+
+/// Longest stderr line kept, in characters
+const STDERR_LINE_MAX: usize = 1000;
+
+/// How long a process that closed stdout gets to exit before it is killed
+pub(crate) const EXIT_GRACE: Duration = Duration::from_secs(1);
+
+/// Restart policy for stdio upstreams that exit unexpectedly
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestartPolicy {
+    /// Restart crashed processes at all
+    pub enabled: bool,
+    /// Delay before the first restart; doubled after each failed attempt
+    pub initial_backoff_ms: u64,
+    /// Upper bound on the delay between attempts
+    pub max_backoff_ms: u64,
+    /// Crashes tolerated within `window_secs` before giving up
+    pub max_restarts: u32,
+    pub window_secs: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            enabled: true,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            max_restarts: 5,
+            window_secs: 300,
+        }
+    }
+}
+
+impl RestartPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == RestartPolicy::default()
+    }
+
+    /// Delay before restart attempt `attempt` (0-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+/// Recent crashes of one upstream
+#[derive(Debug, Default)]
+pub(crate) struct CrashHistory {
+    recent: VecDeque<Instant>,
+    /// Restarts since the upstream was added
+    pub(crate) restarts: u32,
+    /// Why the process last stopped
+    pub(crate) last_exit: Option<String>,
+}
+
+impl CrashHistory {
+    /// Record a crash; returns whether the policy allows another restart
+    pub(crate) fn record(&mut self, policy: &RestartPolicy, reason: String) -> bool {
+        let now = Instant::now();
+        let window = Duration::from_secs(policy.window_secs);
+        while self
+            .recent
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > window)
+        {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(now);
+        self.last_exit = Some(reason);
+
+        policy.enabled && self.recent.len() <= policy.max_restarts as usize
+    }
+
+    /// Forget recent crashes, e.g. after a manual reconnect
+    pub(crate) fn clear_window(&mut self) {
+        self.recent.clear();
+    }
+}
+
+/// The last [`STDERR_TAIL_LINES`] lines a process wrote to stderr
+#[derive(Debug, Clone, Default)]
+pub struct StderrTail {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl StderrTail {
+    pub fn push(&self, line: &str) {
+        let line = match line.char_indices().nth(STDERR_LINE_MAX) {
+            Some((end, _)) => format!("{}...", &line[..end]),
+            None => line.to_string(),
+        };
+        let mut lines = self.lines.lock();
+        if lines.len() == STDERR_TAIL_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Buffered lines, oldest first
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().iter().cloned().collect()
+    }
+}
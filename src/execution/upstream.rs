@@ -8,17 +8,23 @@
 
 use crate::core::registry::{Registry, ServerHealth, ServerInfo};
 use crate::core::{
-    CallableId, CallableKind, CallableRecord, CostHints, RiskTier, SchemaDigest, ToolDefinition,
+    CallableId, CallableKind, CallableRecord, CostHints, SchemaDigest, ToolAnnotations,
+    ToolDefinition,
 };
+use crate::execution::http::{open_legacy_sse, with_timeout, StreamableHttpClient};
+use crate::execution::rpc::{RpcClient, DEFAULT_MAX_CONCURRENCY};
 use crate::execution::sandbox::SandboxConfigOverride;
+use crate::execution::supervisor::{CrashHistory, RestartPolicy, StderrTail, EXIT_GRACE};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::sync::{mpsc, oneshot, watch, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
 #[derive(Error, Debug)]
@@ -89,6 +95,44 @@ pub struct UpstreamConfig {
     pub sandbox_config: Option<SandboxConfigOverride>,
 
     pub description: Option<String>,
+
+    /// Corrections to the tool annotations the server reports, keyed by tool
+    /// name or `*` for every tool; set hints replace the server's
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, ToolAnnotations>,
+
+    /// Restarts of a stdio upstream whose process exits unexpectedly
+    #[serde(default, skip_serializing_if = "RestartPolicy::is_default")]
+    pub restart: RestartPolicy,
+
+    /// Requests in flight at once over stdio or HTTP+SSE; more are queued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+}
+
+impl UpstreamConfig {
+    /// A tool's annotations with the configured overrides applied
+    ///
+    /// Overrides for `*` apply first, then those for the tool's name.
+    pub fn tool_annotations(
+        &self,
+        tool: &str,
+        declared: Option<ToolAnnotations>,
+    ) -> Option<ToolAnnotations> {
+        let overrides: Vec<&ToolAnnotations> = ["*", tool]
+            .iter()
+            .filter_map(|key| self.annotations.get(*key))
+            .collect();
+        if declared.is_none() && overrides.is_empty() {
+            return None;
+        }
+
+        let mut annotations = declared.unwrap_or_default();
+        for overrides in overrides {
+            annotations.apply(overrides);
+        }
+        Some(annotations)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,28 +143,47 @@ pub struct AuthConfig {
     pub token: Option<String>,
 }
 
+impl AuthConfig {
+    /// Resolve the bearer token, preferring an inline token over the env var
+    pub fn bearer_token(&self) -> Option<String> {
+        self.token
+            .clone()
+            .or_else(|| self.env.as_ref().and_then(|var| std::env::var(var).ok()))
+    }
+}
+
 /// MCP protocol messages
+///
+/// Notifications carry a null id, which is omitted on the wire.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct McpRequest {
-    jsonrpc: String,
-    id: JsonValue,
-    method: String,
+pub(crate) struct McpRequest {
+    pub(crate) jsonrpc: String,
+    #[serde(default, skip_serializing_if = "JsonValue::is_null")]
+    pub(crate) id: JsonValue,
+    pub(crate) method: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    params: Option<JsonValue>,
+    pub(crate) params: Option<JsonValue>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct McpResponse {
-    jsonrpc: String,
-    id: JsonValue,
+pub(crate) struct McpResponse {
+    pub(crate) jsonrpc: String,
+    pub(crate) id: JsonValue,
     #[serde(skip_serializing_if = "Option::is_none")]
-    result: Option<JsonValue>,
+    pub(crate) result: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<JsonValue>,
+    pub(crate) error: Option<JsonValue>,
 }
 
+/// How long a tool call waits for its result unless the caller sets a timeout
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// MCP protocol version offered to upstreams over every transport
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+
 /// Upstream connection state
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ConnectionState {
     Disconnected,
     Connecting,
@@ -129,21 +192,53 @@ pub enum ConnectionState {
     Failed,
 }
 
-/// Request tracker for correlating responses
-type ResponseSender = oneshot::Sender<Result<JsonValue>>;
-
 /// Upstream session
 struct UpstreamSession {
     config: UpstreamConfig,
     state: ConnectionState,
     last_ping: Option<chrono::DateTime<chrono::Utc>>,
     tools: Vec<CallableId>,
-    // For stdio: child process and request sender
-    process: Option<Child>,
-    request_tx: Option<mpsc::UnboundedSender<(JsonValue, McpRequest, ResponseSender)>>,
+    // For stdio: child process and JSON-RPC client
+    process: Option<StdioProcess>,
+    rpc: Option<RpcClient>,
+    // For stdio: bumped whenever a new process replaces the previous one
+    generation: watch::Sender<u64>,
+    // For stdio: task restarting the process when it dies
+    supervisor: Option<JoinHandle<()>>,
+    stderr: StderrTail,
+    crashes: CrashHistory,
+    // For Streamable HTTP: session-aware client
+    http: Option<Arc<StreamableHttpClient>>,
+    // For HTTP+SSE: task reading the server's event stream
+    sse_reader: Option<JoinHandle<()>>,
+}
+
+/// A running stdio upstream process
+struct StdioProcess {
+    child: Child,
+    /// Resolves when the process closes its stdout
+    closed: oneshot::Receiver<()>,
+}
+
+/// Snapshot of an upstream's connection, as reported by `list_upstreams`
+#[derive(Debug, Clone, Serialize)]
+pub struct UpstreamStatus {
+    pub alias: String,
+    pub transport: Transport,
+    pub state: ConnectionState,
+    pub tool_count: usize,
+    /// Automatic restarts since the upstream was added
+    pub restarts: u32,
+    /// Why the process last stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_exit: Option<String>,
+    /// Last lines the process wrote to stderr
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stderr: Vec<String>,
 }
 
 /// Upstream manager
+#[derive(Clone)]
 pub struct UpstreamManager {
     sessions: Arc<RwLock<HashMap<String, UpstreamSession>>>,
     registry: Arc<Registry>,
@@ -170,13 +265,20 @@ impl UpstreamManager {
             last_ping: None,
             tools: Vec::new(),
             process: None,
-            request_tx: None,
+            rpc: None,
+            generation: watch::Sender::new(0),
+            supervisor: None,
+            stderr: StderrTail::default(),
+            crashes: CrashHistory::default(),
+            http: None,
+            sse_reader: None,
         };
 
         self.sessions.write().await.insert(alias.clone(), session);
 
         // Connect and fetch tools
         self.connect(&alias).await?;
+        self.ensure_supervisor(&alias).await;
 
         Ok(())
     }
@@ -264,7 +366,7 @@ impl UpstreamManager {
             .args(&command[1..])
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
             .kill_on_drop(true)
             .spawn()
             .map_err(|e| UpstreamError::ConnectionFailed(format!("Failed to spawn: {}", e)))?;
@@ -279,12 +381,27 @@ impl UpstreamManager {
             .take()
             .ok_or_else(|| UpstreamError::ConnectionFailed("Failed to get stdout".to_string()))?;
 
-        // Channel for sending requests
-        let (request_tx, mut request_rx) =
-            mpsc::unbounded_channel::<(JsonValue, McpRequest, ResponseSender)>();
+        // Keep the tail of stderr for debugging crashes
+        if let Some(stderr) = child.stderr.take() {
+            let tail = {
+                let sessions = self.sessions.read().await;
+                sessions.get(&config.alias).map(|s| s.stderr.clone())
+            };
+            let alias = config.alias.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    debug!("{} stderr: {}", alias, line);
+                    if let Some(tail) = &tail {
+                        tail.push(&line);
+                    }
+                }
+            });
+        }
 
-        // Channel for responses from stdout reader
-        let (response_tx, mut response_rx) = mpsc::unbounded_channel::<McpResponse>();
+        // Channel for messages from the stdout reader
+        let (message_tx, message_rx) = mpsc::unbounded_channel::<JsonValue>();
+        let (closed_tx, closed) = oneshot::channel();
 
         // Spawn stdout reader task
         let alias = config.alias.clone();
@@ -297,9 +414,9 @@ impl UpstreamManager {
                     continue;
                 }
 
-                match serde_json::from_str::<McpResponse>(&line) {
-                    Ok(response) => {
-                        if response_tx.send(response).is_err() {
+                match serde_json::from_str::<JsonValue>(&line) {
+                    Ok(message) => {
+                        if message_tx.send(message).is_err() {
                             debug!("Response channel closed for {}", alias);
                             break;
                         }
@@ -310,137 +427,32 @@ impl UpstreamManager {
                 }
             }
             debug!("Stdout reader finished for {}", alias);
+            let _ = closed_tx.send(());
         });
 
-        // Spawn request writer and response router task
-        let alias = config.alias.clone();
-        tokio::spawn(async move {
-            let mut stdin = stdin;
-            let mut pending_requests: HashMap<JsonValue, ResponseSender> = HashMap::new();
-
-            loop {
-                tokio::select! {
-                    Some((id, request, response_tx)) = request_rx.recv() => {
-                        // Send request to stdin
-                        let json = match serde_json::to_string(&request) {
-                            Ok(j) => j,
-                            Err(e) => {
-                                let _ = response_tx.send(Err(UpstreamError::ProtocolError(
-                                    format!("Failed to serialize request: {}", e)
-                                )));
-                                continue;
-                            }
-                        };
-
-                        if let Err(e) = stdin.write_all(json.as_bytes()).await {
-                            let _ = response_tx.send(Err(UpstreamError::ConnectionFailed(
-                                format!("Failed to write to stdin: {}", e)
-                            )));
-                            break;
-                        }
+        let rpc = RpcClient::spawn(
+            config.alias.clone(),
+            stdin,
+            message_rx,
+            config.max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY),
+        );
+        initialize_session(&config.alias, &rpc).await?;
 
-                        if let Err(e) = stdin.write_all(b"\n").await {
-                            let _ = response_tx.send(Err(UpstreamError::ConnectionFailed(
-                                format!("Failed to write newline: {}", e)
-                            )));
-                            break;
-                        }
-
-                        if let Err(e) = stdin.flush().await {
-                            let _ = response_tx.send(Err(UpstreamError::ConnectionFailed(
-                                format!("Failed to flush: {}", e)
-                            )));
-                            break;
-                        }
-
-                        // Track pending request
-                        pending_requests.insert(id, response_tx);
-                    }
-                    Some(response) = response_rx.recv() => {
-                        // Route response to waiting request
-                        if let Some(sender) = pending_requests.remove(&response.id) {
-                            if let Some(error) = response.error {
-                                let _ = sender.send(Err(UpstreamError::RequestFailed(
-                                    error.to_string()
-                                )));
-                            } else if let Some(result) = response.result {
-                                let _ = sender.send(Ok(result));
-                            } else {
-                                let _ = sender.send(Err(UpstreamError::ProtocolError(
-                                    "Response has no result or error".to_string()
-                                )));
-                            }
-                        }
-                    }
-                    else => break,
-                }
-            }
-            debug!("Request handler finished for {}", alias);
-        });
-
-        // Send initialize request
-        let init_request = McpRequest {
-            jsonrpc: "2.0".to_string(),
-            id: JsonValue::from(1),
-            method: "initialize".to_string(),
-            params: Some(serde_json::json!({
-                "protocolVersion": "2024-11-05",
-                "capabilities": {
-                    "tools": {}
-                },
-                "clientInfo": {
-                    "name": "skills.rs",
-                    "version": "0.1.0"
-                }
-            })),
-        };
-
-        let (response_tx, response_rx) = oneshot::channel();
-        request_tx
-            .send((JsonValue::from(1), init_request, response_tx))
-            .map_err(|_| {
-                UpstreamError::ConnectionFailed("Failed to send initialize request".to_string())
-            })?;
-
-        // Wait for initialize response
-        let _init_response = tokio::time::timeout(std::time::Duration::from_secs(10), response_rx)
-            .await
-            .map_err(|_| UpstreamError::Timeout("Initialize request timed out".to_string()))?
-            .map_err(|_| {
-                UpstreamError::ConnectionFailed("Initialize response channel closed".to_string())
-            })??;
-
-        debug!("MCP initialize succeeded for {}", config.alias);
-
-        // Send initialized notification
-        let initialized_notif = McpRequest {
-            jsonrpc: "2.0".to_string(),
-            id: JsonValue::Null,
-            method: "notifications/initialized".to_string(),
-            params: None,
-        };
-
-        let (notif_tx, _notif_rx) = oneshot::channel();
-        request_tx
-            .send((JsonValue::Null, initialized_notif, notif_tx))
-            .map_err(|_| {
-                UpstreamError::ConnectionFailed(
-                    "Failed to send initialized notification".to_string(),
-                )
-            })?;
-
-        // Store session data
+        // Store session data; a replaced process is killed when dropped
         let alias = config.alias.clone();
         let mut sessions = self.sessions.write().await;
         if let Some(session) = sessions.get_mut(&alias) {
-            session.process = Some(child);
-            session.request_tx = Some(request_tx);
+            session.process = Some(StdioProcess { child, closed });
+            session.rpc = Some(rpc);
+            session
+                .generation
+                .send_modify(|generation| *generation += 1);
         }
 
         Ok(())
     }
 
-    /// Connect via HTTP
+    /// Connect via Streamable HTTP
     async fn connect_http(&self, config: &UpstreamConfig) -> Result<()> {
         let url = config
             .url
@@ -449,12 +461,23 @@ impl UpstreamManager {
 
         debug!("Connecting to HTTP endpoint: {}", url);
 
-        // In full implementation:
-        // - POST to /mcp/initialize
-        // - Handle session tokens
-        // - Store connection state
+        let client = Arc::new(StreamableHttpClient::new(
+            config.alias.clone(),
+            url.clone(),
+            config.auth.as_ref().and_then(|a| a.bearer_token()),
+            self.http_client.clone(),
+        ));
+
+        with_timeout(Duration::from_secs(10), "initialize", client.initialize()).await?;
+
+        let mut sessions = self.sessions.write().await;
+        if let Some(session) = sessions.get_mut(&config.alias) {
+            // Drop any previous session on the server before replacing it
+            if let Some(previous) = session.http.replace(client) {
+                tokio::spawn(async move { previous.terminate().await });
+            }
+        }
 
-        // Mock for now
         Ok(())
     }
 
@@ -467,12 +490,35 @@ impl UpstreamManager {
 
         debug!("Connecting to HTTP+SSE endpoint: {}", url);
 
-        // In full implementation:
-        // - POST to initialize endpoint
-        // - Open SSE connection for notifications
-        // - Handle bidirectional communication
+        let connection = open_legacy_sse(
+            &config.alias,
+            url,
+            config.auth.as_ref().and_then(|a| a.bearer_token()),
+            self.http_client.clone(),
+            Duration::from_secs(10),
+        )
+        .await?;
+
+        let rpc = RpcClient::spawn(
+            config.alias.clone(),
+            connection.writer,
+            connection.messages,
+            config.max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY),
+        );
+
+        if let Err(e) = initialize_session(&config.alias, &rpc).await {
+            connection.reader.abort();
+            return Err(e);
+        }
+
+        let mut sessions = self.sessions.write().await;
+        if let Some(session) = sessions.get_mut(&config.alias) {
+            if let Some(previous) = session.sse_reader.replace(connection.reader) {
+                previous.abort();
+            }
+            session.rpc = Some(rpc);
+        }
 
-        // Mock for now
         Ok(())
     }
 
@@ -491,8 +537,9 @@ impl UpstreamManager {
 
         // Fetch tools based on transport
         let tools = match config.transport {
-            Transport::Stdio => self.list_tools_stdio(alias).await?,
-            Transport::Http | Transport::HttpSse => self.list_tools_http(alias).await?,
+            Transport::Stdio | Transport::Http | Transport::HttpSse => {
+                self.list_tools(alias).await?
+            }
             Transport::AgentSkillsRepo | Transport::AgentSkillsFs => {
                 // Agent Skills don't provide MCP tools, they provide skills
                 // Skills are discovered by SkillStore directly
@@ -501,27 +548,41 @@ impl UpstreamManager {
             }
         };
 
-        // Remove old tools for this server
-        self.registry.remove_server(alias);
-
-        // Register new tools
-        let mut tool_ids = Vec::new();
+        // Build every record before touching the registry, so a bad tool leaves the old listing
+        let mut records = Vec::new();
         for tool_def in tools {
             let digest = SchemaDigest::from_schema(&tool_def.input_schema)
                 .map_err(|e| UpstreamError::ProtocolError(e.to_string()))?;
 
             let id = CallableId::tool(alias, &tool_def.name, digest.as_str());
 
+            // Risk and network use follow the (corrected) hints; no hints stay unknown
+            let annotations = config.tool_annotations(&tool_def.name, tool_def.annotations);
+            let risk_tier = annotations
+                .as_ref()
+                .map(ToolAnnotations::risk_tier)
+                .unwrap_or_default();
+            let cost_hints = CostHints {
+                network_required: annotations
+                    .as_ref()
+                    .is_some_and(ToolAnnotations::open_world),
+                ..CostHints::default()
+            };
+            let title = tool_def
+                .title
+                .or_else(|| annotations.as_ref().and_then(|a| a.title.clone()))
+                .unwrap_or_else(|| tool_def.name.clone());
+
             let record = CallableRecord {
-                id: id.clone(),
+                id,
                 kind: CallableKind::Tool,
                 fq_name: format!("{}.{}", alias, tool_def.name),
                 name: tool_def.name.clone(),
-                title: Some(tool_def.name.clone()),
+                title: Some(title),
                 description: tool_def.description.clone(),
                 tags: vec![alias.to_string()],
                 input_schema: tool_def.input_schema.clone(),
-                output_schema: None,
+                output_schema: tool_def.output_schema,
                 schema_digest: digest,
                 server_alias: Some(alias.to_string()),
                 upstream_tool_name: Some(tool_def.name.clone()),
@@ -530,19 +591,20 @@ impl UpstreamManager {
                 skill_directory: None,
                 bundled_tools: vec![],
                 additional_files: vec![],
-                cost_hints: CostHints::default(),
-                risk_tier: RiskTier::Unknown,
+                cost_hints,
+                risk_tier,
+                annotations,
                 last_seen: chrono::Utc::now(),
                 sandbox_config: None,
             };
 
-            self.registry
-                .register(record)
-                .map_err(|e| UpstreamError::ProtocolError(e.to_string()))?;
-
-            tool_ids.push(id);
+            records.push(record);
         }
 
+        // Swap in the new listing without emptying the server in between
+        let tool_ids: Vec<CallableId> = records.iter().map(|r| r.id.clone()).collect();
+        self.registry.replace_server(alias, records);
+
         // Update session
         {
             let mut sessions = self.sessions.write().await;
@@ -564,176 +626,80 @@ impl UpstreamManager {
         Ok(())
     }
 
-    /// List tools via stdio
-    async fn list_tools_stdio(&self, alias: &str) -> Result<Vec<ToolDefinition>> {
-        let request_tx = {
-            let sessions = self.sessions.read().await;
-            sessions
-                .get(alias)
-                .and_then(|s| s.request_tx.clone())
-                .ok_or_else(|| UpstreamError::ServerNotFound(alias.to_string()))?
-        };
-
-        let request_id = JsonValue::from(format!("list_tools_{}", chrono::Utc::now().timestamp()));
-        let request = McpRequest {
-            jsonrpc: "2.0".to_string(),
-            id: request_id.clone(),
-            method: "tools/list".to_string(),
-            params: None,
-        };
+    /// List tools from an upstream, following `nextCursor` pagination
+    async fn list_tools(&self, alias: &str) -> Result<Vec<ToolDefinition>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
 
-        let (response_tx, response_rx) = oneshot::channel();
-        request_tx
-            .send((request_id, request, response_tx))
-            .map_err(|_| UpstreamError::ConnectionFailed("Failed to send request".to_string()))?;
+        loop {
+            let params = cursor.take().map(|c| serde_json::json!({ "cursor": c }));
+            let response = self
+                .send_request(alias, "tools/list", params, Duration::from_secs(30))
+                .await?;
 
-        let response = tokio::time::timeout(std::time::Duration::from_secs(30), response_rx)
-            .await
-            .map_err(|_| UpstreamError::Timeout("tools/list request timed out".to_string()))?
-            .map_err(|_| {
-                UpstreamError::ConnectionFailed("Response channel closed".to_string())
-            })??;
-
-        // Parse tools from response
-        let tools_array = response
-            .get("tools")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| {
-                UpstreamError::ProtocolError("Invalid tools/list response".to_string())
-            })?;
+            tools.extend(parse_tools_list(&response)?);
 
-        let mut tools = Vec::new();
-        for tool_value in tools_array {
-            let name = tool_value
-                .get("name")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| UpstreamError::ProtocolError("Tool missing name".to_string()))?
-                .to_string();
-
-            let description = tool_value
-                .get("description")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-
-            let input_schema = tool_value
-                .get("inputSchema")
-                .cloned()
-                .unwrap_or_else(|| serde_json::json!({"type": "object"}));
-
-            tools.push(ToolDefinition {
-                name,
-                description,
-                input_schema,
-            });
+            match response.get("nextCursor").and_then(|v| v.as_str()) {
+                Some(next) if !next.is_empty() => cursor = Some(next.to_string()),
+                _ => break,
+            }
         }
 
         Ok(tools)
     }
 
-    /// List tools via HTTP
-    async fn list_tools_http(&self, alias: &str) -> Result<Vec<ToolDefinition>> {
-        let config = {
+    /// Send a JSON-RPC request over whichever transport the upstream uses
+    async fn send_request(
+        &self,
+        alias: &str,
+        method: &str,
+        params: Option<JsonValue>,
+        timeout: Duration,
+    ) -> Result<JsonValue> {
+        let (rpc, http, state) = {
             let sessions = self.sessions.read().await;
-            sessions
+            let session = sessions
                 .get(alias)
-                .ok_or_else(|| UpstreamError::ServerNotFound(alias.to_string()))?
-                .config
-                .clone()
+                .ok_or_else(|| UpstreamError::ServerNotFound(alias.to_string()))?;
+            (session.rpc.clone(), session.http.clone(), session.state)
         };
 
-        let url = config
-            .url
-            .as_ref()
-            .ok_or_else(|| UpstreamError::ConnectionFailed("No URL configured".to_string()))?;
-
-        let endpoint = format!("{}/mcp/tools/list", url.trim_end_matches('/'));
-
-        let mut request = self.http_client.post(&endpoint);
-
-        // Add auth if configured
-        if let Some(auth) = &config.auth {
-            if let Some(token) = &auth.token {
-                request = request.bearer_auth(token);
-            } else if let Some(env_var) = &auth.env {
-                if let Ok(token) = std::env::var(env_var) {
-                    request = request.bearer_auth(token);
-                }
-            }
-        }
-
-        let response = request
-            .json(&serde_json::json!({
-                "jsonrpc": "2.0",
-                "id": 1,
-                "method": "tools/list"
-            }))
-            .send()
-            .await
-            .map_err(|e| UpstreamError::RequestFailed(format!("HTTP request failed: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(UpstreamError::RequestFailed(format!(
-                "HTTP {} response",
-                response.status()
-            )));
+        if let Some(client) = http {
+            return with_timeout(timeout, method, client.request(method, params)).await;
         }
 
-        let response_json: McpResponse = response.json().await.map_err(|e| {
-            UpstreamError::ProtocolError(format!("Failed to parse response: {}", e))
+        let rpc = rpc.ok_or_else(|| {
+            UpstreamError::ConnectionFailed(match state {
+                ConnectionState::Degraded => format!("Upstream {} is restarting", alias),
+                ConnectionState::Failed => format!(
+                    "Upstream {} stopped after repeated crashes; reconnect it to retry",
+                    alias
+                ),
+                _ => format!("Upstream {} is not connected", alias),
+            })
         })?;
 
-        if let Some(error) = response_json.error {
-            return Err(UpstreamError::RequestFailed(format!(
-                "Server error: {}",
-                error
-            )));
-        }
-
-        let result = response_json
-            .result
-            .ok_or_else(|| UpstreamError::ProtocolError("No result in response".to_string()))?;
-
-        let tools_array = result
-            .get("tools")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| {
-                UpstreamError::ProtocolError("Invalid tools/list response".to_string())
-            })?;
-
-        let mut tools = Vec::new();
-        for tool_value in tools_array {
-            let name = tool_value
-                .get("name")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| UpstreamError::ProtocolError("Tool missing name".to_string()))?
-                .to_string();
-
-            let description = tool_value
-                .get("description")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-
-            let input_schema = tool_value
-                .get("inputSchema")
-                .cloned()
-                .unwrap_or_else(|| serde_json::json!({"type": "object"}));
-
-            tools.push(ToolDefinition {
-                name,
-                description,
-                input_schema,
-            });
-        }
-
-        Ok(tools)
+        rpc.request(method, params, timeout).await
     }
 
-    /// Call a tool on an upstream server
+    /// Call a tool on an upstream server, waiting up to [`DEFAULT_CALL_TIMEOUT`]
     pub async fn call_tool(
         &self,
         server_alias: &str,
         tool_name: &str,
         arguments: JsonValue,
+    ) -> Result<JsonValue> {
+        self.call_tool_with_timeout(server_alias, tool_name, arguments, DEFAULT_CALL_TIMEOUT)
+            .await
+    }
+
+    /// Call a tool on an upstream server, waiting up to `timeout` for the result
+    pub async fn call_tool_with_timeout(
+        &self,
+        server_alias: &str,
+        tool_name: &str,
+        arguments: JsonValue,
+        timeout: Duration,
     ) -> Result<JsonValue> {
         let config = {
             let sessions = self.sessions.read().await;
@@ -745,12 +711,12 @@ impl UpstreamManager {
         };
 
         match config.transport {
-            Transport::Stdio => {
-                self.call_tool_stdio(server_alias, tool_name, arguments)
-                    .await
-            }
-            Transport::Http | Transport::HttpSse => {
-                self.call_tool_http(server_alias, tool_name, arguments)
+            Transport::Stdio | Transport::Http | Transport::HttpSse => {
+                let params = serde_json::json!({
+                    "name": tool_name,
+                    "arguments": arguments
+                });
+                self.send_request(server_alias, "tools/call", Some(params), timeout)
                     .await
             }
             Transport::AgentSkillsRepo | Transport::AgentSkillsFs => {
@@ -761,120 +727,6 @@ impl UpstreamManager {
         }
     }
 
-    /// Call tool via stdio
-    async fn call_tool_stdio(
-        &self,
-        alias: &str,
-        tool_name: &str,
-        arguments: JsonValue,
-    ) -> Result<JsonValue> {
-        let request_tx = {
-            let sessions = self.sessions.read().await;
-            sessions
-                .get(alias)
-                .and_then(|s| s.request_tx.clone())
-                .ok_or_else(|| UpstreamError::ServerNotFound(alias.to_string()))?
-        };
-
-        let request_id = JsonValue::from(format!("call_tool_{}", chrono::Utc::now().timestamp()));
-        let request = McpRequest {
-            jsonrpc: "2.0".to_string(),
-            id: request_id.clone(),
-            method: "tools/call".to_string(),
-            params: Some(serde_json::json!({
-                "name": tool_name,
-                "arguments": arguments
-            })),
-        };
-
-        let (response_tx, response_rx) = oneshot::channel();
-        request_tx
-            .send((request_id, request, response_tx))
-            .map_err(|_| UpstreamError::ConnectionFailed("Failed to send request".to_string()))?;
-
-        let response = tokio::time::timeout(std::time::Duration::from_secs(60), response_rx)
-            .await
-            .map_err(|_| UpstreamError::Timeout("tools/call request timed out".to_string()))?
-            .map_err(|_| {
-                UpstreamError::ConnectionFailed("Response channel closed".to_string())
-            })??;
-
-        Ok(response)
-    }
-
-    /// Call tool via HTTP
-    async fn call_tool_http(
-        &self,
-        alias: &str,
-        tool_name: &str,
-        arguments: JsonValue,
-    ) -> Result<JsonValue> {
-        let config = {
-            let sessions = self.sessions.read().await;
-            sessions
-                .get(alias)
-                .ok_or_else(|| UpstreamError::ServerNotFound(alias.to_string()))?
-                .config
-                .clone()
-        };
-
-        let url = config
-            .url
-            .as_ref()
-            .ok_or_else(|| UpstreamError::ConnectionFailed("No URL configured".to_string()))?;
-
-        let endpoint = format!("{}/mcp/tools/call", url.trim_end_matches('/'));
-
-        let mut request = self.http_client.post(&endpoint);
-
-        // Add auth if configured
-        if let Some(auth) = &config.auth {
-            if let Some(token) = &auth.token {
-                request = request.bearer_auth(token);
-            } else if let Some(env_var) = &auth.env {
-                if let Ok(token) = std::env::var(env_var) {
-                    request = request.bearer_auth(token);
-                }
-            }
-        }
-
-        let response = request
-            .json(&serde_json::json!({
-                "jsonrpc": "2.0",
-                "id": 1,
-                "method": "tools/call",
-                "params": {
-                    "name": tool_name,
-                    "arguments": arguments
-                }
-            }))
-            .send()
-            .await
-            .map_err(|e| UpstreamError::RequestFailed(format!("HTTP request failed: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(UpstreamError::RequestFailed(format!(
-                "HTTP {} response",
-                response.status()
-            )));
-        }
-
-        let response_json: McpResponse = response.json().await.map_err(|e| {
-            UpstreamError::ProtocolError(format!("Failed to parse response: {}", e))
-        })?;
-
-        if let Some(error) = response_json.error {
-            return Err(UpstreamError::RequestFailed(format!(
-                "Server error: {}",
-                error
-            )));
-        }
-
-        response_json
-            .result
-            .ok_or_else(|| UpstreamError::ProtocolError("No result in response".to_string()))
-    }
-
     /// Get upstream config for a server
     pub async fn get_server_config(&self, alias: &str) -> Option<UpstreamConfig> {
         let sessions = self.sessions.read().await;
@@ -893,6 +745,25 @@ impl UpstreamManager {
         sessions.get(alias).map(|s| s.state)
     }
 
+    /// Connection state, restarts, and recent stderr of every upstream, by alias
+    pub async fn statuses(&self) -> Vec<UpstreamStatus> {
+        let sessions = self.sessions.read().await;
+        let mut statuses: Vec<UpstreamStatus> = sessions
+            .iter()
+            .map(|(alias, session)| UpstreamStatus {
+                alias: alias.clone(),
+                transport: session.config.transport.clone(),
+                state: session.state,
+                tool_count: session.tools.len(),
+                restarts: session.crashes.restarts,
+                last_exit: session.crashes.last_exit.clone(),
+                stderr: session.stderr.lines(),
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.alias.cmp(&b.alias));
+        statuses
+    }
+
     pub async fn get_config(&self, alias: &str) -> Option<UpstreamConfig> {
         let sessions = self.sessions.read().await;
         sessions.get(alias).map(|s| s.config.clone())
@@ -912,7 +783,7 @@ impl UpstreamManager {
         if !updates.tags.is_empty() {
             session.config.tags = updates.tags;
         }
-        
+
         if updates.url.is_some() {
             session.config.url = updates.url;
         }
@@ -946,14 +817,30 @@ impl UpstreamManager {
         // Remove tools from registry
         self.registry.remove_server(alias);
 
-        // Kill process if stdio
-        {
+        // Kill process if stdio, stop the event stream if HTTP+SSE
+        let http_client = {
             let mut sessions = self.sessions.write().await;
-            if let Some(session) = sessions.get_mut(alias) {
-                if let Some(mut process) = session.process.take() {
-                    let _ = process.kill().await;
+            match sessions.get_mut(alias) {
+                Some(session) => {
+                    // The supervisor owns the running process; dropping it kills it
+                    if let Some(supervisor) = session.supervisor.take() {
+                        supervisor.abort();
+                    }
+                    if let Some(mut process) = session.process.take() {
+                        let _ = process.child.kill().await;
+                    }
+                    if let Some(reader) = session.sse_reader.take() {
+                        reader.abort();
+                    }
+                    session.http.take()
                 }
+                None => None,
             }
+        };
+
+        // End the server-side session if HTTP
+        if let Some(client) = http_client {
+            client.terminate().await;
         }
 
         // Remove session
@@ -973,6 +860,11 @@ impl UpstreamManager {
         match self.connect(alias).await {
             Ok(_) => {
                 info!("Reconnected successfully: {}", alias);
+                // A manual reconnect gives a crash-looping upstream a fresh start
+                if let Some(session) = self.sessions.write().await.get_mut(alias) {
+                    session.crashes.clear_window();
+                }
+                self.ensure_supervisor(alias).await;
                 Ok(())
             }
             Err(e) => {
@@ -982,4 +874,170 @@ impl UpstreamManager {
             }
         }
     }
+
+    /// Start supervising a stdio upstream unless a supervisor is already running
+    async fn ensure_supervisor(&self, alias: &str) {
+        let mut sessions = self.sessions.write().await;
+        let Some(session) = sessions.get_mut(alias) else {
+            return;
+        };
+        let running = session
+            .supervisor
+            .as_ref()
+            .is_some_and(|s| !s.is_finished());
+        if matches!(session.config.transport, Transport::Stdio) && !running {
+            session.supervisor = Some(tokio::spawn(self.clone().supervise(alias.to_string())));
+        }
+    }
+
+    /// Wait for a stdio upstream's process to die, then restart it
+    async fn supervise(self, alias: String) {
+        loop {
+            let (process, mut generation) = {
+                let mut sessions = self.sessions.write().await;
+                let Some(session) = sessions.get_mut(&alias) else {
+                    return;
+                };
+                (session.process.take(), session.generation.subscribe())
+            };
+            let Some(StdioProcess { mut child, closed }) = process else {
+                return;
+            };
+
+            let status = tokio::select! {
+                status = child.wait() => Some(status),
+                // Give a process that is exiting a moment to report its status
+                _ = closed => tokio::time::timeout(EXIT_GRACE, child.wait()).await.ok(),
+                // Replaced by a reconnect; the old process is killed with `child`
+                _ = generation.changed() => continue,
+            };
+            let mut reason = match status {
+                Some(Ok(status)) => format!("process exited ({})", status),
+                Some(Err(e)) => format!("failed to wait for process: {}", e),
+                None => "process closed stdout".to_string(),
+            };
+            drop(child);
+            warn!("Upstream {} stopped: {}", alias, reason);
+
+            let mut attempt = 0;
+            loop {
+                let Some(policy) = self.record_crash(&alias, reason).await else {
+                    return;
+                };
+                let delay = policy.backoff(attempt);
+                info!("Restarting upstream {} in {:?}", alias, delay);
+                tokio::time::sleep(delay).await;
+
+                match self.connect(&alias).await {
+                    Ok(()) => {
+                        if let Some(session) = self.sessions.write().await.get_mut(&alias) {
+                            session.crashes.restarts += 1;
+                        }
+                        info!("Restarted upstream {}", alias);
+                        break;
+                    }
+                    Err(e) => {
+                        reason = format!("restart failed: {}", e);
+                        attempt += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Mark a crashed upstream degraded, or failed once it is crash-looping
+    ///
+    /// Returns the restart policy when another restart is allowed.
+    async fn record_crash(&self, alias: &str, reason: String) -> Option<RestartPolicy> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.get_mut(alias)?;
+        session.rpc = None;
+
+        let policy = session.config.restart.clone();
+        if session.crashes.record(&policy, reason) {
+            session.state = ConnectionState::Degraded;
+            self.registry.mark_server_degraded(alias);
+            Some(policy)
+        } else {
+            error!(
+                "Upstream {} crashed too often; not restarting (last: {})",
+                alias,
+                session.crashes.last_exit.as_deref().unwrap_or_default()
+            );
+            session.state = ConnectionState::Failed;
+            self.registry.mark_server_down(alias);
+            None
+        }
+    }
+}
+
+/// Run the MCP `initialize` handshake over a JSON-RPC connection
+async fn initialize_session(alias: &str, rpc: &RpcClient) -> Result<()> {
+    let params = serde_json::json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "capabilities": {
+            "tools": {}
+        },
+        "clientInfo": {
+            "name": "skills.rs",
+            "version": env!("CARGO_PKG_VERSION")
+        }
+    });
+    rpc.request("initialize", Some(params), Duration::from_secs(10))
+        .await?;
+
+    debug!("MCP initialize succeeded for {}", alias);
+
+    rpc.notify("notifications/initialized", None)
+}
+
+/// Parse the `tools` array of a `tools/list` result
+fn parse_tools_list(result: &JsonValue) -> Result<Vec<ToolDefinition>> {
+    let tools_array = result
+        .get("tools")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| UpstreamError::ProtocolError("Invalid tools/list response".to_string()))?;
+
+    let mut tools = Vec::new();
+    for tool_value in tools_array {
+        let name = tool_value
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| UpstreamError::ProtocolError("Tool missing name".to_string()))?
+            .to_string();
+
+        let description = tool_value
+            .get("description")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let input_schema = tool_value
+            .get("inputSchema")
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!({"type": "object"}));
+
+        // Optional metadata; malformed annotations are ignored rather than failing the list
+        let title = tool_value
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let output_schema = tool_value
+            .get("outputSchema")
+            .filter(|v| v.is_object())
+            .cloned();
+        let annotations = tool_value
+            .get("annotations")
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+        tools.push(ToolDefinition {
+            name,
+            title,
+            description,
+            input_schema,
+            output_schema,
+            annotations,
+        });
+    }
+
+    Ok(tools)
 }
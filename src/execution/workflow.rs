@@ -0,0 +1,1468 @@
+//! Workflow DSL interpreter
+//!
+//! Runs `entrypoint: workflow` skills. A workflow is a YAML or JSON document
+//! (`workflow.yaml`, `workflow.yml` or `workflow.json` next to SKILL.md):
+//!
+//! ```yaml
+//! inputs:                      # optional JSON Schema for the exec arguments
+//!   type: object
+//!   properties: { repo: { type: string } }
+//!   required: [repo]
+//! steps:
+//!   - call: github.list_issues # fq_name, name or callable id
+//!     with: { repo: "{{ inputs.repo }}" }
+//!     as: issues
+//!   - map:
+//!       over: "{{ issues.items }}"
+//!       item: issue
+//!       steps:
+//!         - emit: "{{ issue.title }}"
+//!     as: titles
+//!   - branch:
+//!       if: "{{ titles | length > 0 }}"
+//!       then:
+//!         - emit: { titles: "{{ titles }}" }
+//!       else:
+//!         - emit: { titles: [] }
+//! ```
+//!
+//! Constructs: `call`, `assign`, `map`, `branch`, `retry`, `validate`, `emit`.
+//!
+//! Scoping: `map`, `branch` and `retry` bodies run in a nested scope. Reads see
+//! every enclosing scope; assignments update the nearest scope that already
+//! defines the variable, otherwise they create it in the current scope. Assign
+//! a default before a block to carry a value out of it.
+//!
+//! Templating: any string may contain `{{ expr }}`. A string that is exactly one
+//! template yields the raw JSON value; otherwise results are interpolated as
+//! text. Expressions support paths (`a.b[0].c`), literals, comparisons,
+//! `and`/`or`/`not`, and filters (`| length`, `| default(x)`, `| json`,
+//! `| lower`, `| upper`, `| keys`, `| first`, `| last`, `| join(", ")`).
+
+use super::{ExecContext, ExecutionTrace, Result, RuntimeError, TraceStep};
+use crate::core::limits::DEFAULT_SESSION;
+use crate::core::policy::{ConsentLevel, PolicyEngine};
+use crate::core::registry::Registry;
+use crate::core::validation;
+use crate::core::{CallableId, CallableRecord, ToolResult, ToolResultContent};
+use crate::storage::ToolPolicy;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// File names recognized as a skill's workflow definition, in priority order
+pub const WORKFLOW_FILES: &[&str] = &["workflow.yaml", "workflow.yml", "workflow.json"];
+
+/// Default cap on `call` steps per workflow run
+pub const DEFAULT_MAX_CALLS: usize = 30;
+
+/// Maximum nesting of workflows calling workflow skills
+const MAX_WORKFLOW_DEPTH: usize = 8;
+
+tokio::task_local! {
+    static WORKFLOW_DEPTH: usize;
+}
+
+/// Locate the workflow definition in a skill directory
+pub fn find_workflow_file(skill_dir: &Path) -> Option<PathBuf> {
+    WORKFLOW_FILES
+        .iter()
+        .map(|name| skill_dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Executes callables on behalf of a workflow
+#[async_trait]
+pub trait CallableExecutor: Send + Sync {
+    async fn execute_callable(&self, ctx: ExecContext) -> Result<ToolResult>;
+}
+
+/// Parsed workflow document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowDefinition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// JSON Schema for the workflow arguments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Value>,
+
+    /// JSON Schema the emitted result must satisfy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Value>,
+
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// A single workflow step; exactly one construct field must be set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowStep {
+    /// Label used in errors and traces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Callable to invoke (fq_name, name, or callable id)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call: Option<String>,
+
+    /// Arguments for `call` (templated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with: Option<Value>,
+
+    /// Variable receiving the result of `call` or `map`
+    #[serde(default, rename = "as", skip_serializing_if = "Option::is_none")]
+    pub save_as: Option<String>,
+
+    /// Timeout for `call`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+
+    /// Store `{"error": ...}` instead of failing when `call` fails
+    #[serde(default)]
+    pub continue_on_error: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assign: Option<Map<String, Value>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<MapStep>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<BranchStep>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryStep>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validate: Option<ValidateStep>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emit: Option<Value>,
+}
+
+/// Run steps for every element of an array
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapStep {
+    /// Array to iterate (templated)
+    pub over: Value,
+
+    /// Variable bound to the current element
+    #[serde(default = "default_item_var")]
+    pub item: String,
+
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// Conditional execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BranchStep {
+    /// Condition (templated, evaluated for truthiness)
+    #[serde(rename = "if")]
+    pub condition: Value,
+
+    #[serde(default)]
+    pub then: Vec<WorkflowStep>,
+
+    #[serde(default, rename = "else")]
+    pub otherwise: Vec<WorkflowStep>,
+}
+
+/// Re-run steps until they succeed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryStep {
+    #[serde(default = "default_attempts")]
+    pub attempts: u32,
+
+    /// Delay before the second attempt
+    #[serde(default)]
+    pub delay_ms: u64,
+
+    /// Multiplier applied to the delay after each failed attempt
+    #[serde(default = "default_backoff")]
+    pub backoff: f64,
+
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// Check a value against a JSON Schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidateStep {
+    /// Value to check (templated)
+    pub value: Value,
+
+    pub schema: Value,
+
+    /// Error message used instead of the violation list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+fn default_item_var() -> String {
+    "item".to_string()
+}
+
+fn default_attempts() -> u32 {
+    3
+}
+
+fn default_backoff() -> f64 {
+    2.0
+}
+
+enum StepKind<'a> {
+    Call(&'a str),
+    Assign(&'a Map<String, Value>),
+    Map(&'a MapStep),
+    Branch(&'a BranchStep),
+    Retry(&'a RetryStep),
+    Validate(&'a ValidateStep),
+    Emit(&'a Value),
+}
+
+impl WorkflowStep {
+    fn kind(&self) -> std::result::Result<StepKind<'_>, String> {
+        let mut kinds = Vec::new();
+        if let Some(target) = &self.call {
+            kinds.push(StepKind::Call(target));
+        }
+        if let Some(assign) = &self.assign {
+            kinds.push(StepKind::Assign(assign));
+        }
+        if let Some(map) = &self.map {
+            kinds.push(StepKind::Map(map));
+        }
+        if let Some(branch) = &self.branch {
+            kinds.push(StepKind::Branch(branch));
+        }
+        if let Some(retry) = &self.retry {
+            kinds.push(StepKind::Retry(retry));
+        }
+        if let Some(validate) = &self.validate {
+            kinds.push(StepKind::Validate(validate));
+        }
+        if let Some(emit) = &self.emit {
+            kinds.push(StepKind::Emit(emit));
+        }
+
+        if kinds.len() != 1 {
+            return Err(
+                "step must contain exactly one of call, assign, map, branch, retry, validate, emit"
+                    .to_string(),
+            );
+        }
+
+        Ok(kinds.remove(0))
+    }
+}
+
+impl WorkflowDefinition {
+    /// Parse and check a workflow from a JSON value
+    pub fn from_value(value: &Value) -> std::result::Result<Self, String> {
+        let workflow: WorkflowDefinition =
+            serde_json::from_value(value.clone()).map_err(|e| e.to_string())?;
+        workflow.check()?;
+        Ok(workflow)
+    }
+
+    /// Load a workflow from a YAML or JSON file
+    pub fn load(path: &Path) -> std::result::Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let value: Value = if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            serde_json::from_str(&content).map_err(|e| e.to_string())?
+        } else {
+            serde_yaml::from_str(&content).map_err(|e| e.to_string())?
+        };
+
+        Self::from_value(&value).map_err(|e| format!("Invalid workflow {}: {}", path.display(), e))
+    }
+
+    /// Statically check step structure
+    pub fn check(&self) -> std::result::Result<(), String> {
+        check_steps(&self.steps, "steps")
+    }
+
+    /// Names of every callable referenced by `call` steps
+    pub fn call_targets(&self) -> Vec<String> {
+        fn collect(steps: &[WorkflowStep], out: &mut Vec<String>) {
+            for step in steps {
+                if let Some(target) = &step.call {
+                    if !out.contains(target) {
+                        out.push(target.clone());
+                    }
+                }
+                if let Some(map) = &step.map {
+                    collect(&map.steps, out);
+                }
+                if let Some(branch) = &step.branch {
+                    collect(&branch.then, out);
+                    collect(&branch.otherwise, out);
+                }
+                if let Some(retry) = &step.retry {
+                    collect(&retry.steps, out);
+                }
+            }
+        }
+
+        let mut targets = Vec::new();
+        collect(&self.steps, &mut targets);
+        targets
+    }
+}
+
+fn check_steps(steps: &[WorkflowStep], location: &str) -> std::result::Result<(), String> {
+    for (index, step) in steps.iter().enumerate() {
+        let location = format!("{}[{}]", location, index);
+        let kind = step.kind().map_err(|e| format!("{}: {}", location, e))?;
+
+        let is_call = matches!(kind, StepKind::Call(_));
+        if !is_call && (step.with.is_some() || step.timeout_ms.is_some() || step.continue_on_error)
+        {
+            return Err(format!(
+                "{}: with, timeout_ms and continue_on_error only apply to call steps",
+                location
+            ));
+        }
+        if step.save_as.is_some() && !matches!(kind, StepKind::Call(_) | StepKind::Map(_)) {
+            return Err(format!(
+                "{}: 'as' only applies to call and map steps",
+                location
+            ));
+        }
+
+        match kind {
+            StepKind::Map(map) => check_steps(&map.steps, &format!("{}.map.steps", location))?,
+            StepKind::Branch(branch) => {
+                check_steps(&branch.then, &format!("{}.branch.then", location))?;
+                check_steps(&branch.otherwise, &format!("{}.branch.else", location))?;
+            }
+            StepKind::Retry(retry) => {
+                if retry.attempts == 0 {
+                    return Err(format!("{}: retry attempts must be at least 1", location));
+                }
+                check_steps(&retry.steps, &format!("{}.retry.steps", location))?;
+            }
+            StepKind::Emit(value) => parse_templates(value, &location)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Reject templates that do not parse, so typos surface at load time
+fn parse_templates(value: &Value, location: &str) -> std::result::Result<(), String> {
+    match value {
+        Value::String(s) => {
+            let scope = Scope::new(Map::new());
+            // Unknown variables are fine here; only syntax errors matter
+            match render_string(s, &scope) {
+                Err(e) if e.starts_with("syntax") => Err(format!("{}: {}", location, e)),
+                _ => Ok(()),
+            }
+        }
+        Value::Array(items) => items
+            .iter()
+            .try_for_each(|item| parse_templates(item, location)),
+        Value::Object(map) => map
+            .values()
+            .try_for_each(|item| parse_templates(item, location)),
+        _ => Ok(()),
+    }
+}
+
+/// Failure inside a workflow, tagged with the step that raised it
+#[derive(Debug)]
+struct StepFailure {
+    location: String,
+    message: String,
+}
+
+type StepResult = std::result::Result<(), StepFailure>;
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+struct Frame {
+    vars: Map<String, Value>,
+    emit_target: bool,
+    emitted: Option<Value>,
+}
+
+/// Variable scopes, innermost last
+struct Scope {
+    frames: Vec<Frame>,
+}
+
+impl Scope {
+    fn new(vars: Map<String, Value>) -> Self {
+        Scope {
+            frames: vec![Frame {
+                vars,
+                emit_target: true,
+                emitted: None,
+            }],
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        self.frames.iter().rev().find_map(|f| f.vars.get(name))
+    }
+
+    fn set(&mut self, name: &str, value: Value) {
+        let frame = match self.frames.iter().rposition(|f| f.vars.contains_key(name)) {
+            Some(index) => &mut self.frames[index],
+            None => self.frames.last_mut().expect("scope has a root frame"),
+        };
+        frame.vars.insert(name.to_string(), value);
+    }
+
+    fn set_local(&mut self, name: &str, value: Value) {
+        self.frames
+            .last_mut()
+            .expect("scope has a root frame")
+            .vars
+            .insert(name.to_string(), value);
+    }
+
+    fn push(&mut self, emit_target: bool) {
+        self.frames.push(Frame {
+            vars: Map::new(),
+            emit_target,
+            emitted: None,
+        });
+    }
+
+    fn pop(&mut self) -> Frame {
+        self.frames.pop().expect("scope push/pop are balanced")
+    }
+
+    fn emit(&mut self, value: Value) {
+        if let Some(frame) = self.frames.iter_mut().rev().find(|f| f.emit_target) {
+            frame.emitted = Some(value);
+        }
+    }
+}
+
+struct RunState {
+    calls: usize,
+    steps: Vec<TraceStep>,
+    /// MCP session the run's calls are charged to
+    session: Option<String>,
+}
+
+/// Workflow DSL interpreter
+pub struct WorkflowEngine {
+    registry: Arc<Registry>,
+    max_calls: usize,
+    policy_engine: Option<Arc<PolicyEngine>>,
+}
+
+impl WorkflowEngine {
+    pub fn new(registry: Arc<Registry>) -> Self {
+        WorkflowEngine {
+            registry,
+            max_calls: DEFAULT_MAX_CALLS,
+            policy_engine: None,
+        }
+    }
+
+    /// Limit the number of `call` steps a single run may perform
+    pub fn with_max_calls(mut self, max_calls: usize) -> Self {
+        self.max_calls = max_calls;
+        self
+    }
+
+    /// Authorize every `call` step with the server policy, as `exec` does
+    pub fn with_policy_engine(mut self, policy_engine: Arc<PolicyEngine>) -> Self {
+        self.policy_engine = Some(policy_engine);
+        self
+    }
+
+    /// Execute a workflow definition
+    pub async fn execute(
+        &self,
+        executor: &dyn CallableExecutor,
+        workflow: &WorkflowDefinition,
+        arguments: &Value,
+        policy: &ToolPolicy,
+        session: Option<&str>,
+        trace: Option<&mut ExecutionTrace>,
+    ) -> Result<ToolResult> {
+        let depth = WORKFLOW_DEPTH.try_with(|d| *d).unwrap_or(0);
+        if depth >= MAX_WORKFLOW_DEPTH {
+            return Err(RuntimeError::ExecutionFailed(format!(
+                "Workflow nesting exceeds {} levels",
+                MAX_WORKFLOW_DEPTH
+            )));
+        }
+
+        WORKFLOW_DEPTH
+            .scope(
+                depth + 1,
+                self.run(executor, workflow, arguments, policy, session, trace),
+            )
+            .await
+    }
+
+    async fn run(
+        &self,
+        executor: &dyn CallableExecutor,
+        workflow: &WorkflowDefinition,
+        arguments: &Value,
+        policy: &ToolPolicy,
+        session: Option<&str>,
+        trace: Option<&mut ExecutionTrace>,
+    ) -> Result<ToolResult> {
+        if let Some(schema) = &workflow.inputs {
+            let violations = validation::validate(schema, arguments);
+            if !violations.is_empty() {
+                return Err(RuntimeError::ValidationFailed(
+                    validation::format_violations(&violations),
+                ));
+            }
+        }
+
+        let mut root = Map::new();
+        root.insert("inputs".to_string(), arguments.clone());
+        let mut scope = Scope::new(root);
+        let mut state = RunState {
+            calls: 0,
+            steps: Vec::new(),
+            session: session.map(str::to_string),
+        };
+
+        let outcome = self
+            .run_steps(
+                executor,
+                &workflow.steps,
+                &mut scope,
+                &mut state,
+                policy,
+                "steps".to_string(),
+            )
+            .await;
+
+        if let Some(trace) = trace {
+            for mut step in state.steps {
+                step.step_index = trace.steps.len();
+                trace.steps.push(step);
+            }
+        }
+
+        outcome.map_err(|f| {
+            RuntimeError::ExecutionFailed(format!("Workflow step {}: {}", f.location, f.message))
+        })?;
+
+        let root = scope.pop();
+        let output = root.emitted.unwrap_or_else(|| {
+            let mut vars = root.vars;
+            vars.remove("inputs");
+            Value::Object(vars)
+        });
+
+        if let Some(schema) = &workflow.outputs {
+            let violations = validation::validate(schema, &output);
+            if !violations.is_empty() {
+                return Err(RuntimeError::ExecutionFailed(format!(
+                    "Workflow output does not match its schema: {}",
+                    validation::format_violations(&violations)
+                )));
+            }
+        }
+
+        info!("Workflow completed after {} calls", state.calls);
+        Ok(output_to_result(output))
+    }
+
+    fn run_steps<'a>(
+        &'a self,
+        executor: &'a dyn CallableExecutor,
+        steps: &'a [WorkflowStep],
+        scope: &'a mut Scope,
+        state: &'a mut RunState,
+        policy: &'a ToolPolicy,
+        location: String,
+    ) -> BoxFuture<'a, StepResult> {
+        Box::pin(async move {
+            for (index, step) in steps.iter().enumerate() {
+                let location = match &step.name {
+                    Some(name) => format!("{}[{}] ({})", location, index, name),
+                    None => format!("{}[{}]", location, index),
+                };
+                self.run_step(executor, step, scope, state, policy, location)
+                    .await?;
+            }
+            Ok(())
+        })
+    }
+
+    async fn run_step(
+        &self,
+        executor: &dyn CallableExecutor,
+        step: &WorkflowStep,
+        scope: &mut Scope,
+        state: &mut RunState,
+        policy: &ToolPolicy,
+        location: String,
+    ) -> StepResult {
+        let fail = |message: String| StepFailure {
+            location: location.clone(),
+            message,
+        };
+
+        match step.kind().map_err(fail)? {
+            StepKind::Call(target) => {
+                let arguments = match &step.with {
+                    Some(with) => render(with, scope).map_err(fail)?,
+                    None => Value::Object(Map::new()),
+                };
+                if !arguments.is_object() {
+                    return Err(fail("'with' must render to an object".to_string()));
+                }
+
+                let value = match self
+                    .call(executor, target, arguments, step.timeout_ms, policy, state)
+                    .await
+                {
+                    Ok(value) => value,
+                    Err(message) if step.continue_on_error => {
+                        debug!("Continuing after failed call at {}: {}", location, message);
+                        serde_json::json!({ "error": message })
+                    }
+                    Err(message) => return Err(fail(message)),
+                };
+
+                if let Some(name) = &step.save_as {
+                    scope.set(name, value);
+                }
+            }
+            StepKind::Assign(assignments) => {
+                for (name, template) in assignments {
+                    let value = render(template, scope).map_err(fail)?;
+                    scope.set(name, value);
+                }
+            }
+            StepKind::Map(map) => {
+                let items = match render(&map.over, scope).map_err(fail)? {
+                    Value::Array(items) => items,
+                    Value::Null => Vec::new(),
+                    other => {
+                        return Err(fail(format!(
+                            "map 'over' must be an array, got {}",
+                            validation::json_type_name(&other)
+                        )))
+                    }
+                };
+
+                let count = items.len();
+                let mut results = Vec::with_capacity(count);
+                for (index, item) in items.into_iter().enumerate() {
+                    scope.push(true);
+                    scope.set_local(&map.item, item);
+                    scope.set_local(
+                        "loop",
+                        serde_json::json!({
+                            "index": index,
+                            "first": index == 0,
+                            "last": index + 1 == count,
+                        }),
+                    );
+
+                    let outcome = self
+                        .run_steps(
+                            executor,
+                            &map.steps,
+                            scope,
+                            state,
+                            policy,
+                            format!("{}.map[{}].steps", location, index),
+                        )
+                        .await;
+                    let frame = scope.pop();
+                    outcome?;
+
+                    results.push(frame.emitted.unwrap_or_else(|| {
+                        let mut vars = frame.vars;
+                        vars.remove(&map.item);
+                        vars.remove("loop");
+                        Value::Object(vars)
+                    }));
+                }
+
+                if let Some(name) = &step.save_as {
+                    scope.set(name, Value::Array(results));
+                }
+            }
+            StepKind::Branch(branch) => {
+                let condition = render(&branch.condition, scope).map_err(fail)?;
+                let (body, label) = if is_truthy(&condition) {
+                    (&branch.then, "then")
+                } else {
+                    (&branch.otherwise, "else")
+                };
+
+                scope.push(false);
+                let outcome = self
+                    .run_steps(
+                        executor,
+                        body,
+                        scope,
+                        state,
+                        policy,
+                        format!("{}.branch.{}", location, label),
+                    )
+                    .await;
+                scope.pop();
+                outcome?;
+            }
+            StepKind::Retry(retry) => {
+                let mut delay = retry.delay_ms as f64;
+                let mut attempt = 1;
+                loop {
+                    scope.push(false);
+                    let outcome = self
+                        .run_steps(
+                            executor,
+                            &retry.steps,
+                            scope,
+                            state,
+                            policy,
+                            format!("{}.retry.steps", location),
+                        )
+                        .await;
+                    scope.pop();
+
+                    match outcome {
+                        Ok(()) => break,
+                        Err(failure) if attempt < retry.attempts => {
+                            debug!(
+                                "Retry attempt {}/{} failed at {}: {}",
+                                attempt, retry.attempts, failure.location, failure.message
+                            );
+                            if delay > 0.0 {
+                                tokio::time::sleep(Duration::from_millis(delay as u64)).await;
+                            }
+                            delay *= retry.backoff.max(1.0);
+                            attempt += 1;
+                        }
+                        Err(failure) => {
+                            return Err(StepFailure {
+                                message: format!(
+                                    "{} (after {} attempts)",
+                                    failure.message, retry.attempts
+                                ),
+                                ..failure
+                            })
+                        }
+                    }
+                }
+            }
+            StepKind::Validate(check) => {
+                let value = render(&check.value, scope).map_err(fail)?;
+                let violations = validation::validate(&check.schema, &value);
+                if !violations.is_empty() {
+                    return Err(fail(match &check.message {
+                        Some(message) => message.clone(),
+                        None => format!(
+                            "validation failed: {}",
+                            validation::format_violations(&violations)
+                        ),
+                    }));
+                }
+            }
+            StepKind::Emit(template) => {
+                let value = render(template, scope).map_err(fail)?;
+                scope.emit(value);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn call(
+        &self,
+        executor: &dyn CallableExecutor,
+        target: &str,
+        arguments: Value,
+        timeout_ms: Option<u64>,
+        policy: &ToolPolicy,
+        state: &mut RunState,
+    ) -> std::result::Result<Value, String> {
+        if state.calls >= self.max_calls {
+            return Err(format!(
+                "workflow exceeded its limit of {} calls",
+                self.max_calls
+            ));
+        }
+
+        let record = self
+            .resolve(target)
+            .ok_or_else(|| format!("unknown callable '{}'", target))?;
+        check_tool_policy(policy, &record)?;
+
+        // Rendered arguments are checked like a direct exec of the callable.
+        // Consent tokens are bound to one callable and its arguments, so a
+        // sub-call that needs consent cannot be covered and is denied outright.
+        if let Some(engine) = &self.policy_engine {
+            let decision = engine
+                .authorize(&record, &arguments, ConsentLevel::None)
+                .await
+                .map_err(|e| format!("policy check failed for {}: {}", record.fq_name, e))?;
+            if !decision.allowed {
+                let mut message = format!("{} denied: {}", record.fq_name, decision.reason);
+                if decision.required_consent.is_some() {
+                    message.push_str("; workflow steps cannot obtain consent, exec it directly");
+                }
+                return Err(message);
+            }
+
+            // Every step that will run spends rate limit budget, so a `map` cannot
+            // multiply calls for free; invalid arguments fail before anything is charged
+            super::validate_arguments(&record.input_schema, &arguments)
+                .map_err(|e| format!("{} failed: {}", record.fq_name, e))?;
+            let session = state.session.as_deref().unwrap_or(DEFAULT_SESSION);
+            engine
+                .check_rate_limits(&record, session)
+                .await
+                .map_err(|e| format!("{} denied: {}", record.fq_name, e))?;
+        }
+
+        state.calls += 1;
+        let started_at = chrono::Utc::now();
+        debug!("Workflow calling {}", record.fq_name);
+
+        let outcome = executor
+            .execute_callable(ExecContext {
+                callable_id: record.id.clone(),
+                arguments,
+                timeout_ms,
+                trace_enabled: false,
+                session: state.session.clone(),
+            })
+            .await;
+
+        let completed_at = chrono::Utc::now();
+        let outcome = match outcome {
+            Ok(result) if result.is_error => Err(result_text(&result)),
+            Ok(result) => Ok(result_value(result)),
+            Err(e) => Err(e.to_string()),
+        };
+        state.steps.push(TraceStep {
+            step_index: state.steps.len(),
+            callable_id: record.id.as_str().to_string(),
+            started_at,
+            completed_at: Some(completed_at),
+            duration_ms: Some((completed_at - started_at).num_milliseconds().max(0) as u64),
+            success: outcome.is_ok(),
+            error: outcome.as_ref().err().cloned(),
+        });
+
+        outcome.map_err(|error| format!("{} failed: {}", record.fq_name, error))
+    }
+
+    /// Resolve a `call` target by callable id, fq_name, or unique name
+    fn resolve(&self, target: &str) -> Option<CallableRecord> {
+        if let Some(record) = self.registry.get(&CallableId::from(target)) {
+            return Some(record);
+        }
+        if let Some(record) = self.registry.get_by_fq_name(target) {
+            return Some(record);
+        }
+
+        let mut by_name = self.registry.all().into_iter().filter(|r| r.name == target);
+        match (by_name.next(), by_name.next()) {
+            (Some(record), None) => Some(record),
+            _ => None,
+        }
+    }
+}
+
+/// Enforce a skill's allow/deny lists for a callable
+///
+/// Patterns are globs matched against the fq_name, name and id, or
+/// `tag:<tag>` to match by tag. Deny wins over allow.
+pub fn check_tool_policy(
+    policy: &ToolPolicy,
+    record: &CallableRecord,
+) -> std::result::Result<(), String> {
+    let matches = |pattern: &String| -> bool {
+        if let Some(tag) = pattern.strip_prefix("tag:") {
+            return record.tags.iter().any(|t| t == tag);
+        }
+        match glob::Pattern::new(pattern) {
+            Ok(p) => {
+                p.matches(&record.fq_name)
+                    || p.matches(&record.name)
+                    || p.matches(record.id.as_str())
+            }
+            Err(_) => pattern == &record.fq_name || pattern == record.id.as_str(),
+        }
+    };
+
+    if policy.deny.iter().any(matches) {
+        return Err(format!(
+            "{} is denied by the skill's tool policy",
+            record.fq_name
+        ));
+    }
+    if !policy.allow.iter().any(matches) {
+        return Err(format!(
+            "{} is not in the skill's allowed tools",
+            record.fq_name
+        ));
+    }
+    Ok(())
+}
+
+/// Value a workflow sees for a call result
+fn result_value(result: ToolResult) -> Value {
+    if let Some(structured) = result.structured_content {
+        return structured;
+    }
+
+    let text = result_text(&result);
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+fn result_text(result: &ToolResult) -> String {
+    result
+        .content
+        .iter()
+        .filter_map(|c| match c {
+            ToolResultContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn output_to_result(output: Value) -> ToolResult {
+    let text = match &output {
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    let structured_content = match output {
+        Value::Object(_) => output,
+        other => serde_json::json!({ "result": other }),
+    };
+
+    ToolResult {
+        content: vec![ToolResultContent::Text { text }],
+        structured_content: Some(structured_content),
+        is_error: false,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Templating
+// ---------------------------------------------------------------------------
+
+fn render(value: &Value, scope: &Scope) -> std::result::Result<Value, String> {
+    match value {
+        Value::String(s) => render_string(s, scope),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| render(item, scope))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| render(v, scope).map(|v| (k.clone(), v)))
+            .collect::<std::result::Result<Map<_, _>, _>>()
+            .map(Value::Object),
+        other => Ok(other.clone()),
+    }
+}
+
+fn render_string(s: &str, scope: &Scope) -> std::result::Result<Value, String> {
+    let trimmed = s.trim();
+    if let Some(inner) = trimmed
+        .strip_prefix("{{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+    {
+        if !inner.contains("{{") && !inner.contains("}}") {
+            return evaluate(inner, scope);
+        }
+    }
+
+    if !s.contains("{{") {
+        return Ok(Value::String(s.to_string()));
+    }
+
+    let mut output = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("syntax error: unclosed template in '{}'", s))?;
+        let value = evaluate(&after[..end], scope)?;
+        output.push_str(&stringify(&value));
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+
+    Ok(Value::String(output))
+}
+
+fn stringify(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Dot,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Pipe,
+    Comma,
+    Not,
+    And,
+    Or,
+    Op(&'static str),
+}
+
+fn tokenize(expr: &str) -> std::result::Result<Vec<Token>, String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' | '\n' | '\r' => i += 1,
+            '.' => {
+                tokens.push(Token::Dot);
+                i += 1;
+            }
+            '[' => {
+                tokens.push(Token::LBracket);
+                i += 1;
+            }
+            ']' => {
+                tokens.push(Token::RBracket);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '|' if chars.get(i + 1) == Some(&'|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '|' => {
+                tokens.push(Token::Pipe);
+                i += 1;
+            }
+            '&' if chars.get(i + 1) == Some(&'&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '=' | '!' | '<' | '>' => {
+                let next = chars.get(i + 1).copied();
+                let (token, width) = match (c, next) {
+                    ('=', Some('=')) => (Token::Op("=="), 2),
+                    ('!', Some('=')) => (Token::Op("!="), 2),
+                    ('<', Some('=')) => (Token::Op("<="), 2),
+                    ('>', Some('=')) => (Token::Op(">="), 2),
+                    ('<', _) => (Token::Op("<"), 1),
+                    ('>', _) => (Token::Op(">"), 1),
+                    ('!', _) => (Token::Not, 1),
+                    _ => return Err(format!("syntax error: unexpected '{}' in '{}'", c, expr)),
+                };
+                tokens.push(token);
+                i += width;
+            }
+            '\'' | '"' => {
+                let quote = c;
+                let mut s = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        i += 1;
+                    }
+                    s.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(format!("syntax error: unterminated string in '{}'", expr));
+                }
+                tokens.push(Token::Str(s));
+                i += 1;
+            }
+            c if c.is_ascii_digit()
+                || (c == '-' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    // A dot followed by a non-digit is a path separator
+                    if chars[i] == '.' && !chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()) {
+                        break;
+                    }
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| format!("syntax error: invalid number '{}'", text))?;
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric()
+                        || chars[i] == '_'
+                        || chars[i] == '-'
+                        || chars[i] == '$')
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Ident(word),
+                });
+            }
+            other => {
+                return Err(format!(
+                    "syntax error: unexpected '{}' in '{}'",
+                    other, expr
+                ))
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Evaluate a template expression
+fn evaluate(expr: &str, scope: &Scope) -> std::result::Result<Value, String> {
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return Err("syntax error: empty template".to_string());
+    }
+
+    let mut parser = ExprParser {
+        tokens,
+        pos: 0,
+        scope,
+        source: expr,
+    };
+    let value = parser.or_expr()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(format!(
+            "syntax error: unexpected input in '{}'",
+            expr.trim()
+        ));
+    }
+    Ok(value)
+}
+
+/// Recursive-descent evaluator over template tokens
+struct ExprParser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    scope: &'a Scope,
+    source: &'a str,
+}
+
+impl ExprParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> std::result::Result<(), String> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            _ => Err(format!(
+                "syntax error: expected {:?} in '{}'",
+                token,
+                self.source.trim()
+            )),
+        }
+    }
+
+    fn or_expr(&mut self) -> std::result::Result<Value, String> {
+        let mut left = self.and_expr()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.and_expr()?;
+            left = Value::Bool(is_truthy(&left) || is_truthy(&right));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> std::result::Result<Value, String> {
+        let mut left = self.not_expr()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.not_expr()?;
+            left = Value::Bool(is_truthy(&left) && is_truthy(&right));
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> std::result::Result<Value, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            let value = self.not_expr()?;
+            return Ok(Value::Bool(!is_truthy(&value)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> std::result::Result<Value, String> {
+        let left = self.piped()?;
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.piped()?;
+
+        let ordering = compare(&left, &right);
+        let result = match op {
+            "==" => values_equal(&left, &right),
+            "!=" => !values_equal(&left, &right),
+            "<" => ordering == Some(std::cmp::Ordering::Less),
+            "<=" => matches!(
+                ordering,
+                Some(std::cmp::Ordering::Less | std::cmp::Ordering::Equal)
+            ),
+            ">" => ordering == Some(std::cmp::Ordering::Greater),
+            ">=" => matches!(
+                ordering,
+                Some(std::cmp::Ordering::Greater | std::cmp::Ordering::Equal)
+            ),
+            _ => unreachable!("tokenizer only produces known operators"),
+        };
+        Ok(Value::Bool(result))
+    }
+
+    fn piped(&mut self) -> std::result::Result<Value, String> {
+        let mut value = self.primary()?;
+        while self.peek() == Some(&Token::Pipe) {
+            self.pos += 1;
+            let filter = match self.next() {
+                Some(Token::Ident(name)) => name,
+                _ => {
+                    return Err(format!(
+                        "syntax error: expected filter name in '{}'",
+                        self.source.trim()
+                    ))
+                }
+            };
+
+            let mut args = Vec::new();
+            if self.peek() == Some(&Token::LParen) {
+                self.pos += 1;
+                if self.peek() != Some(&Token::RParen) {
+                    loop {
+                        args.push(self.or_expr()?);
+                        if self.peek() == Some(&Token::Comma) {
+                            self.pos += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                self.expect(Token::RParen)?;
+            }
+
+            value = apply_filter(&filter, value, &args)?;
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> std::result::Result<Value, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(number_value(n)),
+            Some(Token::Str(s)) => Ok(Value::String(s)),
+            Some(Token::LParen) => {
+                let value = self.or_expr()?;
+                self.expect(Token::RParen)?;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                _ => {
+                    let root = self.scope.lookup(&name).cloned().unwrap_or(Value::Null);
+                    self.path(root)
+                }
+            },
+            _ => Err(format!(
+                "syntax error: expected a value in '{}'",
+                self.source.trim()
+            )),
+        }
+    }
+
+    fn path(&mut self, mut value: Value) -> std::result::Result<Value, String> {
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    value = match self.next() {
+                        Some(Token::Ident(key)) => value.get(&key).cloned().unwrap_or(Value::Null),
+                        Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => {
+                            value.get(n as usize).cloned().unwrap_or(Value::Null)
+                        }
+                        _ => {
+                            return Err(format!(
+                                "syntax error: expected a field after '.' in '{}'",
+                                self.source.trim()
+                            ))
+                        }
+                    };
+                }
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    let key = self.or_expr()?;
+                    self.expect(Token::RBracket)?;
+                    value = match key {
+                        Value::Number(n) => match n.as_i64() {
+                            Some(i) if i < 0 => value
+                                .as_array()
+                                .and_then(|a| a.len().checked_sub(i.unsigned_abs() as usize))
+                                .and_then(|idx| value.get(idx))
+                                .cloned()
+                                .unwrap_or(Value::Null),
+                            Some(i) => value.get(i as usize).cloned().unwrap_or(Value::Null),
+                            None => Value::Null,
+                        },
+                        Value::String(k) => value.get(&k).cloned().unwrap_or(Value::Null),
+                        _ => Value::Null,
+                    };
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+}
+
+fn number_value(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::from(n as i64)
+    } else {
+        Value::from(n)
+    }
+}
+
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        _ => a == b,
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<std::cmp::Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64()?.partial_cmp(&y.as_f64()?),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
+
+fn apply_filter(name: &str, value: Value, args: &[Value]) -> std::result::Result<Value, String> {
+    let result = match name {
+        "length" => Value::from(match &value {
+            Value::String(s) => s.chars().count(),
+            Value::Array(items) => items.len(),
+            Value::Object(map) => map.len(),
+            Value::Null => 0,
+            _ => return Err("filter 'length' needs a string, array or object".to_string()),
+        }),
+        "default" => {
+            if value.is_null() {
+                args.first().cloned().unwrap_or(Value::Null)
+            } else {
+                value
+            }
+        }
+        "json" => Value::String(value.to_string()),
+        "lower" => Value::String(stringify(&value).to_lowercase()),
+        "upper" => Value::String(stringify(&value).to_uppercase()),
+        "keys" => match value {
+            Value::Object(map) => Value::Array(map.keys().cloned().map(Value::String).collect()),
+            _ => Value::Array(Vec::new()),
+        },
+        "first" => match value {
+            Value::Array(items) => items.into_iter().next().unwrap_or(Value::Null),
+            _ => Value::Null,
+        },
+        "last" => match value {
+            Value::Array(items) => items.into_iter().last().unwrap_or(Value::Null),
+            _ => Value::Null,
+        },
+        "join" => {
+            let separator = args
+                .first()
+                .map(stringify)
+                .unwrap_or_else(|| ",".to_string());
+            match value {
+                Value::Array(items) => Value::String(
+                    items
+                        .iter()
+                        .map(stringify)
+                        .collect::<Vec<_>>()
+                        .join(&separator),
+                ),
+                other => Value::String(stringify(&other)),
+            }
+        }
+        other => return Err(format!("unknown filter '{}'", other)),
+    };
+    Ok(result)
+}
@@ -11,9 +11,11 @@ pub mod storage;
 
 pub use core::policy::{ConsentLevel, PolicyConfig, PolicyEngine};
 pub use core::registry::{Registry, ServerHealth, ServerInfo};
+pub use core::validation::SchemaViolation;
 pub use core::{
     BundledTool, CallableId, CallableKind, CallableRecord, CallableSignature, CostHints,
-    ResourceContent, RiskTier, SchemaDigest, ToolDefinition, ToolResult, ToolResultContent,
+    ResourceContent, RiskTier, SchemaDigest, ToolAnnotations, ToolDefinition, ToolResult,
+    ToolResultContent,
 };
 
 pub use execution::upstream::{
@@ -12,14 +12,21 @@ use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use paths::{paths_from_env, PathsConfig, SkillsPaths};
 use rmcp::{transport::stdio, ServiceExt};
-use skillsrs::core::persistence::PersistenceLayer;
+use skillsrs::core::consent::ConsentManager;
+use skillsrs::core::persistence::{
+    self, parse_history_time, ExecutionStatus, HistoryFilter, PersistenceLayer,
+};
 use skillsrs::core::policy::{PolicyConfig, PolicyEngine};
 use skillsrs::core::registry::Registry;
-use skillsrs::core::CallableKind;
+use skillsrs::core::{CallableId, CallableKind};
 use skillsrs::execution::upstream::UpstreamManager;
 use skillsrs::execution::{sandbox::SandboxBackend, sandbox::SandboxConfig, Runtime};
 use skillsrs::mcp::SkillsServer;
-use skillsrs::storage::search::SearchEngine;
+use skillsrs::storage::query::parse_query;
+use skillsrs::storage::search::{
+    IndexConfig, SearchEngine, SearchInclude, SearchMode, SearchQuery,
+};
+use skillsrs::storage::semantic::EmbeddingModel;
 use skillsrs::storage::SkillStore;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -123,13 +130,23 @@ enum Commands {
     },
 
     /// Search/grep tools by pattern (AI agent mode)
+    ///
+    /// Examples:
+    ///   skills grep "*file*"
+    ///   skills grep "*issue* server:github risk:<=writes -tag:deprecated"
+    ///   skills grep "gthub_isue" --mode fuzzy
+    ///   skills grep "^(read|write)_" --mode regex
     Grep {
-        /// Glob pattern (e.g., "*file*")
+        /// Glob pattern (e.g., "*file*"), or a query when --mode is given
         pattern: String,
 
         /// Include descriptions
         #[arg(short, long)]
         descriptions: bool,
+
+        /// Search mode: literal, regex, or fuzzy (default: glob on names)
+        #[arg(short, long)]
+        mode: Option<SearchMode>,
     },
 
     /// Execute a tool directly (alias for 'tool')
@@ -146,6 +163,60 @@ enum Commands {
         json: bool,
     },
 
+    /// Show execution history
+    ///
+    /// Examples:
+    ///   skills history --since 24h
+    ///   skills history --callable filesystem/read_file --status error
+    History {
+        /// Callable id, server/tool, or tool/skill name
+        #[arg(short, long)]
+        callable: Option<String>,
+
+        /// Only executions at or after this time (RFC 3339, YYYY-MM-DD, or age like 24h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only executions at or before this time (same formats as --since)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Filter by outcome: success or error
+        #[arg(short, long)]
+        status: Option<String>,
+
+        /// Maximum number of records
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: i64,
+
+        /// JSON output
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Approve or deny a pending consent request
+    ///
+    /// Examples:
+    ///   skills approve --list
+    ///   skills approve 3f2a9c1b7d4e
+    ///   skills approve 3f2a9c1b7d4e --deny
+    Approve {
+        /// Consent request id (shown in the exec denial message)
+        request_id: Option<String>,
+
+        /// List pending requests
+        #[arg(short, long)]
+        list: bool,
+
+        /// Deny the request instead of approving it
+        #[arg(long)]
+        deny: bool,
+
+        /// JSON output
+        #[arg(short, long)]
+        json: bool,
+    },
+
     /// Validate configuration and skills
     Validate,
 
@@ -370,6 +441,10 @@ enum ServerMode {
         /// Bind address
         #[arg(short, long, default_value = "127.0.0.1:8000")]
         bind: String,
+
+        /// Serve the consent approval endpoints on this loopback address
+        #[arg(long)]
+        consent_bind: Option<String>,
     },
 }
 
@@ -395,6 +470,9 @@ struct Config {
     #[serde(default, skip_serializing_if = "is_default_use_global")]
     use_global: UseGlobalSettings,
 
+    #[serde(default, skip_serializing_if = "is_default_index_config")]
+    index: IndexConfig,
+
     /// Agent Skills repositories to auto-sync
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     agent_skills_repos: Vec<skillsrs::storage::sync::AgentSkillsRepoConfig>,
@@ -411,6 +489,7 @@ impl Config {
             && is_default_paths_config(&self.paths)
             && is_default_sandbox_config(&self.sandbox)
             && is_default_use_global(&self.use_global)
+            && is_default_index_config(&self.index)
     }
 }
 
@@ -431,6 +510,13 @@ fn is_default_policy_config(cfg: &PolicyConfig) -> bool {
         && cfg.max_exec_ms == default.max_exec_ms
         && cfg.allow_patterns == default.allow_patterns
         && cfg.deny_patterns == default.deny_patterns
+        && cfg.consent_ttl_secs == default.consent_ttl_secs
+        && cfg.argument_rules == default.argument_rules
+        && cfg.rate_limits == default.rate_limits
+}
+
+fn is_default_index_config(cfg: &IndexConfig) -> bool {
+    *cfg == IndexConfig::default()
 }
 
 fn is_default_paths_config(cfg: &PathsConfig) -> bool {
@@ -632,6 +718,10 @@ fn merge_config(base: &mut Config, overlay: Config) {
         base.policy = overlay.policy;
     }
 
+    if !is_default_index_config(&overlay.index) {
+        base.index = overlay.index;
+    }
+
     // ServerConfig has defaults; only override when values differ from defaults.
     let d = ServerConfig::default();
     if overlay.server.bind != d.bind
@@ -681,8 +771,8 @@ async fn init_server(
     info!("Using skills root: {}", paths.skills_root.display());
     info!("Using database: {}", paths.database_path.display());
 
-    // Initialize persistence layer for FTS search
-    let _persistence = Arc::new(
+    // Initialize persistence layer (execution history, FTS search)
+    let persistence = Arc::new(
         PersistenceLayer::new(&paths.database_path)
             .await
             .context("Failed to initialize persistence")?,
@@ -695,15 +785,74 @@ async fn init_server(
     // Create registry
     let registry = Arc::new(Registry::new());
 
-    // Create search engine
-    let search_engine = Arc::new(SearchEngine::new(registry.clone()));
+    // Create search engine (switches to FTS5 for large catalogs)
+    let mut search_engine = SearchEngine::new(registry.clone())
+        .with_fts(persistence.clone(), config.index.fts_threshold);
+    if config.index.feedback {
+        search_engine = search_engine.with_feedback(persistence.clone());
+    }
+    if let Some(model_path) = &config.index.embedding_model {
+        match EmbeddingModel::load(model_path) {
+            Ok(model) => {
+                info!(
+                    "Semantic search enabled: {} ({} words)",
+                    model_path.display(),
+                    model.vocabulary_size()
+                );
+                search_engine =
+                    search_engine.with_semantic(Arc::new(model), Some(persistence.clone()));
+            }
+            Err(e) => warn!(
+                "Failed to load embedding model {}: {}",
+                model_path.display(),
+                e
+            ),
+        }
+    }
+    let search_engine = Arc::new(search_engine);
 
     // Create policy engine
-    let policy_engine = Arc::new(PolicyEngine::new(config.policy.clone())?);
+    let policy_engine =
+        Arc::new(PolicyEngine::new(config.policy.clone())?.with_persistence(persistence.clone()));
+    if let Err(e) = policy_engine.restore_usage().await {
+        warn!("Failed to restore rate limit counters: {}", e);
+    }
+    let consent = Arc::new(
+        ConsentManager::new(persistence.clone())
+            .await
+            .context("Failed to initialize consent grants")?
+            .with_ttl_secs(policy_engine.consent_ttl_secs()),
+    );
+
+    // Warm start: cached upstream tools are searchable until their servers reconnect
+    let configured: Vec<String> = config.upstreams.iter().map(|u| u.alias.clone()).collect();
+    if let Err(e) = persistence.warm_start(&registry, &configured).await {
+        warn!("Failed to load cached tools: {}", e);
+    }
+    persistence::spawn_registry_sync(persistence.clone(), &registry);
 
     // Create upstream manager and connect to upstreams
     let upstream_manager = Arc::new(UpstreamManager::new(registry.clone()));
     for upstream_config in &config.upstreams {
+        if !registry.get_by_server(&upstream_config.alias).is_empty() {
+            // Cached tools already serve search/schema; connect without blocking startup
+            info!(
+                "Connecting to upstream in background: {}",
+                upstream_config.alias
+            );
+            let manager = upstream_manager.clone();
+            let upstream_config = upstream_config.clone();
+            tokio::spawn(async move {
+                if let Err(e) = manager.add_upstream(upstream_config.clone()).await {
+                    error!(
+                        "Failed to connect to upstream {}: {}",
+                        upstream_config.alias, e
+                    );
+                }
+            });
+            continue;
+        }
+
         info!("Connecting to upstream: {}", upstream_config.alias);
         if let Err(e) = upstream_manager.add_upstream(upstream_config.clone()).await {
             error!(
@@ -721,11 +870,12 @@ async fn init_server(
     if no_sandbox {
         sandbox_config.backend = SandboxBackend::None;
     }
-    let runtime = Arc::new(Runtime::with_sandbox_config(
-        registry.clone(),
-        upstream_manager.clone(),
-        sandbox_config,
-    ));
+    let runtime = Arc::new(
+        Runtime::with_sandbox_config(registry.clone(), upstream_manager.clone(), sandbox_config)
+            .with_max_calls_per_skill(policy_engine.max_calls_per_skill())
+            .with_policy_engine(policy_engine.clone())
+            .with_persistence(persistence.clone()),
+    );
 
     // Sync Agent Skills from config before loading
     if !config.agent_skills_repos.is_empty() {
@@ -839,12 +989,25 @@ async fn init_server(
 
     // Create MCP server
     let server = SkillsServer::new(registry, search_engine, policy_engine, runtime, skill_store)
-        .with_upstream_manager(upstream_manager);
+        .with_upstream_manager(upstream_manager)
+        .with_persistence(persistence)
+        .with_consent(consent);
 
     info!("Server initialized successfully");
     Ok(server)
 }
 
+/// Open the database for execution history, continuing without it on failure
+async fn open_history(paths: &SkillsPaths) -> Option<Arc<PersistenceLayer>> {
+    match PersistenceLayer::new(&paths.database_path).await {
+        Ok(persistence) => Some(Arc::new(persistence)),
+        Err(e) => {
+            warn!("Execution history disabled: {}", e);
+            None
+        }
+    }
+}
+
 /// Print the stylish SKILLS banner
 fn print_banner() {
     eprintln!(
@@ -1006,12 +1169,31 @@ use_global:
                     service.waiting().await?;
                 }
 
-                ServerMode::Http { bind } => {
+                ServerMode::Http { bind, consent_bind } => {
+                    // Consent approval is unauthenticated, so keep it off anything the agent shares
+                    let consent_addr = consent_bind
+                        .map(|addr| -> Result<std::net::SocketAddr> {
+                            let addr: std::net::SocketAddr = addr.parse().with_context(|| {
+                                format!("Invalid --consent-bind address: {}", addr)
+                            })?;
+                            if !addr.ip().is_loopback() {
+                                anyhow::bail!(
+                                    "--consent-bind must be a loopback address, got {}",
+                                    addr
+                                );
+                            }
+                            Ok(addr)
+                        })
+                        .transpose()?;
+
                     print_banner();
                     info!("Starting skills.rs in HTTP mode on {}", bind);
                     eprintln!("Mode: HTTP");
                     eprintln!("Listening on: http://{}", bind);
                     eprintln!("MCP Endpoint: http://{}/mcp", bind);
+                    if let Some(addr) = consent_addr {
+                        eprintln!("Consent approvals: http://{}/consent", addr);
+                    }
                     eprintln!("Exposing 4 tools: search, schema, exec, manage");
                     eprintln!("Skills directory: {}", paths.skills_root.display());
                     if no_sandbox {
@@ -1021,55 +1203,21 @@ use_global:
 
                     let server = init_server(&config, &paths, no_sandbox).await?;
 
-                    // Create HTTP service
-                    use rmcp::transport::streamable_http_server::{
-                        session::local::LocalSessionManager,
-                        tower::{StreamableHttpServerConfig, StreamableHttpService},
-                    };
-
-                    let mcp_service = StreamableHttpService::new(
-                        move || Ok(server.clone()),
-                        LocalSessionManager::default().into(),
-                        StreamableHttpServerConfig::default(),
-                    );
+                    if let Some(addr) = consent_addr {
+                        let consent = server
+                            .consent_manager()
+                            .context("Consent grants are not enabled")?;
+                        let listener = tokio::net::TcpListener::bind(addr).await?;
+                        info!("Consent approvals listening on {}", addr);
+                        tokio::spawn(async move {
+                            let app = skillsrs::mcp::http::consent_router(consent);
+                            if let Err(e) = axum::serve(listener, app).await {
+                                error!("Consent listener failed: {}", e);
+                            }
+                        });
+                    }
 
-                    // Create router
-                    let app = axum::Router::new()
-                        .route(
-                            "/",
-                            axum::routing::get(|| async {
-                                axum::response::Html(
-                                    r#"
-                                <!DOCTYPE html>
-                                <html>
-                                <head><title>skills.rs</title></head>
-                                <body>
-                                    <h1>skills.rs - Infinite Skills. Finite Context.</h1>
-                                    <p>MCP endpoint available at: <a href="/mcp">/mcp</a></p>
-                                    <p>Exposes exactly 4 tools:</p>
-                                    <ul>
-                                        <li><code>search</code> - Discovery over registry</li>
-                                        <li><code>schema</code> - On-demand schema fetching</li>
-                                        <li><code>exec</code> - Validated execution</li>
-                                        <li><code>manage</code> - Skill lifecycle management</li>
-                                    </ul>
-                                </body>
-                                </html>
-                                "#,
-                                )
-                            }),
-                        )
-                        .route(
-                            "/health",
-                            axum::routing::get(|| async {
-                                axum::response::Json(serde_json::json!({
-                                    "status": "healthy",
-                                    "service": "skills.rs",
-                                    "version": env!("CARGO_PKG_VERSION")
-                                }))
-                            }),
-                        )
-                        .nest_service("/mcp", mcp_service);
+                    let app = skillsrs::mcp::http::router(server);
 
                     // Start server
                     let listener = tokio::net::TcpListener::bind(&bind).await?;
@@ -1208,11 +1356,15 @@ use_global:
             if no_sandbox {
                 sandbox_config.backend = SandboxBackend::None;
             }
-            let runtime = Arc::new(Runtime::with_sandbox_config(
+            let mut runtime = Runtime::with_sandbox_config(
                 registry.clone(),
                 upstream_manager.clone(),
                 sandbox_config,
-            ));
+            );
+            if let Some(persistence) = open_history(&paths).await {
+                runtime = runtime.with_persistence(persistence);
+            }
+            let runtime = Arc::new(runtime);
 
             // Connect to upstreams
             for upstream_config in &config.upstreams {
@@ -1283,6 +1435,7 @@ use_global:
                 arguments: arguments.clone(),
                 timeout_ms: Some(30000),
                 trace_enabled: false,
+                session: None,
             };
 
             match runtime.execute(exec_context).await {
@@ -1339,11 +1492,15 @@ use_global:
             if no_sandbox {
                 sandbox_config.backend = SandboxBackend::None;
             }
-            let runtime = Arc::new(Runtime::with_sandbox_config(
+            let mut runtime = Runtime::with_sandbox_config(
                 registry.clone(),
                 upstream_manager.clone(),
                 sandbox_config,
-            ));
+            );
+            if let Some(persistence) = open_history(&paths).await {
+                runtime = runtime.with_persistence(persistence);
+            }
+            let runtime = Arc::new(runtime);
 
             // Connect to upstreams
             for upstream_config in &config.upstreams {
@@ -1399,6 +1556,7 @@ use_global:
                 arguments: arguments.clone(),
                 timeout_ms: Some(30000),
                 trace_enabled: false,
+                session: None,
             };
 
             match runtime.execute(exec_context).await {
@@ -1422,6 +1580,7 @@ use_global:
         Commands::Grep {
             pattern,
             descriptions,
+            mode,
         } => {
             // Initialize components
             let registry = Arc::new(Registry::new());
@@ -1445,14 +1604,36 @@ use_global:
 
             let callables = registry.all();
 
-            // Simple glob matching
-            let pattern = pattern.replace('*', ".*");
-            let re = regex::Regex::new(&format!("(?i){}", pattern))?;
-
-            let matches: Vec<_> = callables
-                .iter()
-                .filter(|c| re.is_match(&c.name) || re.is_match(&c.fq_name))
-                .collect();
+            let matches: Vec<_> = if let Some(mode) = mode {
+                let search_engine = SearchEngine::new(registry.clone());
+                let results = search_engine
+                    .search(&SearchQuery {
+                        q: pattern.clone(),
+                        limit: callables.len().max(1),
+                        mode,
+                        ..Default::default()
+                    })
+                    .await?;
+                results
+                    .matches
+                    .iter()
+                    .filter_map(|m| callables.iter().find(|c| c.id.as_str() == m.id))
+                    .collect()
+            } else {
+                // Simple glob matching, narrowed by the same inline filters as `search`
+                let parsed = parse_query(&pattern)?;
+                let glob = if parsed.text.is_empty() {
+                    "*"
+                } else {
+                    parsed.text.as_str()
+                };
+                let re = regex::Regex::new(&format!("(?i){}", glob.replace('*', ".*")))?;
+                callables
+                    .iter()
+                    .filter(|c| re.is_match(&c.name) || re.is_match(&c.fq_name))
+                    .filter(|c| parsed.matches(c))
+                    .collect()
+            };
 
             if matches.is_empty() {
                 println!("No tools found matching pattern: {}", pattern);
@@ -1474,6 +1655,118 @@ use_global:
             }
         }
 
+        Commands::History {
+            callable,
+            since,
+            until,
+            status,
+            limit,
+            json,
+        } => {
+            let persistence = PersistenceLayer::new(&paths.database_path)
+                .await
+                .context("Failed to open database")?;
+
+            let filter = HistoryFilter {
+                callable,
+                since: since
+                    .map(|s| parse_history_time(&s))
+                    .transpose()
+                    .context("Invalid --since")?,
+                until: until
+                    .map(|s| parse_history_time(&s))
+                    .transpose()
+                    .context("Invalid --until")?,
+                status: status
+                    .map(|s| s.parse::<ExecutionStatus>())
+                    .transpose()
+                    .context("Invalid --status")?,
+                limit,
+            };
+            let records = persistence.query_execution_history(&filter).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&records)?);
+            } else if records.is_empty() {
+                println!("No executions found");
+            } else {
+                for record in &records {
+                    let id = CallableId::from(record.callable_id.as_str());
+                    let name = match (id.server_alias(), id.tool_name(), id.skill_name()) {
+                        (Some(server), Some(tool), _) => format!("{}/{}", server, tool),
+                        (_, _, Some(skill)) => format!("skill/{}", skill),
+                        _ => record.callable_id.clone(),
+                    };
+                    println!(
+                        "{}  {}  {:>7}  {}",
+                        record.started_at.format("%Y-%m-%d %H:%M:%S"),
+                        if record.is_error { "✗" } else { "✓" },
+                        record
+                            .duration_ms
+                            .map(|d| format!("{}ms", d))
+                            .unwrap_or_default(),
+                        name
+                    );
+                }
+            }
+        }
+
+        Commands::Approve {
+            request_id,
+            list,
+            deny,
+            json,
+        } => {
+            let persistence = PersistenceLayer::new(&paths.database_path)
+                .await
+                .context("Failed to open database")?;
+            let consent = ConsentManager::new(Arc::new(persistence))
+                .await?
+                .with_ttl_secs(config.policy.consent_ttl_secs);
+
+            match request_id {
+                Some(id) if !list => {
+                    if deny {
+                        consent.deny(&id).await?;
+                        eprintln!("✗ Denied consent request {}", id);
+                    } else {
+                        let request = consent.get(&id).await?;
+                        let grant = consent.approve(&id).await?;
+                        if json {
+                            println!("{}", serde_json::to_string_pretty(&grant)?);
+                        } else {
+                            eprintln!(
+                                "✓ Approved {} for {} (expires {})",
+                                grant.level,
+                                request.fq_name,
+                                grant.expires_at.format("%Y-%m-%d %H:%M:%S UTC")
+                            );
+                            eprintln!("Pass this token as consent.token when retrying exec:");
+                            println!("{}", grant.token);
+                        }
+                    }
+                }
+                _ => {
+                    let requests = consent.list_pending().await?;
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&requests)?);
+                    } else if requests.is_empty() {
+                        println!("No pending consent requests");
+                    } else {
+                        for request in &requests {
+                            println!(
+                                "{}  {}  {:<15}  {}",
+                                request.id,
+                                request.created_at.format("%Y-%m-%d %H:%M:%S"),
+                                request.level,
+                                request.fq_name
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
         Commands::Validate => {
             info!("Validating configuration");
             eprintln!("Validating skills.rs configuration...\n");
@@ -1491,6 +1784,7 @@ use_global:
                 config.policy.max_calls_per_skill
             );
             eprintln!("  Max exec time: {}ms", config.policy.max_exec_ms);
+            eprintln!("  Consent TTL: {}s", config.policy.consent_ttl_secs);
 
             // Validate upstreams
             eprintln!("\n✓ Upstream configuration:");
@@ -1818,7 +2112,14 @@ agent_skills_repos:
 
             // Create minimal components for search testing
             let registry = Arc::new(Registry::new());
-            let search_engine = Arc::new(SearchEngine::new(registry.clone()));
+            let mut search_engine = SearchEngine::new(registry.clone());
+            if let Some(model_path) = &config.index.embedding_model {
+                let model = EmbeddingModel::load(model_path).with_context(|| {
+                    format!("Failed to load embedding model {}", model_path.display())
+                })?;
+                search_engine = search_engine.with_semantic(Arc::new(model), None);
+            }
+            let search_engine = Arc::new(search_engine);
 
             // Initialize upstream manager and connect
             let upstream_manager = Arc::new(UpstreamManager::new(registry.clone()));
@@ -1834,12 +2135,18 @@ agent_skills_repos:
             // Rebuild search index
             search_engine.rebuild();
 
-            let search_query = skillsrs::storage::search::SearchQuery {
+            let search_query = SearchQuery {
                 q: query.clone(),
                 kind: kind.unwrap_or_else(|| "any".to_string()),
                 limit: 10,
                 filters: None,
                 cursor: None,
+                mode: SearchMode::Literal,
+                session: None,
+                include: SearchInclude {
+                    signature: true,
+                    ..Default::default()
+                },
             };
 
             let results = search_engine.search(&search_query).await?;
@@ -1860,6 +2167,9 @@ agent_skills_repos:
                     eprintln!("   Server: {}", server);
                 }
                 eprintln!("   Score: {:.2}", result.score);
+                if let Some(signature) = &result.signature_short {
+                    eprintln!("   Signature: {}", signature);
+                }
                 eprintln!("   Description: {}", result.description_snippet);
                 eprintln!();
             }
@@ -2106,6 +2416,9 @@ agent_skills_repos:
                         tags,
                         sandbox_config: None,
                         description,
+                        annotations: Default::default(),
+                        restart: Default::default(),
+                        max_concurrency: None,
                     }
                 }
                 TransportType::Stdio => {
@@ -2127,6 +2440,9 @@ agent_skills_repos:
                         tags,
                         sandbox_config: None,
                         description,
+                        annotations: Default::default(),
+                        restart: Default::default(),
+                        max_concurrency: None,
                     }
                 }
             };
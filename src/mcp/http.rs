@@ -0,0 +1,101 @@
+//! HTTP routers for `skills server http`
+//!
+//! [`router`] serves the MCP endpoint to agents. Consent approval lives on a
+//! separate [`consent_router`]: an agent that could reach it could approve its
+//! own consent requests, so it is never merged into the MCP app and is served
+//! only on a loopback listener of its own.
+
+use crate::core::consent::{ConsentError, ConsentManager};
+use crate::mcp::SkillsServer;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Json, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use rmcp::transport::streamable_http_server::{
+    session::local::LocalSessionManager,
+    tower::{StreamableHttpServerConfig, StreamableHttpService},
+};
+use std::sync::Arc;
+
+const INDEX_PAGE: &str = r#"
+<!DOCTYPE html>
+<html>
+<head><title>skills.rs</title></head>
+<body>
+    <h1>skills.rs - Infinite Skills. Finite Context.</h1>
+    <p>MCP endpoint available at: <a href="/mcp">/mcp</a></p>
+    <p>Exposes exactly 4 tools:</p>
+    <ul>
+        <li><code>search</code> - Discovery over registry</li>
+        <li><code>schema</code> - On-demand schema fetching</li>
+        <li><code>exec</code> - Validated execution</li>
+        <li><code>manage</code> - Skill lifecycle management</li>
+    </ul>
+</body>
+</html>
+"#;
+
+/// The agent-facing app: an index page, `/health`, and the MCP endpoint at `/mcp`
+pub fn router(server: SkillsServer) -> Router {
+    let mcp_service = StreamableHttpService::new(
+        move || Ok(server.clone()),
+        LocalSessionManager::default().into(),
+        StreamableHttpServerConfig::default(),
+    );
+
+    Router::new()
+        .route("/", get(|| async { Html(INDEX_PAGE) }))
+        .route(
+            "/health",
+            get(|| async {
+                Json(serde_json::json!({
+                    "status": "healthy",
+                    "service": "skills.rs",
+                    "version": env!("CARGO_PKG_VERSION")
+                }))
+            }),
+        )
+        .nest_service("/mcp", mcp_service)
+}
+
+/// Routes for approving consent requests out of band
+///
+/// Unauthenticated; serve them only where the agent cannot reach them.
+pub fn consent_router(consent: Arc<ConsentManager>) -> Router {
+    Router::new()
+        .route("/consent", get(list))
+        .route("/consent/{id}/approve", post(approve))
+        .route("/consent/{id}/deny", post(deny))
+        .with_state(consent)
+}
+
+/// HTTP status for a failed consent operation
+fn consent_error_status(error: &ConsentError) -> StatusCode {
+    match error {
+        ConsentError::NotFound(_) => StatusCode::NOT_FOUND,
+        ConsentError::InvalidState { .. } | ConsentError::Expired(_) => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn list(State(consent): State<Arc<ConsentManager>>) -> Response {
+    match consent.list_pending().await {
+        Ok(requests) => Json(requests).into_response(),
+        Err(e) => (consent_error_status(&e), e.to_string()).into_response(),
+    }
+}
+
+async fn approve(State(consent): State<Arc<ConsentManager>>, Path(id): Path<String>) -> Response {
+    match consent.approve(&id).await {
+        Ok(grant) => Json(grant).into_response(),
+        Err(e) => (consent_error_status(&e), e.to_string()).into_response(),
+    }
+}
+
+async fn deny(State(consent): State<Arc<ConsentManager>>, Path(id): Path<String>) -> Response {
+    match consent.deny(&id).await {
+        Ok(()) => Json(serde_json::json!({ "request_id": id, "status": "denied" })).into_response(),
+        Err(e) => (consent_error_status(&e), e.to_string()).into_response(),
+    }
+}
@@ -14,16 +14,26 @@
 //! - 1 management tool (manage) for skill lifecycle, keeping the context minimal while enabling full CRUD
 //! - This balance achieves "Infinite Skills. Finite Context." - agents can manage skills without tool bloat
 
+pub mod http;
+
+use crate::core::budget::{estimate_tokens, SchemaReduction, BYTES_PER_TOKEN};
+use crate::core::consent::ConsentManager;
+use crate::core::examples;
+use crate::core::limits::DEFAULT_SESSION;
+use crate::core::persistence::{self, ExecutionStatus, HistoryFilter, PersistenceLayer};
 use crate::core::policy::{ConsentLevel, PolicyEngine};
 use crate::core::registry::Registry;
-use crate::core::{CallableId, ToolResult};
+use crate::core::render::{Rendering, SchemaFormat};
+use crate::core::{CallableId, CoreError, ToolResult};
 use crate::execution::{ExecContext, Runtime};
-use crate::storage::search::{SearchEngine, SearchFilters, SearchQuery};
+use crate::storage::search::{SearchEngine, SearchFilters, SearchInclude, SearchMode, SearchQuery};
 use crate::storage::{CreateSkillRequest, SkillStore};
 use rmcp::{
     handler::server::router::tool::ToolRouter,
     handler::server::wrapper::Parameters,
-    model::{CallToolResult, Content, Implementation, ServerCapabilities, ServerInfo},
+    model::{
+        CallToolResult, Content, Implementation, ResourceContents, ServerCapabilities, ServerInfo,
+    },
     service::RequestContext,
     tool, tool_router, Json, RoleServer, ServerHandler,
 };
@@ -42,6 +52,8 @@ pub struct SkillsServer {
     runtime: Arc<Runtime>,
     skill_store: Arc<SkillStore>,
     upstream_manager: Option<Arc<crate::execution::upstream::UpstreamManager>>,
+    persistence: Option<Arc<PersistenceLayer>>,
+    consent: Option<Arc<ConsentManager>>,
     tool_router: ToolRouter<Self>,
 }
 
@@ -49,6 +61,9 @@ pub struct SkillsServer {
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SearchInput {
     /// Search query
+    #[schemars(
+        description = "Search query. Inline filters narrow results: server:<alias> kind:tool|skill risk:<=writes tag:<t> -tag:<t> param:<name> net:true|false fs:true|false output:true|false calls:<=n duration:<ms version:^1.2 (prefix any filter with - to negate)"
+    )]
     pub q: String,
 
     /// Filter by callable kind
@@ -62,6 +77,13 @@ pub struct SearchInput {
     #[schemars(schema_with = "non_negative_int_schema")]
     pub limit: usize,
 
+    /// How to interpret the query
+    #[serde(default)]
+    #[schemars(
+        description = "Query mode: literal (ranked terms, default), regex, or fuzzy (tolerates misspelled names)"
+    )]
+    pub mode: SearchMode,
+
     /// Filters for search results
     #[serde(default)]
     pub filters: Option<SearchFilters>,
@@ -70,9 +92,17 @@ pub struct SearchInput {
     #[serde(default)]
     pub include: Option<IncludeOptions>,
 
-    /// Pagination cursor
+    /// Opaque `next_cursor` from the previous page of the same query
     #[serde(default)]
     pub cursor: Option<String>,
+
+    /// Approximate response size limit in tokens
+    #[serde(default)]
+    #[schemars(
+        description = "Approximate response size limit in tokens; optional match fields and then trailing matches are dropped to fit"
+    )]
+    #[schemars(schema_with = "optional_non_negative_int_schema")]
+    pub max_tokens: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -115,6 +145,14 @@ fn non_negative_int_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Sc
     .unwrap()
 }
 
+fn optional_non_negative_int_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    serde_json::from_value(serde_json::json!({
+        "type": ["integer", "null"],
+        "minimum": 0
+    }))
+    .unwrap()
+}
+
 /// Schema for bundled_files: array of [filename, content] tuples (or null).
 fn bundled_files_schema(_gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
     serde_json::from_value(serde_json::json!({
@@ -141,6 +179,20 @@ pub struct SearchOutput {
     pub matches: Vec<JsonValue>,
     pub next_cursor: Option<String>,
     pub stats: SearchStats,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetReport>,
+}
+
+/// How a response was fitted to its token budget
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BudgetReport {
+    #[schemars(schema_with = "non_negative_int_schema")]
+    pub max_tokens: usize,
+    /// Estimate for this response (about 4 bytes per token)
+    #[schemars(schema_with = "non_negative_int_schema")]
+    pub estimated_tokens: usize,
+    /// What was left out to fit, in the order it was dropped
+    pub elided: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -164,21 +216,35 @@ pub struct SchemaInput {
 
     /// Schema format to return
     #[serde(default = "default_format")]
-    #[schemars(description = "Format: json_schema, signature, or both")]
+    #[schemars(
+        description = "Format: json_schema, signature, both, typescript (interface), outline (`*` marks required fields), or minimal (JSON Schema with $refs inlined and noise stripped)"
+    )]
     pub format: String,
 
     /// Include output schema if available
     #[serde(default = "default_true")]
     pub include_output_schema: bool,
 
-    /// Maximum response size in bytes
+    /// Maximum response size in bytes; larger responses are reduced as for `max_tokens`
     #[serde(default = "default_max_bytes")]
     #[schemars(schema_with = "non_negative_int_schema")]
     pub max_bytes: usize,
 
+    /// Approximate response size limit in tokens
+    #[serde(default)]
+    #[schemars(
+        description = "Approximate response size limit in tokens; descriptions, long enums, and nested schemas (as $ref handles fetchable via json_pointer) are dropped to fit"
+    )]
+    #[schemars(schema_with = "optional_non_negative_int_schema")]
+    pub max_tokens: Option<usize>,
+
     /// JSON Pointer to schema subtree
     #[serde(default)]
     pub json_pointer: Option<String>,
+
+    /// Prefer arguments from past successful executions as signature examples
+    #[serde(default)]
+    pub examples_from_history: bool,
 }
 
 fn default_format() -> String {
@@ -204,6 +270,12 @@ pub struct SchemaOutput {
     pub output_schema: Option<JsonValue>,
     #[schemars(schema_with = "json_value_schema")]
     pub signature: Option<JsonValue>,
+    /// Text rendering with its `bytes` and estimated `tokens`, for `typescript`, `outline`, and `minimal`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "json_value_schema")]
+    pub rendering: Option<JsonValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetReport>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -214,6 +286,9 @@ pub struct CallableInfo {
     pub fq_name: String,
     pub server: Option<String>,
     pub version: Option<String>,
+    /// Schema comes from the persistent cache; the server has not reconnected yet
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
 }
 
 /// Input schema for exec
@@ -243,10 +318,15 @@ pub struct ExecInput {
     pub trace: Option<TraceArgs>,
 }
 
+/// Consent for risk-gated callables.
+///
+/// A self-declared `level` is informational only; elevated consent is
+/// honored solely through a `token` issued by an out-of-band approval.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ConsentArgs {
     #[serde(default)]
     pub level: Option<String>,
+    /// Approval token from `skills approve <request-id>`
     #[serde(default)]
     pub token: Option<String>,
 }
@@ -291,6 +371,8 @@ impl SkillsServer {
             runtime,
             skill_store,
             upstream_manager: None,
+            persistence: None,
+            consent: None,
             tool_router: Self::tool_router(),
         }
     }
@@ -300,8 +382,144 @@ impl SkillsServer {
         self
     }
 
+    /// Enable the `history` manage operation
+    pub fn with_persistence(mut self, persistence: Arc<PersistenceLayer>) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// Enable consent requests and token verification for risk-gated callables
+    pub fn with_consent(mut self, consent: Arc<ConsentManager>) -> Self {
+        self.consent = Some(consent);
+        self
+    }
+
+    /// Consent manager used by `exec`, if consent grants are enabled
+    pub fn consent_manager(&self) -> Option<Arc<ConsentManager>> {
+        self.consent.clone()
+    }
+
+    /// Run an `exec` call: policy, validation, consent, rate limits, then the callable
+    async fn execute_callable(
+        &self,
+        input: ExecInput,
+        extensions: &rmcp::model::Extensions,
+    ) -> Result<CallToolResult, String> {
+        debug!("exec called for: {}", input.id);
+
+        let callable_id: CallableId = input.id.into();
+        let dry_run = input.dry_run;
+
+        // Get callable record
+        let record = self
+            .registry
+            .get(&callable_id)
+            .ok_or_else(|| format!("Callable not found: {}", callable_id))?;
+
+        // Authorize without consent first; elevated levels come only from a verified token
+        let mut policy_result = self
+            .policy_engine
+            .authorize(&record, &input.arguments, ConsentLevel::None)
+            .await
+            .map_err(|e| format!("Policy check failed: {}", e))?;
+
+        let token = input.consent.as_ref().and_then(|c| c.token.as_deref());
+        let mut grant = None;
+        if let (Some(required), Some(consent)) = (policy_result.required_consent, &self.consent) {
+            match token {
+                Some(token) => {
+                    let level = consent
+                        .verify(token, &callable_id, &input.arguments)
+                        .await
+                        .map_err(|e| format!("Execution denied: {}", e))?;
+                    policy_result = self
+                        .policy_engine
+                        .authorize(&record, &input.arguments, level)
+                        .await
+                        .map_err(|e| format!("Policy check failed: {}", e))?;
+                    grant = Some((consent, token));
+                }
+                None => {
+                    let request = consent
+                        .request(&record, &input.arguments, required, &policy_result.reason)
+                        .await
+                        .map_err(|e| format!("Failed to create consent request: {}", e))?;
+                    warn!("Execution pending consent: {}", request.id);
+                    return Err(format!(
+                        "Execution denied: {}. Consent request {} is pending; ask the user to run \
+                         `skills approve {}`, then retry exec with the same arguments and \
+                         consent.token set to the issued token",
+                        policy_result.reason, request.id, request.id
+                    ));
+                }
+            }
+        }
+
+        if !policy_result.allowed {
+            warn!("Execution denied: {}", policy_result.reason);
+            return Err(format!("Execution denied: {}", policy_result.reason));
+        }
+
+        self.runtime
+            .validate_arguments(&record.input_schema, &input.arguments)
+            .map_err(|e| {
+                let stage = if dry_run { "Dry run" } else { "Execution" };
+                format!("{} failed: {}", stage, e)
+            })?;
+
+        if dry_run {
+            info!("Dry run: would execute {}", record.fq_name);
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "Dry run: would execute {}",
+                record.fq_name
+            ))]));
+        }
+
+        // Spend the grant only once the call is about to run
+        if let Some((consent, token)) = grant {
+            consent
+                .consume(token, &callable_id, &input.arguments)
+                .await
+                .map_err(|e| format!("Execution denied: {}", e))?;
+        }
+
+        // Charged last, so rejected calls never use up quota
+        let session = mcp_session(extensions);
+        self.policy_engine
+            .check_rate_limits(&record, session)
+            .await
+            .map_err(|e| format!("Execution denied: {}", e))?;
+
+        // Execute
+        let ctx = ExecContext {
+            callable_id: callable_id.clone(),
+            arguments: input.arguments,
+            timeout_ms: input.timeout_ms,
+            trace_enabled: input
+                .trace
+                .as_ref()
+                .map(|t| t.include_route || t.include_timing || t.include_steps)
+                .unwrap_or(false),
+            session: Some(session.to_string()),
+        };
+
+        let result = self.runtime.execute(ctx).await;
+        self.search_engine
+            .record_outcome(
+                session,
+                &callable_id,
+                result.as_ref().is_ok_and(|r| !r.is_error),
+            )
+            .await;
+        let result = result.map_err(|e| format!("Execution failed: {}", e))?;
+
+        info!("exec completed for {}", record.fq_name);
+
+        // Pass content items, structured content, and the error flag through
+        Ok(Self::tool_result_to_call_result(result))
+    }
+
     /// Convert ToolResult to CallToolResult
-    #[allow(dead_code)]
     fn tool_result_to_call_result(result: ToolResult) -> CallToolResult {
         let mut contents = Vec::new();
 
@@ -314,27 +532,75 @@ impl SkillsServer {
                     contents.push(Content::image(data, mime_type));
                 }
                 crate::core::ToolResultContent::Resource { resource } => {
-                    // Convert resource to text representation
-                    let text = resource.text.unwrap_or_else(|| {
-                        format!(
-                            "Resource: {} ({})",
-                            resource.uri,
-                            resource.mime_type.unwrap_or_default()
-                        )
-                    });
-                    contents.push(Content::text(text));
+                    let embedded = match (resource.text, resource.blob) {
+                        (Some(text), _) => ResourceContents::TextResourceContents {
+                            uri: resource.uri,
+                            mime_type: resource.mime_type,
+                            text,
+                            meta: None,
+                        },
+                        (None, Some(blob)) => ResourceContents::BlobResourceContents {
+                            uri: resource.uri,
+                            mime_type: resource.mime_type,
+                            blob,
+                            meta: None,
+                        },
+                        // Nothing to embed; describe the resource instead
+                        (None, None) => {
+                            contents.push(Content::text(format!(
+                                "Resource: {} ({})",
+                                resource.uri,
+                                resource.mime_type.unwrap_or_default()
+                            )));
+                            continue;
+                        }
+                    };
+                    contents.push(Content::resource(embedded));
                 }
             }
         }
 
-        if result.is_error {
+        let mut call_result = if result.is_error {
             CallToolResult::error(contents)
         } else {
             CallToolResult::success(contents)
-        }
+        };
+        call_result.structured_content = result.structured_content;
+        call_result
     }
 }
 
+/// Session of an MCP request
+///
+/// Streamable HTTP requests carry their session id; stdio is a single session.
+fn mcp_session(extensions: &rmcp::model::Extensions) -> &str {
+    extensions
+        .get::<::http::request::Parts>()
+        .and_then(|parts| parts.headers.get("mcp-session-id"))
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or(DEFAULT_SESSION)
+}
+
+/// Recent executions scanned for example arguments
+const HISTORY_EXAMPLE_SCAN: i64 = 20;
+
+/// Optional search match fields, dropped in this order to fit a token budget
+const SEARCH_ELIDABLE_FIELDS: &[&str] = &[
+    "explanation",
+    "section",
+    "description_snippet",
+    "signature_short",
+    "uses",
+];
+
+/// Remove a field from every match, returning how many had it
+fn strip_field(matches: &mut [JsonValue], field: &str) -> usize {
+    matches
+        .iter_mut()
+        .filter_map(|m| m.as_object_mut()?.remove(field))
+        .count()
+}
+
 /// Implement the tool router with exactly 4 tools
 #[tool_router]
 impl SkillsServer {
@@ -347,6 +613,7 @@ impl SkillsServer {
     async fn search(
         &self,
         Parameters(input): Parameters<SearchInput>,
+        extensions: rmcp::model::Extensions,
     ) -> Result<Json<SearchOutput>, String> {
         debug!("search called with query: {}", input.q);
 
@@ -356,9 +623,22 @@ impl SkillsServer {
             limit: input.limit.clamp(1, 50),
             filters: input.filters,
             cursor: input.cursor,
+            mode: input.mode,
+            session: Some(mcp_session(&extensions).to_string()),
+            include: input
+                .include
+                .map(|include| {
+                    let defaults = SearchInclude::default();
+                    SearchInclude {
+                        signature: include.signature.unwrap_or(defaults.signature),
+                        schema_digest: include.schema_digest.unwrap_or(defaults.schema_digest),
+                        uses: include.uses.unwrap_or(defaults.uses),
+                    }
+                })
+                .unwrap_or_default(),
         };
 
-        let results = self
+        let mut results = self
             .search_engine
             .search(&query)
             .await
@@ -366,13 +646,12 @@ impl SkillsServer {
 
         let stats = self.registry.stats();
 
-        let output = SearchOutput {
-            matches: results
-                .matches
+        let mut output = SearchOutput {
+            matches: std::mem::take(&mut results.matches)
                 .into_iter()
                 .map(|m| serde_json::to_value(m).unwrap())
                 .collect(),
-            next_cursor: results.next_cursor,
+            next_cursor: results.next_cursor.clone(),
             stats: SearchStats {
                 total_callables: stats.total_callables,
                 total_tools: stats.total_tools,
@@ -380,8 +659,52 @@ impl SkillsServer {
                 searched_servers: stats.servers.len(),
                 stale_servers: stats.stale_servers,
             },
+            budget: None,
         };
 
+        if let Some(max_tokens) = input.max_tokens {
+            let mut elided = Vec::new();
+            for field in SEARCH_ELIDABLE_FIELDS {
+                if estimate_tokens(&output) <= max_tokens {
+                    break;
+                }
+                let removed = strip_field(&mut output.matches, field);
+                if removed > 0 {
+                    elided.push(format!("{} ({} matches)", field, removed));
+                }
+            }
+
+            // Shorten the page so next_cursor resumes after the last match kept
+            if estimate_tokens(&output) > max_tokens && output.matches.len() > 1 {
+                let mut used = estimate_tokens(&SearchOutput {
+                    matches: Vec::new(),
+                    ..output.clone()
+                });
+                let fit = output
+                    .matches
+                    .iter()
+                    .take_while(|m| {
+                        used += estimate_tokens(m) + 1;
+                        used <= max_tokens
+                    })
+                    .count()
+                    .max(1);
+
+                elided.push(format!(
+                    "{} matches past the budget (continue with next_cursor)",
+                    output.matches.len() - fit
+                ));
+                output.matches.truncate(fit);
+                output.next_cursor = self.search_engine.cursor_after(&query, &results, fit);
+            }
+
+            output.budget = Some(BudgetReport {
+                max_tokens,
+                estimated_tokens: estimate_tokens(&output),
+                elided,
+            });
+        }
+
         info!("search returned {} matches", output.matches.len());
 
         Ok(Json(output))
@@ -405,8 +728,11 @@ impl SkillsServer {
             .get(&callable_id)
             .ok_or_else(|| format!("Callable not found: {}", callable_id))?;
 
-        let format = input.format.as_str();
-        let max_bytes = input.max_bytes;
+        let format: SchemaFormat = input.format.parse().map_err(|e: CoreError| e.to_string())?;
+        let max_tokens = input
+            .max_tokens
+            .unwrap_or(usize::MAX)
+            .min(input.max_bytes / BYTES_PER_TOKEN);
 
         let mut output = SchemaOutput {
             callable: CallableInfo {
@@ -416,45 +742,144 @@ impl SkillsServer {
                 fq_name: record.fq_name.clone(),
                 server: record.server_alias.clone(),
                 version: record.skill_version.clone(),
+                stale: self.registry.is_stale(&record),
             },
             schema_digest: record.schema_digest.as_str().to_string(),
             input_schema: None,
             output_schema: None,
             signature: None,
+            rendering: None,
+            budget: None,
         };
 
-        if format == "json_schema" || format == "both" {
-            let mut schema = record.input_schema.clone();
+        let mut schema = None;
+        let mut output_schema = None;
+        if format != SchemaFormat::Signature {
+            let mut subtree = record.input_schema.clone();
 
             // Apply JSON pointer if specified
-            if let Some(pointer) = input.json_pointer {
-                if let Some(subtree) = schema.pointer(&pointer) {
-                    schema = subtree.clone();
+            if let Some(pointer) = &input.json_pointer {
+                if let Some(value) = subtree.pointer(pointer) {
+                    subtree = value.clone();
                 } else {
                     return Err(format!("Invalid JSON pointer: {}", pointer));
                 }
             }
 
-            output.input_schema = Some(schema);
-
+            schema = Some(subtree);
             if input.include_output_schema {
-                output.output_schema = record.output_schema.clone();
+                output_schema = record.output_schema.clone();
             }
         }
 
-        if format == "signature" || format == "both" {
-            let signature = crate::core::CallableSignature::from_schema(&record.input_schema);
-            output.signature = Some(serde_json::to_value(signature).unwrap());
+        // Show the (possibly reduced) schemas in the requested format
+        let show = |output: &mut SchemaOutput,
+                    schema: &Option<JsonValue>,
+                    output_schema: &Option<JsonValue>| {
+            if format.is_rendering() {
+                output.rendering = schema
+                    .as_ref()
+                    .and_then(|s| Rendering::new(format, s, output_schema.as_ref(), &record.name))
+                    .map(|r| serde_json::to_value(r).unwrap());
+            } else {
+                output.input_schema = schema.clone();
+                output.output_schema = output_schema.clone();
+            }
+        };
+        show(&mut output, &schema, &output_schema);
+
+        // Real arguments make better examples than synthesized ones
+        let mut history_examples = Vec::new();
+        if input.examples_from_history {
+            if let Some(persistence) = &self.persistence {
+                let records = persistence
+                    .get_execution_history(&record.id, HISTORY_EXAMPLE_SCAN)
+                    .await
+                    .map_err(|e| format!("Failed to query history: {}", e))?;
+                history_examples =
+                    examples::from_history(&record.input_schema, &records, examples::MAX_EXAMPLES);
+            }
         }
+        let signature_of = |schema: &JsonValue| {
+            let mut signature = crate::core::CallableSignature::from_schema(schema);
+            if !history_examples.is_empty() {
+                signature.examples = examples::merge(history_examples.clone(), &signature.examples);
+            }
+            serde_json::to_value(signature).unwrap()
+        };
+
+        if format == SchemaFormat::Signature || format == SchemaFormat::Both {
+            output.signature = Some(signature_of(&record.input_schema));
+        }
+
+        // Degrade to fit the budget rather than failing
+        let mut elided = Vec::new();
+        if estimate_tokens(&output) > max_tokens {
+            let base = input.json_pointer.as_deref().unwrap_or("");
+            let mut signature_schema = record.input_schema.clone();
+            let plan = schema
+                .as_ref()
+                .map(SchemaReduction::plan)
+                .unwrap_or_else(|| vec![SchemaReduction::Descriptions, SchemaReduction::Enums]);
+
+            for step in plan {
+                if estimate_tokens(&output) <= max_tokens {
+                    break;
+                }
+                if let Some(note) = schema.as_mut().and_then(|schema| step.apply(schema, base)) {
+                    elided.push(format!("input_schema: {}", note));
+                }
+                // `$ref` handles only resolve against the input schema
+                if !matches!(step, SchemaReduction::Collapse(_)) {
+                    if let Some(note) = output_schema
+                        .as_mut()
+                        .and_then(|schema| step.apply(schema, ""))
+                    {
+                        elided.push(format!("output_schema: {}", note));
+                    }
+                }
+                show(&mut output, &schema, &output_schema);
+
+                // Signature constraints repeat property descriptions
+                if step == SchemaReduction::Descriptions
+                    && output.signature.is_some()
+                    && step.apply(&mut signature_schema, "").is_some()
+                {
+                    output.signature = Some(signature_of(&signature_schema));
+                    elided.push("signature: descriptions".to_string());
+                }
+            }
+
+            if estimate_tokens(&output) > max_tokens {
+                if let Some(JsonValue::Array(examples)) = output
+                    .signature
+                    .as_mut()
+                    .and_then(|signature| signature.get_mut("examples"))
+                {
+                    if !examples.is_empty() {
+                        examples.clear();
+                        elided.push("signature: examples".to_string());
+                    }
+                }
+            }
 
-        // Check size limit
-        let output_str = serde_json::to_string(&output).unwrap();
-        if output_str.len() > max_bytes {
-            return Err(format!(
-                "Response exceeds maxBytes limit: {} > {}",
-                output_str.len(),
-                max_bytes
-            ));
+            if estimate_tokens(&output) > max_tokens && output_schema.take().is_some() {
+                show(&mut output, &schema, &output_schema);
+                elided.push("output_schema".to_string());
+            }
+            if estimate_tokens(&output) > max_tokens
+                && output.input_schema.is_some()
+                && output.signature.take().is_some()
+            {
+                elided.push("signature (input_schema has the same parameters)".to_string());
+            }
+        }
+        if input.max_tokens.is_some() || !elided.is_empty() {
+            output.budget = Some(BudgetReport {
+                max_tokens,
+                estimated_tokens: estimate_tokens(&output),
+                elided,
+            });
         }
 
         info!("schema returned schema for {}", record.fq_name);
@@ -467,79 +892,16 @@ impl SkillsServer {
         name = "exec",
         description = "Execute a callable with validation and policy enforcement. Always search and get schema first."
     )]
-    async fn exec(&self, Parameters(input): Parameters<ExecInput>) -> Result<String, String> {
-        debug!("exec called for: {}", input.id);
-
-        let callable_id: CallableId = input.id.into();
-        let dry_run = input.dry_run;
-
-        // Get callable record
-        let record = self
-            .registry
-            .get(&callable_id)
-            .ok_or_else(|| format!("Callable not found: {}", callable_id))?;
-
-        // Parse consent level
-        let consent_level = input
-            .consent
-            .as_ref()
-            .and_then(|c| c.level.as_deref())
-            .map(|s| match s {
-                "user_confirmed" => ConsentLevel::UserConfirmed,
-                "admin_confirmed" => ConsentLevel::AdminConfirmed,
-                _ => ConsentLevel::None,
-            })
-            .unwrap_or(ConsentLevel::None);
-
-        // Check policy authorization
-        let policy_result = self
-            .policy_engine
-            .authorize(&record, &input.arguments, consent_level)
-            .await
-            .map_err(|e| format!("Policy check failed: {}", e))?;
-
-        if !policy_result.allowed {
-            warn!("Execution denied: {}", policy_result.reason);
-            return Err(format!("Execution denied: {}", policy_result.reason));
-        }
-
-        if dry_run {
-            info!("Dry run: would execute {}", record.fq_name);
-            return Ok(format!("Dry run: would execute {}", record.fq_name));
-        }
-
-        // Execute
-        let ctx = ExecContext {
-            callable_id: callable_id.clone(),
-            arguments: input.arguments,
-            timeout_ms: input.timeout_ms,
-            trace_enabled: input
-                .trace
-                .as_ref()
-                .map(|t| t.include_route || t.include_timing || t.include_steps)
-                .unwrap_or(false),
-        };
-
-        let result = self
-            .runtime
-            .execute(ctx)
+    async fn exec(
+        &self,
+        Parameters(input): Parameters<ExecInput>,
+        extensions: rmcp::model::Extensions,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        // Refusals and failures are tool errors the model can act on
+        Ok(self
+            .execute_callable(input, &extensions)
             .await
-            .map_err(|e| format!("Execution failed: {}", e))?;
-
-        info!("exec completed for {}", record.fq_name);
-
-        // Convert ToolResult to string representation (content is already JSON from runtime)
-        let text: String = result
-            .content
-            .iter()
-            .filter_map(|c| match c {
-                crate::core::ToolResultContent::Text { text } => Some(text.as_str()),
-                _ => None,
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        Ok(text)
+            .unwrap_or_else(|e| CallToolResult::error(vec![Content::text(e)])))
     }
 
     /// Manage skills lifecycle: create, get, update, delete
@@ -548,7 +910,7 @@ impl SkillsServer {
     /// the "Finite Context" principle while enabling full CRUD functionality.
     #[tool(
         name = "manage",
-        description = "Manage skill and upstream lifecycle. Operations: create/get/update/delete skills, add_upstream/remove_upstream/list_upstreams. For add_upstream: requires upstream_alias, transport, url|command, optional description and tags."
+        description = "Manage skill and upstream lifecycle. Operations: create/get/update/delete skills, add_upstream/remove_upstream/list_upstreams, history (filters: callable, since, until, status, limit). For add_upstream: requires upstream_alias, transport, url|command, optional description and tags."
     )]
     async fn manage(
         &self,
@@ -611,6 +973,21 @@ impl SkillsServer {
                     skill_id.clone()
                 };
 
+                // If a specific section is requested, return just that section
+                if let Some(section) = input.section {
+                    let section_content = self
+                        .skill_store
+                        .load_skill_section(&skill_name, input.filename.as_deref(), &section)
+                        .map_err(|e| format!("Failed to load section: {}", e))?;
+                    return Ok(Json(ManageOutput {
+                        operation: "get".to_string(),
+                        skill_id: Some(skill_id),
+                        name: Some(skill_name),
+                        message: section_content,
+                        data: None,
+                    }));
+                }
+
                 // If a specific file is requested, return just that file
                 if let Some(filename) = input.filename {
                     let file_content = self
@@ -774,6 +1151,9 @@ impl SkillsServer {
                     tags: input.tags.unwrap_or_default(),
                     sandbox_config: None,
                     description: input.upstream_description,
+                    annotations: Default::default(),
+                    restart: Default::default(),
+                    max_concurrency: None,
                 };
 
                 let message = if let Some(ref upstream_manager) = self.upstream_manager {
@@ -822,8 +1202,12 @@ impl SkillsServer {
             }
 
             ManageOperation::ListUpstreams => {
-                let servers = if let Some(ref upstream_manager) = self.upstream_manager {
-                    upstream_manager.list_servers().await
+                let (servers, statuses) = if let Some(ref upstream_manager) = self.upstream_manager
+                {
+                    (
+                        upstream_manager.list_servers().await,
+                        upstream_manager.statuses().await,
+                    )
                 } else {
                     return Err("Upstream manager not available".to_string());
                 };
@@ -841,7 +1225,11 @@ impl SkillsServer {
                     skill_id: None,
                     name: None,
                     message,
-                    data: Some(serde_json::json!({ "servers": servers, "count": servers.len() })),
+                    data: Some(serde_json::json!({
+                        "servers": servers,
+                        "count": servers.len(),
+                        "upstreams": statuses,
+                    })),
                 }))
             }
 
@@ -869,6 +1257,9 @@ impl SkillsServer {
                         },
                         sandbox_config: current.sandbox_config,
                         description: input.upstream_description.or(current.description),
+                        annotations: current.annotations,
+                        restart: current.restart,
+                        max_concurrency: current.max_concurrency,
                     }
                 } else {
                     return Err("Upstream manager not available".to_string());
@@ -889,6 +1280,49 @@ impl SkillsServer {
                     data: Some(serde_json::json!({ "alias": alias })),
                 }))
             }
+
+            ManageOperation::History => {
+                let persistence = self
+                    .persistence
+                    .as_ref()
+                    .ok_or("Execution history not available")?;
+
+                let parse_time = |value: Option<String>| {
+                    value
+                        .map(|v| persistence::parse_history_time(&v))
+                        .transpose()
+                        .map_err(|e| e.to_string())
+                };
+                let filter = HistoryFilter {
+                    callable: input.callable,
+                    since: parse_time(input.since)?,
+                    until: parse_time(input.until)?,
+                    status: input
+                        .status
+                        .map(|s| s.parse::<ExecutionStatus>())
+                        .transpose()
+                        .map_err(|e| e.to_string())?,
+                    limit: input
+                        .limit
+                        .unwrap_or(HistoryFilter::default().limit)
+                        .clamp(1, 1000),
+                };
+
+                let records = persistence
+                    .query_execution_history(&filter)
+                    .await
+                    .map_err(|e| format!("Failed to query history: {}", e))?;
+
+                Ok(Json(ManageOutput {
+                    operation: "history".to_string(),
+                    skill_id: None,
+                    name: None,
+                    message: format!("Found {} execution(s)", records.len()),
+                    data: Some(
+                        serde_json::json!({ "executions": records, "count": records.len() }),
+                    ),
+                }))
+            }
         }
     }
 }
@@ -982,12 +1416,14 @@ pub enum ManageOperation {
     ListUpstreams,
     /// Update an upstream MCP server configuration
     UpdateUpstream,
+    /// Query execution history
+    History,
 }
 
 /// Unified input for skill management operations
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ManageInput {
-    /// Operation to perform: create, get, update, delete, add_upstream, remove_upstream, list_upstreams
+    /// Operation to perform: create, get, update, delete, add_upstream, remove_upstream, list_upstreams, history
     pub operation: ManageOperation,
     /// Skill ID (required for get, update, delete; optional for create)
     #[serde(default)]
@@ -1025,6 +1461,10 @@ pub struct ManageInput {
     /// Specific filename to load when getting content (optional)
     #[serde(default)]
     pub filename: Option<String>,
+    /// Section heading to load when getting content (optional); searched in
+    /// `filename` if given, otherwise in SKILL.md and reference files
+    #[serde(default)]
+    pub section: Option<String>,
     /// Upstream alias (required for add_upstream, remove_upstream)
     #[serde(default)]
     pub upstream_alias: Option<String>,
@@ -1043,6 +1483,21 @@ pub struct ManageInput {
     /// Description of the upstream MCP server (optional, helps LLMs discover tools)
     #[serde(default)]
     pub upstream_description: Option<String>,
+    /// History filter: callable id, server.tool, or tool/skill name (optional)
+    #[serde(default)]
+    pub callable: Option<String>,
+    /// History filter: start time as RFC 3339, YYYY-MM-DD, or an age like 24h (optional)
+    #[serde(default)]
+    pub since: Option<String>,
+    /// History filter: end time, same formats as since (optional)
+    #[serde(default)]
+    pub until: Option<String>,
+    /// History filter: success or error (optional)
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Maximum history records to return (optional, defaults to 50)
+    #[serde(default)]
+    pub limit: Option<i64>,
 }
 
 /// Output from management operations
@@ -6,10 +6,12 @@
 //! Agent Skills Format:
 //! - SKILL.md with YAML frontmatter
 //! - Optional scripts/, references/, assets/ directories
+//! - Optional workflow.yaml (or .yml/.json) for `entrypoint: workflow` skills
 //! - Lowercase, hyphenated naming convention
 //!
 //! Spec: https://agentskills.io/specification
 
+use crate::execution::workflow::{find_workflow_file, WorkflowDefinition};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -86,6 +88,10 @@ pub struct AgentSkillsFrontmatter {
     /// Accepts both string format ("Bash Read Write") and array format (["Bash", "Read", "Write"])
     #[serde(default, rename = "allowed-tools")]
     pub allowed_tools: Option<AllowedTools>,
+
+    /// Tools the skill must never call; wins over allowed-tools
+    #[serde(default, rename = "denied-tools")]
+    pub denied_tools: Option<AllowedTools>,
 }
 
 /// Default version for Agent Skills
@@ -102,6 +108,7 @@ pub struct AgentSkill {
     pub scripts: Vec<PathBuf>,
     pub references: Vec<PathBuf>,
     pub assets: Vec<PathBuf>,
+    pub workflow: Option<WorkflowDefinition>,
 }
 
 impl AgentSkill {
@@ -139,6 +146,14 @@ impl AgentSkill {
         let references = Self::discover_files(&path.join("references")).await;
         let assets = Self::discover_files(&path.join("assets")).await;
 
+        let workflow = match find_workflow_file(path) {
+            Some(workflow_path) => Some(
+                WorkflowDefinition::load(&workflow_path)
+                    .map_err(AgentSkillsError::ValidationError)?,
+            ),
+            None => None,
+        };
+
         Ok(AgentSkill {
             frontmatter,
             content: body,
@@ -146,6 +161,7 @@ impl AgentSkill {
             scripts,
             references,
             assets,
+            workflow,
         })
     }
 
@@ -267,6 +283,15 @@ impl AgentSkill {
             .unwrap_or_default()
     }
 
+    /// Parse denied-tools into a Vec (supports both string and array formats)
+    pub fn parse_denied_tools(&self) -> Vec<String> {
+        self.frontmatter
+            .denied_tools
+            .as_ref()
+            .map(|tools| tools.to_vec())
+            .unwrap_or_default()
+    }
+
     /// Extract version from frontmatter
     pub fn version(&self) -> String {
         self.frontmatter.version.clone()
@@ -297,7 +322,9 @@ impl AgentSkill {
         use crate::{EntrypointType, SkillHints, SkillManifest, ToolPolicy};
 
         // Determine entrypoint type
-        let entrypoint = if self.has_scripts() {
+        let entrypoint = if self.workflow.is_some() {
+            EntrypointType::Workflow
+        } else if self.has_scripts() {
             EntrypointType::Script
         } else {
             EntrypointType::Prompted
@@ -311,7 +338,7 @@ impl AgentSkill {
             } else {
                 allowed_tools
             },
-            deny: vec![],
+            deny: self.parse_denied_tools(),
             required: vec![],
         };
 
@@ -337,13 +364,20 @@ impl AgentSkill {
             expected_calls: None,
         };
 
-        // Create input schema (generic object for Agent Skills)
-        let inputs = serde_json::json!({
-            "type": "object",
-            "properties": {},
-            "additionalProperties": true,
-            "description": description.clone()
-        });
+        // Workflows declare their own schemas; other skills take a generic object
+        let inputs = self
+            .workflow
+            .as_ref()
+            .and_then(|w| w.inputs.clone())
+            .unwrap_or_else(|| {
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {},
+                    "additionalProperties": true,
+                    "description": description.clone()
+                })
+            });
+        let outputs = self.workflow.as_ref().and_then(|w| w.outputs.clone());
 
         SkillManifest {
             id: self.frontmatter.name.clone(),
@@ -363,7 +397,7 @@ impl AgentSkill {
             version: self.version(),
             description,
             inputs,
-            outputs: None,
+            outputs,
             entrypoint,
             tool_policy,
             hints,
@@ -0,0 +1,182 @@
+//! Opaque, signed pagination cursors for `search`
+//!
+//! A cursor records the query it belongs to, the registry generation its page
+//! was ranked at, and the sort position (score, fq_name, id) of the last match
+//! returned. The next page resumes strictly after that position rather than at
+//! an offset. When the generation differs, scores may have shifted with the
+//! catalog, so the page resumes after wherever the last match now ranks;
+//! callables added ahead of it are not replayed. If the last match is gone,
+//! the position is meaningless and the cursor is rejected as expired.
+//!
+//! Feedback boosts decay with time, so a cursor also records when its first
+//! page was ranked; later pages apply feedback as of that moment and rank
+//! identically.
+//!
+//! Cursors look like `sc1.<hex payload>.<signature>`. The signing key is
+//! generated per `SearchEngine`, so cursors do not survive a restart.
+
+use crate::storage::search::{IndexError, Result, SearchQuery};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+
+/// Version prefix for issued cursors
+const CURSOR_PREFIX: &str = "sc1";
+
+/// Where a page ended in the ranked result list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Position {
+    /// Stored as its bit pattern: a decimal round trip can shift the last digit
+    #[serde(with = "score_bits")]
+    pub(crate) score: f64,
+    pub(crate) fq_name: String,
+    pub(crate) id: String,
+}
+
+impl Position {
+    /// Result order: score descending, then fq_name, then id
+    pub(crate) fn order(a: (f64, &str, &str), b: (f64, &str, &str)) -> Ordering {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.1.cmp(b.1))
+            .then_with(|| a.2.cmp(b.2))
+    }
+
+    pub(crate) fn key(&self) -> (f64, &str, &str) {
+        (self.score, &self.fq_name, &self.id)
+    }
+}
+
+mod score_bits {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(score: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(score.to_bits())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        u64::deserialize(deserializer).map(f64::from_bits)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    query: String,
+    generation: u64,
+    /// Nanoseconds after the codec's epoch at which the first page was ranked
+    ranked_at: u64,
+    last: Position,
+}
+
+/// Issues and verifies cursors
+pub(crate) struct CursorCodec {
+    key: [u8; 32],
+    epoch: Instant,
+}
+
+impl CursorCodec {
+    pub(crate) fn new() -> Self {
+        let mut seed = Vec::with_capacity(32);
+        seed.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+        seed.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+        CursorCodec {
+            key: *blake3::hash(&seed).as_bytes(),
+            epoch: Instant::now(),
+        }
+    }
+
+    /// The current time, at the precision a cursor records it
+    pub(crate) fn now(&self) -> Instant {
+        self.instant(self.epoch.elapsed().as_nanos() as u64)
+    }
+
+    fn instant(&self, nanos: u64) -> Instant {
+        self.epoch + Duration::from_nanos(nanos)
+    }
+
+    pub(crate) fn encode(
+        &self,
+        query: &SearchQuery,
+        generation: u64,
+        ranked_at: Instant,
+        last: Position,
+    ) -> String {
+        let payload = Payload {
+            query: query_digest(query),
+            generation,
+            ranked_at: ranked_at.saturating_duration_since(self.epoch).as_nanos() as u64,
+            last,
+        };
+        // A struct of strings and finite numbers always serializes
+        let payload = hex_encode(&serde_json::to_vec(&payload).unwrap_or_default());
+        let signature = blake3::keyed_hash(&self.key, payload.as_bytes());
+        format!("{}.{}.{}", CURSOR_PREFIX, payload, signature.to_hex())
+    }
+
+    /// Verify a cursor against the query it is used with
+    ///
+    /// Returns the generation the cursor was issued at, when its first page was
+    /// ranked, and the last position served.
+    pub(crate) fn decode(
+        &self,
+        cursor: &str,
+        query: &SearchQuery,
+    ) -> Result<(u64, Instant, Position)> {
+        let invalid =
+            |reason: &str| IndexError::InvalidQuery(format!("Invalid cursor: {}", reason));
+
+        let mut parts = cursor.trim().split('.');
+        if parts.next() != Some(CURSOR_PREFIX) {
+            return Err(invalid("malformed"));
+        }
+        let (payload, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(payload), Some(signature), None) => (payload, signature),
+            _ => return Err(invalid("malformed")),
+        };
+        let signature = blake3::Hash::from_hex(signature).map_err(|_| invalid("malformed"))?;
+
+        // blake3::Hash compares in constant time
+        if blake3::keyed_hash(&self.key, payload.as_bytes()) != signature {
+            return Err(invalid(
+                "bad signature (cursors do not survive a server restart)",
+            ));
+        }
+
+        let payload: Payload = hex_decode(payload)
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| invalid("malformed"))?;
+        if payload.query != query_digest(query) {
+            return Err(invalid("issued for a different query"));
+        }
+        Ok((
+            payload.generation,
+            self.instant(payload.ranked_at),
+            payload.last,
+        ))
+    }
+}
+
+/// Digest of everything that determines a result list
+fn query_digest(query: &SearchQuery) -> String {
+    let filters = serde_json::to_string(&query.filters).unwrap_or_default();
+    let mut hasher = blake3::Hasher::new();
+    for part in [query.q.as_str(), &query.kind, query.mode.as_str(), &filters] {
+        hasher.update(part.as_bytes());
+        hasher.update(&[0]);
+    }
+    hasher.finalize().to_hex()[..16].to_string()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,223 @@
+//! Ranking feedback from search click-through
+//!
+//! When a callable returned by `search` is then executed in the same session,
+//! the outcome is counted against the query's terms in `PersistenceLayer`.
+//! Later searches sharing those terms boost callables that succeeded for
+//! them, demote callables that frequently error, and favor callables the
+//! session used recently. Every boost is shown in the match explanation.
+
+use crate::core::persistence::PersistenceLayer;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Executions this long after a search still count as picks from its results
+const CLICK_THROUGH_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+/// Age at which the session recency boost halves
+const RECENCY_HALF_LIFE_SECS: f64 = 600.0;
+
+/// Pseudo-count pulling sparse statistics toward neutral
+const PRIOR: f64 = 2.0;
+
+/// Largest boost for callables that succeed for similar queries
+const AFFINITY_WEIGHT: f64 = 0.5;
+
+/// Largest boost for a callable used just now in this session
+const RECENCY_WEIGHT: f64 = 0.25;
+
+/// Largest demotion for callables that always error
+const ERROR_WEIGHT: f64 = 0.5;
+
+/// Sessions remembered before the least recently active is dropped
+const MAX_SESSIONS: usize = 1024;
+
+#[derive(Default)]
+struct SessionState {
+    /// Terms and callable ids of the latest search
+    last_search: Option<(Vec<String>, HashSet<String>, Instant)>,
+    /// When each callable was last executed
+    used: HashMap<String, Instant>,
+    active: Option<Instant>,
+}
+
+/// Score multiplier for one callable, with its reasons
+#[derive(Debug, Clone, PartialEq)]
+pub struct Boost {
+    pub factor: f64,
+    pub explanation: String,
+}
+
+/// Opt-in learning-to-rank stage for `SearchEngine`
+pub struct FeedbackRanker {
+    persistence: Arc<PersistenceLayer>,
+    sessions: Mutex<HashMap<String, SessionState>>,
+}
+
+impl FeedbackRanker {
+    pub fn new(persistence: Arc<PersistenceLayer>) -> Self {
+        FeedbackRanker {
+            persistence,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember the results a session was shown
+    pub fn note_search(&self, session: &str, terms: Vec<String>, ids: HashSet<String>) {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock();
+        if !sessions.contains_key(session) && sessions.len() >= MAX_SESSIONS {
+            let oldest = sessions
+                .iter()
+                .min_by_key(|(_, state)| state.active)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                sessions.remove(&oldest);
+            }
+        }
+        let state = sessions.entry(session.to_string()).or_default();
+        state.last_search = Some((terms, ids, now));
+        state.active = Some(now);
+    }
+
+    /// Count an execution outcome
+    ///
+    /// Outcomes are attributed to the session's latest search only when the
+    /// callable was among its results; per-callable totals are always kept.
+    pub async fn record_outcome(&self, session: &str, callable_id: &str, success: bool) {
+        let terms = {
+            let now = Instant::now();
+            let mut sessions = self.sessions.lock();
+            let state = sessions.entry(session.to_string()).or_default();
+            state.used.insert(callable_id.to_string(), now);
+            state.active = Some(now);
+            match &state.last_search {
+                Some((terms, ids, at))
+                    if ids.contains(callable_id) && at.elapsed() <= CLICK_THROUGH_WINDOW =>
+                {
+                    terms.clone()
+                }
+                _ => Vec::new(),
+            }
+        };
+
+        if let Err(e) = self
+            .persistence
+            .record_search_feedback(callable_id, &terms, success)
+            .await
+        {
+            warn!("Failed to record search feedback: {}", e);
+        }
+    }
+
+    /// Boosts for candidate callables under the given query terms
+    ///
+    /// Callables without any feedback or session use are left out. Recency is
+    /// measured at `at`, and session use after it is ignored, so a ranking can
+    /// be repeated exactly for later pages of the same search.
+    pub async fn boosts(
+        &self,
+        session: Option<&str>,
+        terms: &[String],
+        candidates: &HashSet<String>,
+        at: Instant,
+    ) -> HashMap<String, Boost> {
+        let feedback = match self.persistence.load_search_feedback(terms).await {
+            Ok(feedback) => feedback,
+            Err(e) => {
+                warn!("Failed to load search feedback: {}", e);
+                Vec::new()
+            }
+        };
+
+        // callable -> (query successes, query failures, total successes, total failures)
+        let mut stats: HashMap<&str, (i64, i64, i64, i64)> = HashMap::new();
+        for row in &feedback {
+            if !candidates.contains(&row.callable_id) {
+                continue;
+            }
+            let entry = stats.entry(row.callable_id.as_str()).or_default();
+            if row.term.is_empty() {
+                entry.2 += row.successes;
+                entry.3 += row.failures;
+            } else {
+                entry.0 += row.successes;
+                entry.1 += row.failures;
+            }
+        }
+
+        let used: HashMap<String, Instant> = session
+            .and_then(|session| {
+                self.sessions
+                    .lock()
+                    .get(session)
+                    .map(|state| state.used.clone())
+            })
+            .unwrap_or_default();
+
+        let mut boosts = HashMap::new();
+        for id in candidates {
+            let (ok, failed, total_ok, total_failed) =
+                stats.get(id.as_str()).copied().unwrap_or_default();
+            let last_used = used.get(id).filter(|used| **used <= at);
+            if ok + failed + total_ok + total_failed == 0 && last_used.is_none() {
+                continue;
+            }
+
+            // Average over the query's terms, which also discounts partial overlaps
+            let term_count = terms.len().max(1) as f64;
+            let (ok_avg, failed_avg) = (ok as f64 / term_count, failed as f64 / term_count);
+            let affinity = ok_avg / (ok_avg + failed_avg + PRIOR);
+            let error_rate = total_failed as f64 / (total_ok as f64 + total_failed as f64 + PRIOR);
+            let recency = last_used
+                .map(|used| {
+                    let age = at.saturating_duration_since(*used);
+                    0.5f64.powf(age.as_secs_f64() / RECENCY_HALF_LIFE_SECS)
+                })
+                .unwrap_or(0.0);
+
+            let factor = (1.0 + AFFINITY_WEIGHT * affinity + RECENCY_WEIGHT * recency)
+                * (1.0 - ERROR_WEIGHT * error_rate);
+
+            let mut reasons = Vec::new();
+            if ok + failed > 0 {
+                reasons.push(format!(
+                    "similar queries {} ok, {} failed",
+                    ok_avg.round(),
+                    failed_avg.round()
+                ));
+            }
+            if total_ok + total_failed > 0 {
+                reasons.push(format!("overall {} ok, {} failed", total_ok, total_failed));
+            }
+            if let Some(used) = last_used {
+                reasons.push(format!(
+                    "used {} ago this session",
+                    format_age(at.saturating_duration_since(*used))
+                ));
+            }
+
+            boosts.insert(
+                id.clone(),
+                Boost {
+                    factor,
+                    explanation: format!("feedback ×{:.2} ({})", factor, reasons.join("; ")),
+                },
+            );
+        }
+        boosts
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h", secs / 3600)
+    }
+}
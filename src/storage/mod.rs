@@ -12,7 +12,12 @@
 //! Supports filesystem watching for hot-reload during development.
 
 pub mod agent_skills;
+pub mod cursor;
+pub mod feedback;
+pub mod query;
 pub mod search;
+pub mod sections;
+pub mod semantic;
 pub mod sync;
 
 use crate::core::registry::Registry;
@@ -462,6 +467,51 @@ impl SkillStore {
         Ok(std::fs::read_to_string(&canonical_file)?)
     }
 
+    /// Load one section (with its subsections) from a skill's documentation
+    ///
+    /// Looks in `filename` when given, otherwise in SKILL.md and then
+    /// `references/*.md`.
+    pub fn load_skill_section(
+        &self,
+        skill_id: &str,
+        filename: Option<&str>,
+        heading: &str,
+    ) -> Result<String> {
+        let files: Vec<String> = match filename {
+            Some(filename) => vec![filename.to_string()],
+            None => {
+                let skill_dir = self.find_skill_directory(skill_id)?;
+                let mut files: Vec<String> = sections::load_skill_sections(&skill_dir)
+                    .into_iter()
+                    .map(|section| section.file)
+                    .collect();
+                files.dedup();
+                files
+            }
+        };
+
+        let mut headings = Vec::new();
+        for file in &files {
+            let content = self.load_skill_file(skill_id, file)?;
+            if let Some(section) = sections::extract_section(&content, heading) {
+                return Ok(section);
+            }
+            headings.extend(
+                sections::split_sections(file, &content)
+                    .into_iter()
+                    .filter(|section| section.level > 0)
+                    .map(|section| section.heading),
+            );
+        }
+
+        Err(SkillStoreError::FileNotFound(format!(
+            "Section '{}' not found in skill {} (sections: {})",
+            heading,
+            skill_id,
+            headings.join(", ")
+        )))
+    }
+
     /// Find the directory for a given skill ID
     fn find_skill_directory(&self, skill_id: &str) -> Result<PathBuf> {
         // Try direct match first
@@ -683,6 +733,7 @@ impl SkillStore {
                 filesystem_access: false,
             },
             risk_tier,
+            annotations: None,
             last_seen: chrono::Utc::now(),
             sandbox_config: None,
         };
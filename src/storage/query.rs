@@ -0,0 +1,307 @@
+//! Inline search query syntax
+//!
+//! Filters can be written directly in the query string; they are stripped
+//! before text matching, so one `search` call can both match and narrow:
+//!
+//! ```text
+//! create pr server:github kind:tool risk:<=writes -tag:deprecated param:repo net:false
+//! ```
+//!
+//! | Filter | Value | Matches |
+//! |--------|-------|---------|
+//! | `server:` | upstream alias | tools from that server |
+//! | `kind:` | `tool` or `skill` | callable kind |
+//! | `risk:` | `[op]tier` | risk tier, ordered `read_only < writes < destructive < admin < unknown` |
+//! | `tag:` | tag | records carrying the tag |
+//! | `param:` | name | input schema has this top-level property |
+//! | `net:` / `fs:` | `true` / `false` | network / filesystem cost hints |
+//! | `calls:` / `duration:` | `[op]n` | expected calls / estimated milliseconds cost hints |
+//! | `output:` | `true` / `false` | has an output schema |
+//! | `version:` | semver requirement | skill version, e.g. `version:^1.2` or `"version:>=1, <2"` |
+//!
+//! `op` is one of `<`, `<=`, `=`, `>=`, `>` (default `=`). Any filter can be
+//! negated with a leading `-`, and values containing spaces can be quoted.
+//! Tokens with any other key stay part of the text query.
+
+use crate::core::{CallableKind, CallableRecord, RiskTier};
+use crate::storage::search::{extract_input_keys, IndexError, Result};
+use std::fmt;
+
+/// Comparison operator for ordered filter values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    /// Split a leading operator off a value (`<=writes` → `Le`, `writes`)
+    fn split(value: &str) -> (Comparison, &str) {
+        for (prefix, op) in [
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+            ("=", Comparison::Eq),
+        ] {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (op, rest);
+            }
+        }
+        (Comparison::Eq, value)
+    }
+
+    fn holds<T: Ord>(&self, actual: &T, expected: &T) -> bool {
+        match self {
+            Comparison::Lt => actual < expected,
+            Comparison::Le => actual <= expected,
+            Comparison::Eq => actual == expected,
+            Comparison::Ge => actual >= expected,
+            Comparison::Gt => actual > expected,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Eq => "",
+            Comparison::Ge => ">=",
+            Comparison::Gt => ">",
+        }
+    }
+}
+
+/// A single filter condition
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    Server(String),
+    Kind(CallableKind),
+    Risk(Comparison, RiskTier),
+    Tag(String),
+    Param(String),
+    Network(bool),
+    Filesystem(bool),
+    ExpectedCalls(Comparison, u32),
+    DurationMs(Comparison, u32),
+    HasOutput(bool),
+    Version(semver::VersionReq),
+}
+
+impl Predicate {
+    fn matches(&self, record: &CallableRecord) -> bool {
+        match self {
+            Predicate::Server(server) => record
+                .server_alias
+                .as_deref()
+                .is_some_and(|alias| alias.eq_ignore_ascii_case(server)),
+            Predicate::Kind(kind) => record.kind == *kind,
+            Predicate::Risk(op, tier) => op.holds(&record.risk_tier, tier),
+            Predicate::Tag(tag) => record.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Predicate::Param(param) => extract_input_keys(&record.input_schema).contains(param),
+            Predicate::Network(value) => record.cost_hints.network_required == *value,
+            Predicate::Filesystem(value) => record.cost_hints.filesystem_access == *value,
+            Predicate::ExpectedCalls(op, n) => record
+                .cost_hints
+                .expected_calls
+                .is_some_and(|calls| op.holds(&calls, n)),
+            Predicate::DurationMs(op, ms) => record
+                .cost_hints
+                .estimated_duration_ms
+                .is_some_and(|duration| op.holds(&duration, ms)),
+            Predicate::HasOutput(value) => record.output_schema.is_some() == *value,
+            Predicate::Version(req) => record
+                .skill_version
+                .as_deref()
+                .and_then(|v| semver::Version::parse(v).ok())
+                .is_some_and(|version| req.matches(&version)),
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::Server(server) => write!(f, "server:{}", server),
+            Predicate::Kind(kind) => write!(f, "kind:{}", kind),
+            Predicate::Risk(op, tier) => write!(f, "risk:{}{}", op.as_str(), tier),
+            Predicate::Tag(tag) => write!(f, "tag:{}", tag),
+            Predicate::Param(param) => write!(f, "param:{}", param),
+            Predicate::Network(value) => write!(f, "net:{}", value),
+            Predicate::Filesystem(value) => write!(f, "fs:{}", value),
+            Predicate::ExpectedCalls(op, n) => write!(f, "calls:{}{}", op.as_str(), n),
+            Predicate::DurationMs(op, ms) => write!(f, "duration:{}{}", op.as_str(), ms),
+            Predicate::HasOutput(value) => write!(f, "output:{}", value),
+            Predicate::Version(req) => write!(f, "version:{}", req),
+        }
+    }
+}
+
+/// A possibly negated filter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub negated: bool,
+    pub predicate: Predicate,
+}
+
+impl Clause {
+    pub fn matches(&self, record: &CallableRecord) -> bool {
+        self.predicate.matches(record) != self.negated
+    }
+}
+
+impl fmt::Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.predicate)
+    }
+}
+
+/// A query split into free text and filter clauses
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedQuery {
+    /// Remaining text to match, with filters removed
+    pub text: String,
+    /// Filters; a record must satisfy all of them
+    pub clauses: Vec<Clause>,
+}
+
+impl ParsedQuery {
+    pub fn has_filters(&self) -> bool {
+        !self.clauses.is_empty()
+    }
+
+    /// Whether a record satisfies every clause
+    pub fn matches(&self, record: &CallableRecord) -> bool {
+        self.clauses.iter().all(|clause| clause.matches(record))
+    }
+
+    /// Clauses in canonical form, e.g. `server:github, -tag:deprecated`
+    pub fn describe_filters(&self) -> String {
+        self.clauses
+            .iter()
+            .map(|clause| clause.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Parse a query string into text and filter clauses
+pub fn parse_query(input: &str) -> Result<ParsedQuery> {
+    let mut parsed = ParsedQuery::default();
+    let mut text = Vec::new();
+
+    for token in split_tokens(input) {
+        let (negated, body) = match token.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, token.as_str()),
+        };
+
+        let Some((key, value)) = body.trim_matches('"').split_once(':') else {
+            text.push(token);
+            continue;
+        };
+        let value = value.trim_matches('"');
+
+        let predicate = match key.to_ascii_lowercase().as_str() {
+            "server" => Predicate::Server(non_empty(key, value)?.to_string()),
+            "kind" => Predicate::Kind(match value.to_ascii_lowercase().as_str() {
+                "tool" | "tools" => CallableKind::Tool,
+                "skill" | "skills" => CallableKind::Skill,
+                _ => return Err(invalid(key, value, "expected tool or skill")),
+            }),
+            "risk" => {
+                let (op, tier) = Comparison::split(value);
+                let tier = tier
+                    .parse::<RiskTier>()
+                    .map_err(|_| invalid(key, value, "unknown risk tier"))?;
+                Predicate::Risk(op, tier)
+            }
+            "tag" => Predicate::Tag(non_empty(key, value)?.to_string()),
+            "param" => Predicate::Param(non_empty(key, value)?.to_string()),
+            "net" => Predicate::Network(parse_bool(key, value)?),
+            "fs" => Predicate::Filesystem(parse_bool(key, value)?),
+            "output" => Predicate::HasOutput(parse_bool(key, value)?),
+            "calls" => {
+                let (op, n) = parse_number(key, value)?;
+                Predicate::ExpectedCalls(op, n)
+            }
+            "duration" => {
+                let (op, n) = parse_number(key, value)?;
+                Predicate::DurationMs(op, n)
+            }
+            "version" => Predicate::Version(
+                semver::VersionReq::parse(value)
+                    .map_err(|e| invalid(key, value, &e.to_string()))?,
+            ),
+            _ => {
+                text.push(token);
+                continue;
+            }
+        };
+
+        parsed.clauses.push(Clause { negated, predicate });
+    }
+
+    parsed.text = text.join(" ");
+    Ok(parsed)
+}
+
+/// Split on whitespace outside double quotes, keeping tokens verbatim
+fn split_tokens(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn invalid(key: &str, value: &str, reason: &str) -> IndexError {
+    IndexError::InvalidQuery(format!("{}:{}: {}", key, value, reason))
+}
+
+fn non_empty<'a>(key: &str, value: &'a str) -> Result<&'a str> {
+    if value.is_empty() {
+        Err(invalid(key, value, "value cannot be empty"))
+    } else {
+        Ok(value)
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(invalid(key, value, "expected true or false")),
+    }
+}
+
+fn parse_number(key: &str, value: &str) -> Result<(Comparison, u32)> {
+    let (op, number) = Comparison::split(value);
+    let number = number
+        .parse::<u32>()
+        .map_err(|_| invalid(key, value, "expected a number"))?;
+    Ok((op, number))
+}
@@ -1,19 +1,36 @@
 //! Search and indexing engine for callables
 //!
 //! Provides fast discovery over the unified registry with:
-//! - Multiple query modes (literal, regex, fuzzy)
-//! - Field-weighted ranking
-//! - Filters (kind, server, tags, requires)
+//! - An inverted index with BM25F field-weighted ranking
+//! - A SQLite FTS5 backend for large catalogs
+//! - Optional offline semantic retrieval, fused by reciprocal rank (see `semantic`)
+//! - Section-level matching in SKILL.md bodies and reference files (see `sections`)
+//! - Opt-in ranking feedback from search click-through (see `feedback`)
+//! - Partial term matching (prefixes, camelCase and snake_case splitting, plurals)
+//! - Query modes: literal (ranked terms), regex, and fuzzy (typo-tolerant names)
+//! - Per-match explanations
+//! - Filters (kind, server, tags, requires) and inline filter syntax (see `query`)
 //! - Pagination support
 
-use crate::core::registry::Registry;
-use crate::core::{CallableId, CallableKind, CallableRecord};
-use parking_lot::RwLock;
+use crate::core::persistence::PersistenceLayer;
+use crate::core::registry::{Registry, RegistryEvent};
+use crate::core::{CallableId, CallableKind, CallableRecord, CallableSignature};
+use crate::storage::cursor::{CursorCodec, Position};
+use crate::storage::feedback::FeedbackRanker;
+use crate::storage::query::{parse_query, ParsedQuery};
+use crate::storage::sections::{load_skill_sections, SectionIndex, SectionMatch};
+use crate::storage::semantic::{EmbeddingModel, SemanticIndex};
+use parking_lot::{Mutex, RwLock};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Instant;
 use thiserror::Error;
+use tokio::sync::mpsc;
 use tracing::debug;
 
 #[derive(Error, Debug)]
@@ -24,6 +41,9 @@ pub enum IndexError {
     #[error("Regex compilation failed: {0}")]
     RegexError(#[from] regex::Error),
 
+    #[error("Cursor expired: {0}")]
+    CursorExpired(String),
+
     #[error("Index error: {0}")]
     Internal(String),
 }
@@ -46,6 +66,102 @@ pub struct SearchFilters {
     pub capability: Option<Vec<String>>,
 }
 
+/// Search index configuration (`index:` config section)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexConfig {
+    /// Search through SQLite FTS5 instead of the in-memory index once the
+    /// registry holds at least this many callables (0 disables FTS5)
+    #[serde(default = "default_fts_threshold")]
+    pub fts_threshold: usize,
+
+    /// Local word-embedding model (word2vec/GloVe text format) enabling semantic search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<PathBuf>,
+
+    /// Rank with feedback from executions of search results
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub feedback: bool,
+}
+
+fn default_fts_threshold() -> usize {
+    5000
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        IndexConfig {
+            fts_threshold: default_fts_threshold(),
+            embedding_model: None,
+            feedback: false,
+        }
+    }
+}
+
+/// How a search query is interpreted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Ranked term matching (BM25F)
+    #[default]
+    Literal,
+    /// Case-insensitive regular expression over all searchable fields
+    Regex,
+    /// Typo-tolerant name matching (edit distance and subsequences)
+    Fuzzy,
+}
+
+impl SearchMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchMode::Literal => "literal",
+            SearchMode::Regex => "regex",
+            SearchMode::Fuzzy => "fuzzy",
+        }
+    }
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SearchMode {
+    type Err = IndexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "literal" => Ok(SearchMode::Literal),
+            "regex" => Ok(SearchMode::Regex),
+            "fuzzy" => Ok(SearchMode::Fuzzy),
+            other => Err(IndexError::InvalidQuery(format!(
+                "Invalid mode: {} (expected literal, regex, or fuzzy)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Optional fields of each search match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchInclude {
+    /// Compact one-line signature, e.g. `(repo: string, issue: int) -> Issue`
+    pub signature: bool,
+    pub schema_digest: bool,
+    /// Dependencies of skills
+    pub uses: bool,
+}
+
+impl Default for SearchInclude {
+    fn default() -> Self {
+        SearchInclude {
+            signature: false,
+            schema_digest: true,
+            uses: true,
+        }
+    }
+}
+
 /// Search query
 #[derive(Debug, Clone)]
 pub struct SearchQuery {
@@ -54,6 +170,25 @@ pub struct SearchQuery {
     pub limit: usize,
     pub filters: Option<SearchFilters>,
     pub cursor: Option<String>,
+    pub mode: SearchMode,
+    /// Caller's session, for click-through feedback and recency
+    pub session: Option<String>,
+    pub include: SearchInclude,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        SearchQuery {
+            q: String::new(),
+            kind: "any".to_string(),
+            limit: 10,
+            filters: None,
+            cursor: None,
+            mode: SearchMode::default(),
+            session: None,
+            include: SearchInclude::default(),
+        }
+    }
 }
 
 /// Search match result
@@ -76,6 +211,18 @@ pub struct SearchMatch {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub uses: Option<Vec<String>>,
+
+    /// Served from the persistent cache; the owning server has not reconnected yet
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+
+    /// Which query terms matched which fields, e.g. `read: name; iss→issue (prefix): title`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+
+    /// Best-matching section of a skill's SKILL.md or reference files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<SectionMatch>,
 }
 
 /// Search results
@@ -84,24 +231,164 @@ pub struct SearchResults {
     pub matches: Vec<SearchMatch>,
     pub total_matches: usize,
     pub next_cursor: Option<String>,
+    #[serde(skip)]
+    page: Option<Page>,
+}
+
+/// How a page was ranked, for reissuing its cursor (see `SearchEngine::cursor_after`)
+#[derive(Debug, Clone)]
+struct Page {
+    generation: u64,
+    ranked_at: Instant,
+    /// Sort position of each match on the page
+    positions: Vec<Position>,
 }
 
 /// In-memory search engine
 pub struct SearchEngine {
     registry: Arc<Registry>,
     index: Arc<RwLock<InMemoryIndex>>,
+    events: Mutex<mpsc::UnboundedReceiver<RegistryEvent>>,
+    fts: Option<(Arc<PersistenceLayer>, usize)>,
+    semantic: Option<SemanticIndex>,
+    sections: RwLock<SectionIndex>,
+    feedback: Option<FeedbackRanker>,
+    cursors: CursorCodec,
 }
 
 impl SearchEngine {
     pub fn new(registry: Arc<Registry>) -> Self {
         SearchEngine {
+            events: Mutex::new(registry.subscribe()),
             registry,
             index: Arc::new(RwLock::new(InMemoryIndex::new())),
+            fts: None,
+            semantic: None,
+            sections: RwLock::new(SectionIndex::default()),
+            feedback: None,
+            cursors: CursorCodec::new(),
+        }
+    }
+
+    /// Rank with feedback from executions of search results (see `feedback`)
+    pub fn with_feedback(mut self, persistence: Arc<PersistenceLayer>) -> Self {
+        self.feedback = Some(FeedbackRanker::new(persistence));
+        self
+    }
+
+    /// Report the outcome of executing a callable, for ranking feedback
+    ///
+    /// A no-op unless feedback is enabled.
+    pub async fn record_outcome(&self, session: &str, id: &CallableId, success: bool) {
+        if let Some(feedback) = &self.feedback {
+            feedback.record_outcome(session, id.as_str(), success).await;
+        }
+    }
+
+    /// Add a semantic retrieval stage backed by a local embedding model
+    ///
+    /// Literal queries are matched both lexically and by embedding similarity,
+    /// and the two rankings are fused by reciprocal rank. Callable vectors are
+    /// cached in `persistence` when given.
+    pub fn with_semantic(
+        mut self,
+        model: Arc<EmbeddingModel>,
+        persistence: Option<Arc<PersistenceLayer>>,
+    ) -> Self {
+        self.semantic = Some(SemanticIndex::new(model, persistence));
+        self
+    }
+
+    /// Search through the SQLite FTS5 index once the registry holds at least
+    /// `threshold` callables (0 disables it)
+    ///
+    /// The FTS5 index follows the `callables` table, so the registry must be
+    /// written through to the same database (see `spawn_registry_sync`).
+    pub fn with_fts(mut self, persistence: Arc<PersistenceLayer>, threshold: usize) -> Self {
+        self.fts = Some((persistence, threshold));
+        self
+    }
+
+    /// Whether searches currently go to FTS5
+    fn fts_backend(&self) -> Option<&PersistenceLayer> {
+        match &self.fts {
+            Some((persistence, threshold))
+                if *threshold > 0 && self.registry.len() >= *threshold =>
+            {
+                Some(persistence)
+            }
+            _ => None,
+        }
+    }
+
+    /// Fuse lexical candidates and semantic hits by reciprocal rank
+    fn fuse(
+        &self,
+        mut lexical: Vec<(CallableRecord, f64, Option<String>)>,
+        semantic: Vec<(String, f32)>,
+        text: &str,
+    ) -> Vec<(CallableRecord, f64, Option<String>)> {
+        lexical.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.fq_name.cmp(&b.0.fq_name))
+        });
+
+        let mut fused: HashMap<String, (CallableRecord, f64, Option<String>)> = lexical
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (record, _, explanation))| {
+                (
+                    record.id.as_str().to_string(),
+                    (record, rrf(rank), explanation),
+                )
+            })
+            .collect();
+
+        for (rank, (id, similarity)) in semantic.into_iter().enumerate() {
+            let part = format!("semantic: {:.2}", similarity);
+            if let Some(entry) = fused.get_mut(&id) {
+                entry.1 += rrf(rank);
+                let lexical = entry
+                    .2
+                    .take()
+                    .unwrap_or_else(|| explain_record(&entry.0, text));
+                entry.2 = Some(if lexical.is_empty() {
+                    part
+                } else {
+                    format!("{}; {}", lexical, part)
+                });
+            } else if let Some(record) = self.registry.get(&CallableId::from(id.clone())) {
+                fused.insert(id, (record, rrf(rank), Some(part)));
+            }
+        }
+
+        fused.into_values().collect()
+    }
+
+    /// Index the documentation sections of a skill (no-op for tools)
+    fn index_sections(&self, record: &CallableRecord) {
+        if let (CallableKind::Skill, Some(dir)) = (record.kind, &record.skill_directory) {
+            let sections = load_skill_sections(dir);
+            self.sections.write().set_sections(&record.id, sections);
         }
     }
 
     /// Rebuild index from registry
+    ///
+    /// The callable index is skipped while searches go to FTS5, so large
+    /// catalogs are not held in memory twice; skill sections are always rebuilt.
     pub fn rebuild(&self) {
+        self.sections.write().clear();
+        for record in self.registry.by_kind(CallableKind::Skill) {
+            self.index_sections(&record);
+        }
+
+        if self.fts_backend().is_some() {
+            self.index.write().clear();
+            return;
+        }
+
         debug!("Rebuilding search index");
         let callables = self.registry.all();
         let mut index = self.index.write();
@@ -116,16 +403,55 @@ impl SearchEngine {
 
     /// Incremental index update
     pub fn update_record(&self, record: &CallableRecord) {
+        self.index_sections(record);
         let mut index = self.index.write();
         index.add_record(record);
     }
 
     /// Remove from index
     pub fn remove_record(&self, id: &CallableId) {
+        self.sections.write().remove(id);
         let mut index = self.index.write();
         index.remove_record(id);
     }
 
+    /// Apply registry changes since the last search
+    ///
+    /// Falls back to a full rebuild when the index and registry disagree on
+    /// size, which covers records loaded with `Registry::hydrate`.
+    fn sync(&self) {
+        let pending: Vec<RegistryEvent> = {
+            let mut events = self.events.lock();
+            std::iter::from_fn(|| events.try_recv().ok()).collect()
+        };
+
+        for event in &pending {
+            match event {
+                RegistryEvent::Upserted(record) => self.index_sections(record),
+                RegistryEvent::Removed(id) => self.sections.write().remove(id),
+            }
+        }
+
+        // FTS5 follows the database; skill sections are all that is kept in memory
+        if self.fts_backend().is_some() {
+            return;
+        }
+
+        if !pending.is_empty() {
+            let mut index = self.index.write();
+            for event in pending {
+                match event {
+                    RegistryEvent::Upserted(record) => index.add_record(&record),
+                    RegistryEvent::Removed(id) => index.remove_record(&id),
+                }
+            }
+        }
+
+        if self.index.read().len() != self.registry.len() {
+            self.rebuild();
+        }
+    }
+
     /// Search the index
     pub async fn search(&self, query: &SearchQuery) -> Result<SearchResults> {
         if query.q.is_empty() {
@@ -136,63 +462,271 @@ impl SearchEngine {
 
         debug!("Search query: {:?}", query.q);
 
-        // Detect query intent
-
-        // Get all callables from registry
-        let mut candidates = self.registry.all();
-
-        // Apply kind filter
-        if query.kind != "any" {
-            let target_kind = match query.kind.as_str() {
-                "tools" => CallableKind::Tool,
-                "skills" => CallableKind::Skill,
-                _ => {
-                    return Err(IndexError::InvalidQuery(format!(
-                        "Invalid kind: {}",
-                        query.kind
-                    )));
+        let target_kind = match query.kind.as_str() {
+            "any" => None,
+            "tools" => Some(CallableKind::Tool),
+            "skills" => Some(CallableKind::Skill),
+            _ => {
+                return Err(IndexError::InvalidQuery(format!(
+                    "Invalid kind: {}",
+                    query.kind
+                )));
+            }
+        };
+
+        self.sync();
+        let generation = self.registry.generation();
+
+        // Later pages apply feedback as of the first, so decay cannot reorder them
+        let resume = query
+            .cursor
+            .as_deref()
+            .map(|cursor| self.cursors.decode(cursor, query))
+            .transpose()?;
+        let ranked_at = resume
+            .as_ref()
+            .map_or_else(|| self.cursors.now(), |(_, at, _)| *at);
+
+        // Inline filters (`server:github -tag:deprecated ...`) narrow whatever the text matches
+        let parsed = parse_query(&query.q)?;
+        let text = parsed.text.as_str();
+
+        // Retrieve and score candidates
+        let mut candidates: Vec<(CallableRecord, f64, Option<String>)> = if text.is_empty() {
+            if !parsed.has_filters() {
+                return Err(IndexError::InvalidQuery(
+                    "Query cannot be empty".to_string(),
+                ));
+            }
+            let explanation = format!("filters: {}", parsed.describe_filters());
+            self.registry
+                .all()
+                .into_iter()
+                .map(|record| (record, 0.0, Some(explanation.clone())))
+                .collect()
+        } else {
+            match query.mode {
+                SearchMode::Literal => {
+                    if let Some(persistence) = self.fts_backend() {
+                        let kind = target_kind.map(|k| k.to_string());
+                        let resume_id = resume.as_ref().map(|(_, _, last)| last.id.as_str());
+                        let limit = FTS_CANDIDATE_LIMIT as usize;
+
+                        // Filters apply after retrieval, so read batches until enough hits
+                        // pass them, counting from the cursor's last match when resuming
+                        let mut candidates = Vec::new();
+                        let mut wanted = if resume_id.is_some() {
+                            usize::MAX
+                        } else {
+                            limit
+                        };
+                        let mut offset = 0;
+                        while candidates.len() < wanted {
+                            let batch = persistence
+                                .search_callables_page(
+                                    text,
+                                    kind.as_deref(),
+                                    offset,
+                                    FTS_CANDIDATE_LIMIT,
+                                )
+                                .await
+                                .map_err(|e| {
+                                    IndexError::Internal(format!("FTS5 search failed: {}", e))
+                                })?;
+                            let exhausted = batch.len() < limit;
+                            offset += FTS_CANDIDATE_LIMIT;
+
+                            for (id, score) in batch {
+                                let Some(record) = self.registry.get(&CallableId::from(id)) else {
+                                    continue;
+                                };
+                                if !passes_filters(&record, &parsed, query.filters.as_ref()) {
+                                    continue;
+                                }
+                                if Some(record.id.as_str()) == resume_id {
+                                    wanted = candidates.len() + 1 + limit;
+                                }
+                                candidates.push((record, score, None));
+                            }
+                            if exhausted {
+                                break;
+                            }
+                        }
+                        candidates
+                    } else {
+                        let hits = self.index.read().search(text);
+                        hits.into_iter()
+                            .filter_map(|hit| {
+                                let record = self.registry.get(&CallableId::from(hit.id))?;
+                                Some((record, hit.score, Some(hit.explanation)))
+                            })
+                            .collect()
+                    }
                 }
-            };
-            candidates.retain(|c| c.kind == target_kind);
+                SearchMode::Regex => {
+                    let re = regex::RegexBuilder::new(text)
+                        .case_insensitive(true)
+                        .build()?;
+                    self.registry
+                        .all()
+                        .into_iter()
+                        .filter_map(|record| {
+                            let (score, explanation) = regex_score(&re, &record)?;
+                            Some((record, score, Some(explanation)))
+                        })
+                        .collect()
+                }
+                SearchMode::Fuzzy => self
+                    .registry
+                    .all()
+                    .into_iter()
+                    .filter_map(|record| {
+                        let (score, explanation) = fuzzy_score(text, &record)?;
+                        Some((record, score, Some(explanation)))
+                    })
+                    .collect(),
+            }
+        };
+
+        // Skill documentation sections add to (or surface) the skills they belong to
+        let mut section_matches: HashMap<String, SectionMatch> = HashMap::new();
+        if query.mode == SearchMode::Literal && !text.is_empty() {
+            let hits = self.sections.read().search(text);
+            for hit in hits {
+                let part = format!(
+                    "§ {} ({}): {}",
+                    if hit.section.heading.is_empty() {
+                        "(intro)"
+                    } else {
+                        hit.section.heading.as_str()
+                    },
+                    hit.section.file,
+                    hit.terms.join(", ")
+                );
+                let score = hit.score * SECTION_WEIGHT;
+
+                if let Some(entry) = candidates
+                    .iter_mut()
+                    .find(|(record, _, _)| record.id.as_str() == hit.callable_id)
+                {
+                    entry.1 += score;
+                    let lexical = entry
+                        .2
+                        .take()
+                        .unwrap_or_else(|| explain_record(&entry.0, text));
+                    entry.2 = Some(if lexical.is_empty() {
+                        part
+                    } else {
+                        format!("{}; {}", lexical, part)
+                    });
+                } else if let Some(record) = self
+                    .registry
+                    .get(&CallableId::from(hit.callable_id.clone()))
+                {
+                    candidates.push((record, score, Some(part)));
+                } else {
+                    continue;
+                }
+                section_matches.insert(hit.callable_id, hit.section);
+            }
         }
 
-        // Apply filters
-        if let Some(filters) = &query.filters {
-            candidates = apply_filters(candidates, filters);
+        // Semantic stage: fuse with the lexical ranking
+        if let (SearchMode::Literal, Some(semantic)) = (query.mode, &self.semantic) {
+            if !text.is_empty() {
+                semantic.refresh(&self.registry.all()).await;
+                let hits = semantic.search(text, SEMANTIC_MIN_SIMILARITY, SEMANTIC_CANDIDATE_LIMIT);
+                candidates = self.fuse(candidates, hits, text);
+            }
         }
 
-        // Score and rank matches
-        // Score and rank matches using ripgrep-style substring matching
-        let mut scored: Vec<(CallableRecord, f64)> = candidates
-            .into_iter()
-            .filter_map(|record| {
-                let score = score_ripgrep(&query.q, &record);
+        // Feedback: boost what worked for similar queries, demote what errors
+        if let Some(feedback) = &self.feedback {
+            let ids: HashSet<String> = candidates
+                .iter()
+                .map(|(record, _, _)| record.id.as_str().to_string())
+                .collect();
+            let boosts = feedback
+                .boosts(
+                    query.session.as_deref(),
+                    &query_terms(text),
+                    &ids,
+                    ranked_at,
+                )
+                .await;
+            for (record, score, explanation) in candidates.iter_mut() {
+                if let Some(boost) = boosts.get(record.id.as_str()) {
+                    *score *= boost.factor;
+                    let base = explanation
+                        .take()
+                        .unwrap_or_else(|| explain_record(record, text));
+                    *explanation = Some(if base.is_empty() {
+                        boost.explanation.clone()
+                    } else {
+                        format!("{}; {}", base, boost.explanation)
+                    });
+                }
+            }
+        }
 
-                if score > 0.0 {
-                    Some((record, score))
+        // Apply kind, inline, and request filters
+        if let Some(kind) = target_kind {
+            candidates.retain(|(record, _, _)| record.kind == kind);
+        }
+        candidates.retain(|(record, _, _)| passes_filters(record, &parsed, query.filters.as_ref()));
+
+        // Sort by score descending, then name and id for stable pagination
+        candidates.sort_by(|a, b| Position::order(rank_key(a), rank_key(b)));
+
+        let total_matches = candidates.len();
+
+        // Resume after the last match of the previous page
+        let offset = match resume {
+            Some((issued, _, last)) => {
+                if issued == generation {
+                    candidates.partition_point(|c| {
+                        Position::order(rank_key(c), last.key()) != std::cmp::Ordering::Greater
+                    })
                 } else {
-                    None
+                    // Scores shift with the catalog, so re-anchor on the last match itself
+                    candidates
+                        .iter()
+                        .position(|(record, _, _)| record.id.as_str() == last.id)
+                        .map(|i| i + 1)
+                        .ok_or_else(|| {
+                            IndexError::CursorExpired(format!(
+                                "{} is no longer in the results; repeat the search without a cursor",
+                                last.fq_name
+                            ))
+                        })?
                 }
+            }
+            None => 0,
+        };
+
+        let positions: Vec<Position> = candidates
+            .iter()
+            .skip(offset)
+            .take(query.limit)
+            .map(|(record, score, _)| Position {
+                score: *score,
+                fq_name: record.fq_name.clone(),
+                id: record.id.as_str().to_string(),
             })
             .collect();
+        let next_cursor = positions
+            .last()
+            .filter(|_| offset + positions.len() < total_matches)
+            .map(|last| {
+                self.cursors
+                    .encode(query, generation, ranked_at, last.clone())
+            });
 
-        // Sort by score descending
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        let total_matches = scored.len();
-
-        // Apply pagination
-        let offset = query
-            .cursor
-            .as_ref()
-            .and_then(|c| c.parse::<usize>().ok())
-            .unwrap_or(0);
-
-        let matches: Vec<SearchMatch> = scored
+        let matches: Vec<SearchMatch> = candidates
             .into_iter()
             .skip(offset)
             .take(query.limit)
-            .map(|(record, score)| {
+            .map(|(record, score, explanation)| {
                 let inputs = extract_input_keys(&record.input_schema);
                 let description_snippet = record
                     .description
@@ -210,10 +744,18 @@ impl SearchEngine {
                     server: record.server_alias.clone(),
                     description_snippet,
                     inputs,
-                    score,
-                    signature_short: None,
-                    schema_digest: Some(record.schema_digest.short().to_string()),
-                    uses: if record.kind == CallableKind::Skill {
+                    score: (score * 1000.0).round() / 1000.0,
+                    signature_short: query.include.signature.then(|| {
+                        CallableSignature::short(
+                            &record.input_schema,
+                            record.output_schema.as_ref(),
+                        )
+                    }),
+                    schema_digest: query
+                        .include
+                        .schema_digest
+                        .then(|| record.schema_digest.short().to_string()),
+                    uses: if query.include.uses && record.kind == CallableKind::Skill {
                         Some(
                             record
                                 .uses
@@ -224,192 +766,618 @@ impl SearchEngine {
                     } else {
                         None
                     },
+                    stale: self.registry.is_stale(&record),
+                    explanation: Some(explanation.unwrap_or_else(|| explain_record(&record, text))),
+                    section: section_matches.remove(record.id.as_str()),
                 }
             })
             .collect();
 
-        let next_cursor = if offset + query.limit < total_matches {
-            Some((offset + query.limit).to_string())
-        } else {
-            None
-        };
+        if let (Some(feedback), Some(session)) = (&self.feedback, &query.session) {
+            feedback.note_search(
+                session,
+                query_terms(text),
+                matches.iter().map(|m| m.id.clone()).collect(),
+            );
+        }
 
         Ok(SearchResults {
             matches,
             total_matches,
             next_cursor,
+            page: Some(Page {
+                generation,
+                ranked_at,
+                positions,
+            }),
         })
     }
+
+    /// Cursor resuming after the first `kept` matches of a page
+    ///
+    /// For pages cut short after ranking, e.g. to fit a token budget, so the
+    /// next page continues from the same ranking rather than a fresh search.
+    pub fn cursor_after(
+        &self,
+        query: &SearchQuery,
+        results: &SearchResults,
+        kept: usize,
+    ) -> Option<String> {
+        let Some(page) = &results.page else {
+            return results.next_cursor.clone();
+        };
+        if kept >= page.positions.len() {
+            return results.next_cursor.clone();
+        }
+        match kept.checked_sub(1) {
+            Some(last) => Some(self.cursors.encode(
+                query,
+                page.generation,
+                page.ranked_at,
+                page.positions[last].clone(),
+            )),
+            // Nothing kept; resume where this page began
+            None => query.cursor.clone(),
+        }
+    }
+}
+
+/// Sort key of a scored candidate (see `Position::order`)
+fn rank_key((record, score, _): &(CallableRecord, f64, Option<String>)) -> (f64, &str, &str) {
+    (*score, record.fq_name.as_str(), record.id.as_str())
 }
 
-/// In-memory inverted index
+/// Section scores count this much toward their skill's score
+const SECTION_WEIGHT: f64 = 0.5;
+
+/// Reciprocal rank fusion constant; larger values flatten rank differences
+const RRF_K: f64 = 60.0;
+
+/// Lowest cosine similarity for a semantic hit
+const SEMANTIC_MIN_SIMILARITY: f32 = 0.3;
+
+/// Most semantic hits fused per query
+const SEMANTIC_CANDIDATE_LIMIT: usize = 50;
+
+/// Fused score for a 0-based rank, scaled so rank 0 in one list scores 1.0
+fn rrf(rank: usize) -> f64 {
+    (RRF_K + 1.0) / (RRF_K + 1.0 + rank as f64)
+}
+
+/// FTS5 hits read per batch, and filtered hits gathered past the cursor
+const FTS_CANDIDATE_LIMIT: i64 = 500;
+
+/// Indexed fields, in `FIELD_WEIGHTS` order
+const FIELD_NAMES: [&str; FIELD_COUNT] =
+    ["name", "fq_name", "title", "description", "tags", "params"];
+
+/// BM25F field weights: names dominate, prose counts least
+const FIELD_WEIGHTS: [f64; FIELD_COUNT] = [3.0, 2.5, 2.0, 1.0, 1.5, 1.5];
+
+const FIELD_COUNT: usize = 6;
+
+/// BM25 term-frequency saturation
+const K1: f64 = 1.2;
+
+/// BM25 length normalization
+const B: f64 = 0.75;
+
+/// Score multiplier for a query term that only matches as a prefix
+pub(crate) const PREFIX_WEIGHT: f64 = 0.6;
+
+/// Shortest query term that may match as a prefix
+pub(crate) const MIN_PREFIX_LEN: usize = 3;
+
+/// Most index terms a single prefix may expand to
+const MAX_PREFIX_EXPANSIONS: usize = 64;
+
+/// Bonus when the whole query equals the callable name
+const EXACT_NAME_BONUS: f64 = 2.0;
+
+/// Query words too common to carry meaning
+pub(crate) const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "by", "for", "from", "in", "is", "it", "of", "on", "or", "the", "to", "with",
+];
+
+struct IndexedDoc {
+    /// Lowercase name and fq_name, for the exact-name bonus
+    names: [String; 2],
+    lengths: [u32; FIELD_COUNT],
+    terms: Vec<String>,
+}
+
+/// Scored index hit
+struct IndexHit {
+    id: String,
+    score: f64,
+    explanation: String,
+}
+
+/// In-memory inverted index with per-field term frequencies
 struct InMemoryIndex {
-    // Token -> CallableId mappings
-    tokens: HashMap<String, HashSet<String>>,
-    // CallableId -> tokens for removal
-    reverse: HashMap<String, HashSet<String>>,
+    // Term -> CallableId -> term frequency per field
+    postings: BTreeMap<String, HashMap<String, [u32; FIELD_COUNT]>>,
+    // CallableId -> field lengths and terms, for scoring and removal
+    docs: HashMap<String, IndexedDoc>,
+    total_lengths: [u64; FIELD_COUNT],
 }
 
 impl InMemoryIndex {
     fn new() -> Self {
         InMemoryIndex {
-            tokens: HashMap::new(),
-            reverse: HashMap::new(),
+            postings: BTreeMap::new(),
+            docs: HashMap::new(),
+            total_lengths: [0; FIELD_COUNT],
         }
     }
 
     fn add_record(&mut self, record: &CallableRecord) {
+        self.remove_record(&record.id);
+
         let id = record.id.as_str().to_string();
-        let tokens = tokenize_record(record);
+        let fields = tokenize_record(record);
+        let mut lengths = [0u32; FIELD_COUNT];
+        let mut terms = Vec::new();
 
-        for token in &tokens {
-            self.tokens
-                .entry(token.clone())
-                .or_default()
-                .insert(id.clone());
+        for (field, tokens) in fields.iter().enumerate() {
+            lengths[field] = tokens.len() as u32;
+            self.total_lengths[field] += tokens.len() as u64;
+            for token in tokens {
+                let postings = self.postings.entry(token.clone()).or_default();
+                let tf = postings.entry(id.clone()).or_insert_with(|| {
+                    terms.push(token.clone());
+                    [0; FIELD_COUNT]
+                });
+                tf[field] += 1;
+            }
         }
 
-        self.reverse.insert(id, tokens);
+        self.docs.insert(
+            id,
+            IndexedDoc {
+                names: [record.name.to_lowercase(), record.fq_name.to_lowercase()],
+                lengths,
+                terms,
+            },
+        );
     }
 
     fn remove_record(&mut self, id: &CallableId) {
         let id_str = id.as_str();
-        if let Some(tokens) = self.reverse.remove(id_str) {
-            for token in tokens {
-                if let Some(ids) = self.tokens.get_mut(&token) {
+        if let Some(doc) = self.docs.remove(id_str) {
+            for (total, len) in self.total_lengths.iter_mut().zip(doc.lengths) {
+                *total -= u64::from(len);
+            }
+            for term in doc.terms {
+                if let Some(ids) = self.postings.get_mut(&term) {
                     ids.remove(id_str);
+                    if ids.is_empty() {
+                        self.postings.remove(&term);
+                    }
                 }
             }
         }
     }
 
     fn clear(&mut self) {
-        self.tokens.clear();
-        self.reverse.clear();
+        self.postings.clear();
+        self.docs.clear();
+        self.total_lengths = [0; FIELD_COUNT];
     }
 
     fn len(&self) -> usize {
-        self.reverse.len()
+        self.docs.len()
     }
-}
 
-/// Tokenize a record for indexing
-fn tokenize_record(record: &CallableRecord) -> HashSet<String> {
-    let mut tokens = HashSet::new();
+    /// Score every document matching at least one query term
+    ///
+    /// Each query term contributes its best BM25F score among exact and
+    /// prefix matches. The sum is scaled by the fraction of query terms
+    /// matched, so documents covering the whole query rank first.
+    fn search(&self, query: &str) -> Vec<IndexHit> {
+        let terms = query_terms(query);
+        if terms.is_empty() || self.docs.is_empty() {
+            return Vec::new();
+        }
 
-    // Tokenize name
-    for token in tokenize(&record.name) {
-        tokens.insert(token);
-    }
+        let doc_count = self.docs.len() as f64;
+        let avg_lengths: Vec<f64> = self
+            .total_lengths
+            .iter()
+            .map(|total| (*total as f64 / doc_count).max(1.0))
+            .collect();
 
-    // Tokenize FQ name
-    for token in tokenize(&record.fq_name) {
-        tokens.insert(token);
-    }
+        // doc -> (score, matched terms, explanation parts)
+        let mut scores: HashMap<&str, (f64, usize, Vec<String>)> = HashMap::new();
+
+        for term in &terms {
+            // doc -> (best contribution, explanation)
+            let mut best: HashMap<&str, (f64, String)> = HashMap::new();
+
+            let prefix_matches = self
+                .postings
+                .range::<str, _>((
+                    std::ops::Bound::Excluded(term.as_str()),
+                    std::ops::Bound::Unbounded,
+                ))
+                .take_while(|(candidate, _)| candidate.starts_with(term.as_str()))
+                .take(if term.len() >= MIN_PREFIX_LEN {
+                    MAX_PREFIX_EXPANSIONS
+                } else {
+                    0
+                })
+                .map(|(candidate, postings)| (candidate, postings, PREFIX_WEIGHT));
+            let exact = self
+                .postings
+                .get_key_value(term.as_str())
+                .map(|(k, p)| (k, p, 1.0));
+
+            for (candidate, postings, weight) in exact.into_iter().chain(prefix_matches) {
+                let df = postings.len() as f64;
+                let idf = (1.0 + (doc_count - df + 0.5) / (df + 0.5)).ln();
+
+                for (doc_id, tf) in postings {
+                    let Some(doc) = self.docs.get(doc_id) else {
+                        continue;
+                    };
+                    let weighted_tf: f64 = (0..FIELD_COUNT)
+                        .filter(|f| tf[*f] > 0)
+                        .map(|f| {
+                            let norm = 1.0 - B + B * f64::from(doc.lengths[f]) / avg_lengths[f];
+                            FIELD_WEIGHTS[f] * f64::from(tf[f]) / norm
+                        })
+                        .sum();
+                    let contribution = weight * idf * weighted_tf / (K1 + weighted_tf);
+
+                    if best
+                        .get(doc_id.as_str())
+                        .map_or(true, |(score, _)| contribution > *score)
+                    {
+                        let fields: Vec<&str> = (0..FIELD_COUNT)
+                            .filter(|f| tf[*f] > 0)
+                            .map(|f| FIELD_NAMES[f])
+                            .collect();
+                        let label = if candidate == term {
+                            format!("{}: {}", term, fields.join(", "))
+                        } else {
+                            format!("{}→{} (prefix): {}", term, candidate, fields.join(", "))
+                        };
+                        best.insert(doc_id.as_str(), (contribution, label));
+                    }
+                }
+            }
 
-    // Tokenize title
-    if let Some(title) = &record.title {
-        for token in tokenize(title) {
-            tokens.insert(token);
+            for (doc_id, (contribution, label)) in best {
+                let entry = scores.entry(doc_id).or_insert((0.0, 0, Vec::new()));
+                entry.0 += contribution;
+                entry.1 += 1;
+                entry.2.push(label);
+            }
         }
+
+        let whole_query = query.trim().to_lowercase();
+        scores
+            .into_iter()
+            .map(|(id, (score, matched, mut parts))| {
+                let mut score = score * matched as f64 / terms.len() as f64;
+                if self
+                    .docs
+                    .get(id)
+                    .is_some_and(|doc| doc.names.contains(&whole_query))
+                {
+                    score += EXACT_NAME_BONUS;
+                    parts.push("exact name".to_string());
+                }
+                IndexHit {
+                    id: id.to_string(),
+                    score,
+                    explanation: parts.join("; "),
+                }
+            })
+            .collect()
     }
+}
+
+/// Normalized, deduplicated query terms without stopwords
+pub(crate) fn query_terms(query: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    split_words(query)
+        .into_iter()
+        .filter(|t| !STOPWORDS.contains(&t.as_str()))
+        .map(|t| normalize_term(&t))
+        .filter(|t| seen.insert(t.clone()))
+        .collect()
+}
 
-    // Tokenize description
-    if let Some(desc) = &record.description {
-        for token in tokenize(desc) {
-            tokens.insert(token);
+/// Explain a match that did not come from the in-memory index (FTS5 hits)
+fn explain_record(record: &CallableRecord, query: &str) -> String {
+    let fields = tokenize_record(record);
+    let mut parts = Vec::new();
+
+    for term in query_terms(query) {
+        let matched_in = |candidate: &str| -> Vec<&str> {
+            (0..FIELD_COUNT)
+                .filter(|f| fields[*f].iter().any(|t| t == candidate))
+                .map(|f| FIELD_NAMES[f])
+                .collect()
+        };
+
+        let exact = matched_in(&term);
+        if !exact.is_empty() {
+            parts.push(format!("{}: {}", term, exact.join(", ")));
+        } else if term.len() >= MIN_PREFIX_LEN {
+            if let Some(candidate) = fields
+                .iter()
+                .flatten()
+                .find(|t| t.starts_with(term.as_str()))
+            {
+                parts.push(format!(
+                    "{}→{} (prefix): {}",
+                    term,
+                    candidate,
+                    matched_in(candidate).join(", ")
+                ));
+            }
         }
     }
 
-    // Add tags
-    for tag in &record.tags {
-        tokens.insert(tag.to_lowercase());
+    parts.join("; ")
+}
+
+/// Score a record by the weighted fields a regex matches
+fn regex_score(re: &regex::Regex, record: &CallableRecord) -> Option<(f64, String)> {
+    let fields = [
+        record.name.clone(),
+        record.fq_name.clone(),
+        record.title.clone().unwrap_or_default(),
+        record.description.clone().unwrap_or_default(),
+        record.tags.join(" "),
+        extract_input_keys(&record.input_schema).join(" "),
+    ];
+
+    let matched: Vec<usize> = (0..FIELD_COUNT)
+        .filter(|f| re.is_match(&fields[*f]))
+        .collect();
+    if matched.is_empty() {
+        return None;
     }
 
-    tokens
+    let score = matched.iter().map(|f| FIELD_WEIGHTS[*f]).sum();
+    let names: Vec<&str> = matched.iter().map(|f| FIELD_NAMES[*f]).collect();
+    Some((score, format!("/{}/: {}", re.as_str(), names.join(", "))))
 }
 
-/// Simple tokenization (lowercase, split on non-alphanumeric including underscores)
-fn tokenize(text: &str) -> Vec<String> {
-    text.to_lowercase()
-        .split(|c: char| !c.is_alphanumeric())
-        .filter(|s| !s.is_empty())
-        .map(String::from)
-        .collect()
-}
+/// Lowest similarity (0..1) a fuzzy match needs to be returned
+const FUZZY_MIN_SCORE: f64 = 0.6;
+
+/// Score a record's name and fq_name against a possibly misspelled query
+///
+/// Takes the better of per-word edit distance (`gthub_isue` → `github`,
+/// `issue`) and whole-name matching, where the query may also be an
+/// abbreviation (an in-order subsequence, e.g. `ghissue`).
+fn fuzzy_score(query: &str, record: &CallableRecord) -> Option<(f64, String)> {
+    let query_words = split_words(query);
+    if query_words.is_empty() {
+        return None;
+    }
 
-/// Score using fuzzy matching (simple edit distance)
-fn score_ripgrep(query: &str, record: &CallableRecord) -> f64 {
-    let query_lower = query.to_lowercase();
-    let mut score = 0.0;
+    let mut name_words = split_words(&record.fq_name);
+    for word in split_words(&record.name) {
+        if !name_words.contains(&word) {
+            name_words.push(word);
+        }
+    }
 
-    // Use simple substring matching as fuzzy approximation
-    if record.name.to_lowercase().contains(&query_lower) {
-        score += 30.0;
+    // Per-word: each query word against its closest name word
+    let mut word_total = 0.0;
+    let mut word_parts = Vec::new();
+    for word in &query_words {
+        let best = name_words
+            .iter()
+            .map(|candidate| (candidate, levenshtein(word, candidate)))
+            .filter(|(_, distance)| *distance <= max_edits(word.chars().count()))
+            .min_by_key(|(_, distance)| *distance);
+        if let Some((candidate, distance)) = best {
+            let len = word.chars().count().max(candidate.chars().count());
+            word_total += 1.0 - distance as f64 / len as f64;
+            word_parts.push(match distance {
+                0 => word.clone(),
+                1 => format!("{}→{} (1 edit)", word, candidate),
+                n => format!("{}→{} ({} edits)", word, candidate, n),
+            });
+        }
+    }
+    let word_score = word_total / query_words.len() as f64;
+
+    // Whole name: edit distance or in-order subsequence, ignoring separators
+    let compact = |text: &str| -> String {
+        text.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let query_compact = compact(query);
+    let mut whole = (0.0, String::new());
+    for target in [&record.name, &record.fq_name] {
+        let target_compact = compact(target);
+        let len = query_compact
+            .chars()
+            .count()
+            .max(target_compact.chars().count());
+        let mut score = 1.0 - levenshtein(&query_compact, &target_compact) as f64 / len as f64;
+        let mut how = "edit distance";
+        if query_compact.chars().count() >= 3 && is_subsequence(&query_compact, &target_compact) {
+            let coverage =
+                query_compact.chars().count() as f64 / target_compact.chars().count() as f64;
+            if 0.5 + 0.5 * coverage > score {
+                score = 0.5 + 0.5 * coverage;
+                how = "subsequence";
+            }
+        }
+        if score > whole.0 {
+            whole = (score, format!("{} of {}", how, target));
+        }
     }
 
-    if record.fq_name.to_lowercase().contains(&query_lower) {
-        score += 25.0;
+    // Word-level explanations are more useful whenever every word found a match
+    let score = word_score.max(whole.0);
+    let explanation = if word_parts.len() == query_words.len() {
+        word_parts.join("; ")
+    } else {
+        whole.1
+    };
+    (score >= FUZZY_MIN_SCORE).then(|| (score, format!("fuzzy: {}", explanation)))
+}
+
+/// Edits tolerated for a query word of `len` characters
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=2 => 0,
+        3..=4 => 1,
+        5..=8 => 2,
+        _ => 3,
     }
+}
 
-    if let Some(title) = &record.title {
-        if title.to_lowercase().contains(&query_lower) {
-            score += 15.0;
+/// Levenshtein distance over chars
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
         }
+        std::mem::swap(&mut prev, &mut cur);
     }
 
-    if let Some(desc) = &record.description {
-        if desc.to_lowercase().contains(&query_lower) {
-            score += 10.0;
+    prev[b.len()]
+}
+
+/// Whether `needle`'s chars appear in `haystack` in order
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// Tokenize a record's searchable fields, in `FIELD_NAMES` order
+fn tokenize_record(record: &CallableRecord) -> [Vec<String>; FIELD_COUNT] {
+    let terms = |text: &str| -> Vec<String> {
+        split_words(text)
+            .iter()
+            .map(|w| normalize_term(w))
+            .collect()
+    };
+
+    [
+        terms(&record.name),
+        terms(&record.fq_name),
+        record.title.as_deref().map(terms).unwrap_or_default(),
+        record.description.as_deref().map(terms).unwrap_or_default(),
+        record.tags.iter().flat_map(|tag| terms(tag)).collect(),
+        extract_input_keys(&record.input_schema)
+            .iter()
+            .flat_map(|key| terms(key))
+            .collect(),
+    ]
+}
+
+/// Split text into lowercase words on non-alphanumerics and camelCase boundaries
+///
+/// `readFile` and `read_file` both yield `read`, `file`; `HTTPRequest` yields
+/// `http`, `request`.
+pub(crate) fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for chunk in text.split(|c: char| !c.is_alphanumeric()) {
+        let chars: Vec<char> = chunk.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (prev, cur) = (chars[i - 1], chars[i]);
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if (prev.is_lowercase() && cur.is_uppercase())
+                || (prev.is_uppercase() && cur.is_uppercase() && next_is_lower)
+            {
+                words.push(chars[start..i].iter().collect::<String>().to_lowercase());
+                start = i;
+            }
+        }
+        if start < chars.len() {
+            words.push(chars[start..].iter().collect::<String>().to_lowercase());
         }
     }
+    words
+}
 
-    score
+/// Reduce simple English plurals so `issues` and `issue` index together
+pub(crate) fn normalize_term(word: &str) -> String {
+    if word.len() > 4 && word.ends_with("ies") {
+        format!("{}y", &word[..word.len() - 3])
+    } else if word.len() > 3
+        && word.ends_with('s')
+        && !word.ends_with("ss")
+        && !word.ends_with("us")
+        && !word.ends_with("is")
+    {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
 }
 
-/// Apply filters to candidates
-fn apply_filters(candidates: Vec<CallableRecord>, filters: &SearchFilters) -> Vec<CallableRecord> {
-    let mut filtered = candidates;
+/// Whether a callable satisfies the inline filters and any `SearchFilters`
+fn passes_filters(
+    record: &CallableRecord,
+    parsed: &ParsedQuery,
+    filters: Option<&SearchFilters>,
+) -> bool {
+    let requested = match filters {
+        Some(filters) => filters_match(record, filters),
+        None => true,
+    };
+    parsed.matches(record) && requested
+}
 
+/// Whether a callable passes every set filter
+fn filters_match(c: &CallableRecord, filters: &SearchFilters) -> bool {
     // Server filter (tools only)
     if let Some(server) = &filters.server {
-        filtered.retain(|c| {
-            c.server_alias
-                .as_ref()
-                .map(|s| s == server)
-                .unwrap_or(false)
-        });
+        if c.server_alias.as_ref() != Some(server) {
+            return false;
+        }
     }
 
     // Tags filter (any match)
     if let Some(tags) = &filters.tags {
-        if !tags.is_empty() {
-            filtered.retain(|c| tags.iter().any(|tag| c.tags.contains(tag)));
+        if !tags.is_empty() && !tags.iter().any(|tag| c.tags.contains(tag)) {
+            return false;
         }
     }
 
     // Requires filter (schema must have all keys)
     if let Some(requires) = &filters.requires {
         if !requires.is_empty() {
-            filtered.retain(|c| {
-                let keys = extract_input_keys(&c.input_schema);
-                requires.iter().all(|req| keys.contains(req))
-            });
+            let keys = extract_input_keys(&c.input_schema);
+            if !requires.iter().all(|req| keys.contains(req)) {
+                return false;
+            }
         }
     }
 
     // Capability filter (tags-based capability matching)
     if let Some(capability) = &filters.capability {
-        if !capability.is_empty() {
-            filtered.retain(|c| capability.iter().any(|cap| c.tags.contains(cap)));
+        if !capability.is_empty() && !capability.iter().any(|cap| c.tags.contains(cap)) {
+            return false;
         }
     }
 
-    filtered
+    true
 }
 
 /// Extract top-level input keys from JSON schema
-fn extract_input_keys(schema: &serde_json::Value) -> Vec<String> {
+pub(crate) fn extract_input_keys(schema: &serde_json::Value) -> Vec<String> {
     if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
         properties.keys().cloned().collect()
     } else {
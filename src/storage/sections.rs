@@ -0,0 +1,348 @@
+//! Section-level indexing of skill documentation
+//!
+//! SKILL.md bodies and `references/*.md` files are split at markdown headings
+//! and indexed per section, so a search hit can point at the part of a skill
+//! that matched (`skill:pdf-tools § Extracting tables`) and an agent can load
+//! only that section with `manage get`.
+
+use crate::core::CallableId;
+use crate::storage::search::{
+    normalize_term, query_terms, split_words, MIN_PREFIX_LEN, PREFIX_WEIGHT,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Heading terms weigh this much more than body terms
+const HEADING_WEIGHT: f64 = 2.0;
+
+/// BM25 term-frequency saturation
+const K1: f64 = 1.2;
+
+/// BM25 length normalization
+const B: f64 = 0.75;
+
+/// Longest snippet returned with a section hit, in characters
+const SNIPPET_CHARS: usize = 160;
+
+/// A heading and the text under it, up to the next heading of any level
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// File relative to the skill directory, e.g. `SKILL.md` or `references/tables.md`
+    pub file: String,
+    /// Heading text; empty for text before the first heading
+    pub heading: String,
+    /// Heading level (1-6); 0 for text before the first heading
+    pub level: usize,
+    pub body: String,
+}
+
+/// Where a search hit matched inside a skill's documentation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionMatch {
+    pub file: String,
+    pub heading: String,
+    pub snippet: String,
+}
+
+/// Split markdown into sections at ATX headings, ignoring YAML frontmatter
+/// and headings inside fenced code blocks
+pub fn split_sections(file: &str, markdown: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut current = Section {
+        file: file.to_string(),
+        heading: String::new(),
+        level: 0,
+        body: String::new(),
+    };
+    let mut in_fence = false;
+
+    for line in strip_frontmatter(markdown).lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+
+        if let Some((level, heading)) = (!in_fence).then(|| parse_heading(line)).flatten() {
+            let next = Section {
+                file: file.to_string(),
+                heading,
+                level,
+                body: String::new(),
+            };
+            let done = std::mem::replace(&mut current, next);
+            if done.level > 0 || !done.body.trim().is_empty() {
+                sections.push(done);
+            }
+            continue;
+        }
+
+        current.body.push_str(line);
+        current.body.push('\n');
+    }
+
+    if current.level > 0 || !current.body.trim().is_empty() {
+        sections.push(current);
+    }
+    sections
+}
+
+/// Sections of a skill's SKILL.md and `references/*.md`, in that order
+pub fn load_skill_sections(skill_dir: &Path) -> Vec<Section> {
+    let mut sections = Vec::new();
+
+    if let Ok(content) = std::fs::read_to_string(skill_dir.join("SKILL.md")) {
+        sections.extend(split_sections("SKILL.md", &content));
+    }
+
+    let mut references: Vec<_> = std::fs::read_dir(skill_dir.join("references"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    references.sort();
+    for path in references {
+        if let (Some(name), Ok(content)) = (
+            path.file_name().and_then(|n| n.to_str()),
+            std::fs::read_to_string(&path),
+        ) {
+            sections.extend(split_sections(&format!("references/{}", name), &content));
+        }
+    }
+
+    sections
+}
+
+/// Text of the section titled `heading`, including its subsections
+///
+/// Headings match case-insensitively, with or without leading `#`s.
+pub fn extract_section(markdown: &str, heading: &str) -> Option<String> {
+    let wanted = heading.trim().trim_start_matches('#').trim();
+    let mut output: Option<(usize, String)> = None;
+    let mut in_fence = false;
+
+    for line in strip_frontmatter(markdown).lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let parsed = (!in_fence).then(|| parse_heading(line)).flatten();
+
+        match (&mut output, parsed) {
+            (Some((level, _)), Some((next_level, _))) if next_level <= *level => break,
+            (Some((_, text)), _) => {
+                text.push_str(line);
+                text.push('\n');
+            }
+            (None, Some((level, text))) if text.eq_ignore_ascii_case(wanted) => {
+                output = Some((level, format!("{}\n", line)));
+            }
+            (None, _) => {}
+        }
+    }
+
+    output.map(|(_, text)| text.trim_end().to_string())
+}
+
+fn strip_frontmatter(markdown: &str) -> &str {
+    markdown
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.find("\n---").map(|end| &rest[end + 4..]))
+        .map(|rest| rest.trim_start_matches(['\r', '\n']))
+        .unwrap_or(markdown)
+}
+
+/// `## Extracting tables` → (2, "Extracting tables")
+fn parse_heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim().to_string()))
+}
+
+struct IndexedSection {
+    section: Section,
+    heading_terms: Vec<String>,
+    body_terms: Vec<String>,
+}
+
+impl IndexedSection {
+    fn new(section: Section) -> Self {
+        let terms = |text: &str| -> Vec<String> {
+            split_words(text)
+                .iter()
+                .map(|w| normalize_term(w))
+                .collect()
+        };
+        IndexedSection {
+            heading_terms: terms(&section.heading),
+            body_terms: terms(&section.body),
+            section,
+        }
+    }
+
+    fn length(&self) -> f64 {
+        HEADING_WEIGHT * self.heading_terms.len() as f64 + self.body_terms.len() as f64
+    }
+
+    /// Weighted term frequency, counting prefix matches at a discount
+    fn frequency(&self, term: &str) -> f64 {
+        let weight = |token: &String| -> f64 {
+            if token == term {
+                1.0
+            } else if term.len() >= MIN_PREFIX_LEN && token.starts_with(term) {
+                PREFIX_WEIGHT
+            } else {
+                0.0
+            }
+        };
+        HEADING_WEIGHT * self.heading_terms.iter().map(weight).sum::<f64>()
+            + self.body_terms.iter().map(weight).sum::<f64>()
+    }
+}
+
+/// The best-matching section of one skill
+pub(crate) struct SectionHit {
+    pub(crate) callable_id: String,
+    pub(crate) score: f64,
+    pub(crate) terms: Vec<String>,
+    pub(crate) section: SectionMatch,
+}
+
+/// BM25 index over the documentation sections of registered skills
+#[derive(Default)]
+pub(crate) struct SectionIndex {
+    skills: HashMap<String, Vec<IndexedSection>>,
+}
+
+impl SectionIndex {
+    pub(crate) fn set_sections(&mut self, id: &CallableId, sections: Vec<Section>) {
+        if sections.is_empty() {
+            self.skills.remove(id.as_str());
+        } else {
+            self.skills.insert(
+                id.as_str().to_string(),
+                sections.into_iter().map(IndexedSection::new).collect(),
+            );
+        }
+    }
+
+    pub(crate) fn remove(&mut self, id: &CallableId) {
+        self.skills.remove(id.as_str());
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.skills.clear();
+    }
+
+    /// Best section per skill for the query, best first
+    pub(crate) fn search(&self, query: &str) -> Vec<SectionHit> {
+        let terms = query_terms(query);
+        let docs: Vec<(&String, &IndexedSection)> = self
+            .skills
+            .iter()
+            .flat_map(|(id, sections)| sections.iter().map(move |s| (id, s)))
+            .collect();
+        if terms.is_empty() || docs.is_empty() {
+            return Vec::new();
+        }
+
+        let doc_count = docs.len() as f64;
+        let avg_length = (docs.iter().map(|(_, s)| s.length()).sum::<f64>() / doc_count).max(1.0);
+        let frequencies: Vec<Vec<f64>> = docs
+            .iter()
+            .map(|(_, s)| terms.iter().map(|t| s.frequency(t)).collect())
+            .collect();
+        let idf: Vec<f64> = (0..terms.len())
+            .map(|t| {
+                let df = frequencies.iter().filter(|f| f[t] > 0.0).count() as f64;
+                (1.0 + (doc_count - df + 0.5) / (df + 0.5)).ln()
+            })
+            .collect();
+
+        let mut best: HashMap<&str, SectionHit> = HashMap::new();
+        for ((id, section), tf) in docs.iter().zip(&frequencies) {
+            let norm = K1 * (1.0 - B + B * section.length() / avg_length);
+            let matched: Vec<String> = terms
+                .iter()
+                .zip(tf)
+                .filter(|(_, f)| **f > 0.0)
+                .map(|(t, _)| t.clone())
+                .collect();
+            if matched.is_empty() {
+                continue;
+            }
+
+            let score: f64 = tf
+                .iter()
+                .zip(&idf)
+                .map(|(f, idf)| idf * f * (K1 + 1.0) / (f + norm))
+                .sum::<f64>()
+                * matched.len() as f64
+                / terms.len() as f64;
+
+            if best.get(id.as_str()).map_or(true, |hit| score > hit.score) {
+                best.insert(
+                    id.as_str(),
+                    SectionHit {
+                        callable_id: id.to_string(),
+                        score,
+                        section: SectionMatch {
+                            file: section.section.file.clone(),
+                            heading: section.section.heading.clone(),
+                            snippet: snippet(&section.section.body, &matched),
+                        },
+                        terms: matched,
+                    },
+                );
+            }
+        }
+
+        let mut hits: Vec<SectionHit> = best.into_values().collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.callable_id.cmp(&b.callable_id))
+        });
+        hits
+    }
+}
+
+/// Whitespace-collapsed excerpt of `body` around the first matched term
+fn snippet(body: &str, terms: &[String]) -> String {
+    let text = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = text.to_lowercase();
+    let start = terms
+        .iter()
+        .filter_map(|t| lower.find(t.as_str()))
+        .min()
+        .map(|pos| {
+            // Back up to the start of the sentence, but not too far
+            let sentence = lower[..pos].rfind(". ").map_or(0, |i| i + 2);
+            if pos - sentence > SNIPPET_CHARS / 2 {
+                lower[..pos].rfind(' ').map_or(pos, |i| i + 1)
+            } else {
+                sentence
+            }
+        })
+        .unwrap_or(0);
+
+    // `to_lowercase` can change byte offsets for non-ASCII text
+    let start = if lower.len() == text.len() && text.is_char_boundary(start) {
+        start
+    } else {
+        0
+    };
+
+    let excerpt: String = text[start..].chars().take(SNIPPET_CHARS).collect();
+    if excerpt.len() < text.len() - start {
+        format!("{}…", excerpt.trim_end())
+    } else {
+        excerpt
+    }
+}
@@ -0,0 +1,290 @@
+//! Offline semantic retrieval with local word embeddings
+//!
+//! Loads a static word-vector model from a local file in the word2vec/GloVe
+//! text format (one `word v1 v2 ... vn` line per word, optionally preceded by
+//! a `count dim` header) and embeds text as the normalized mean of its word
+//! vectors. Everything runs on the CPU in pure Rust with no network access.
+//!
+//! Callable vectors are cached in SQLite per model, and reused while the
+//! callable's schema digest and embedded text are unchanged.
+
+use crate::core::persistence::{CachedEmbedding, PersistenceLayer};
+use crate::core::CallableRecord;
+use crate::storage::search::{extract_input_keys, normalize_term, split_words, STOPWORDS};
+use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+#[derive(Error, Debug)]
+pub enum EmbeddingError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid embedding model at line {line}: {message}")]
+    Format { line: usize, message: String },
+
+    #[error("Embedding model contains no vectors")]
+    Empty,
+}
+
+pub type Result<T> = std::result::Result<T, EmbeddingError>;
+
+/// Static word-embedding model
+pub struct EmbeddingModel {
+    /// `<file stem>:<content digest>`, so cached vectors never outlive their model
+    id: String,
+    dim: usize,
+    vectors: HashMap<String, Vec<f32>>,
+}
+
+impl EmbeddingModel {
+    /// Load a model from a word2vec/GloVe text file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "model".to_string());
+        let digest = blake3::hash(content.as_bytes()).to_hex();
+
+        let mut dim = 0;
+        let mut vectors = HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let mut parts = line.split_whitespace();
+            let Some(word) = parts.next() else {
+                continue;
+            };
+            let values: Vec<&str> = parts.collect();
+
+            // word2vec header: `<count> <dim>`
+            if i == 0 && values.len() == 1 && word.parse::<usize>().is_ok() {
+                continue;
+            }
+
+            let vector = values
+                .iter()
+                .map(|v| v.parse::<f32>())
+                .collect::<std::result::Result<Vec<f32>, _>>()
+                .map_err(|e| EmbeddingError::Format {
+                    line: i + 1,
+                    message: e.to_string(),
+                })?;
+            if dim == 0 {
+                dim = vector.len();
+            }
+            if vector.is_empty() || vector.len() != dim {
+                return Err(EmbeddingError::Format {
+                    line: i + 1,
+                    message: format!("expected {} dimensions, found {}", dim, vector.len()),
+                });
+            }
+            vectors.insert(word.to_lowercase(), vector);
+        }
+
+        if vectors.is_empty() {
+            return Err(EmbeddingError::Empty);
+        }
+        debug!("Loaded embedding model with {} words", vectors.len());
+
+        Ok(EmbeddingModel {
+            id: format!("{}:{}", stem, &digest[..16]),
+            dim,
+            vectors,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn vocabulary_size(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// Embed text as the unit-length mean of its known word vectors
+    ///
+    /// Returns `None` when no word of the text is in the vocabulary.
+    pub fn embed(&self, text: &str) -> Option<Vec<f32>> {
+        let mut sum = vec![0.0f32; self.dim];
+        let mut found = 0;
+
+        for word in split_words(text) {
+            if STOPWORDS.contains(&word.as_str()) {
+                continue;
+            }
+            let vector = self
+                .vectors
+                .get(&word)
+                .or_else(|| self.vectors.get(&normalize_term(&word)));
+            if let Some(vector) = vector {
+                for (total, x) in sum.iter_mut().zip(vector) {
+                    *total += x;
+                }
+                found += 1;
+            }
+        }
+
+        let norm = sum.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if found == 0 || norm == 0.0 {
+            return None;
+        }
+        Some(sum.into_iter().map(|x| x / norm).collect())
+    }
+}
+
+/// Text embedded for a callable: its names (counted twice, via `fq_name` and
+/// `name`), title, description, tags, and parameters
+fn record_text(record: &CallableRecord) -> String {
+    let mut parts = vec![record.fq_name.clone(), record.name.clone()];
+    parts.extend(record.title.clone());
+    parts.extend(record.description.clone());
+    parts.extend(record.tags.iter().cloned());
+    parts.extend(extract_input_keys(&record.input_schema));
+    parts.join(" ")
+}
+
+/// Cosine similarity of two unit vectors
+fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+struct Entry {
+    schema_digest: String,
+    content_digest: String,
+    vector: Vec<f32>,
+}
+
+/// Per-callable vectors for one model, backed by the SQLite cache
+pub(crate) struct SemanticIndex {
+    model: Arc<EmbeddingModel>,
+    persistence: Option<Arc<PersistenceLayer>>,
+    vectors: RwLock<HashMap<String, Entry>>,
+    cache_loaded: AtomicBool,
+}
+
+impl SemanticIndex {
+    pub(crate) fn new(
+        model: Arc<EmbeddingModel>,
+        persistence: Option<Arc<PersistenceLayer>>,
+    ) -> Self {
+        SemanticIndex {
+            model,
+            persistence,
+            vectors: RwLock::new(HashMap::new()),
+            cache_loaded: AtomicBool::new(false),
+        }
+    }
+
+    /// Embed callables that are new or changed, reusing cached vectors where valid
+    pub(crate) async fn refresh(&self, records: &[CallableRecord]) {
+        if let Some(persistence) = &self.persistence {
+            if !self.cache_loaded.swap(true, Ordering::SeqCst) {
+                match persistence.load_embeddings(self.model.id()).await {
+                    Ok(cached) => {
+                        let mut vectors = self.vectors.write();
+                        for embedding in cached {
+                            if embedding.vector.is_empty()
+                                || embedding.vector.len() == self.model.dim()
+                            {
+                                vectors.insert(
+                                    embedding.callable_id,
+                                    Entry {
+                                        schema_digest: embedding.schema_digest,
+                                        content_digest: embedding.content_digest,
+                                        vector: embedding.vector,
+                                    },
+                                );
+                            }
+                        }
+                    }
+                    Err(e) => warn!("Failed to load cached embeddings: {}", e),
+                }
+            }
+        }
+
+        let mut computed = Vec::new();
+        {
+            let live: HashSet<&str> = records.iter().map(|r| r.id.as_str()).collect();
+            let mut vectors = self.vectors.write();
+            vectors.retain(|id, _| live.contains(id.as_str()));
+
+            for record in records {
+                let text = record_text(record);
+                let content_digest = blake3::hash(text.as_bytes()).to_hex().to_string();
+                let schema_digest = record.schema_digest.as_str();
+                let fresh = vectors.get(record.id.as_str()).is_some_and(|entry| {
+                    entry.schema_digest == schema_digest && entry.content_digest == content_digest
+                });
+                if fresh {
+                    continue;
+                }
+
+                // Callables with no known words get an empty vector and are never returned
+                let vector = self.model.embed(&text).unwrap_or_default();
+                vectors.insert(
+                    record.id.as_str().to_string(),
+                    Entry {
+                        schema_digest: schema_digest.to_string(),
+                        content_digest: content_digest.clone(),
+                        vector: vector.clone(),
+                    },
+                );
+                computed.push(CachedEmbedding {
+                    callable_id: record.id.as_str().to_string(),
+                    schema_digest: schema_digest.to_string(),
+                    content_digest,
+                    vector,
+                });
+            }
+        }
+
+        if computed.is_empty() {
+            return;
+        }
+        debug!("Embedded {} callables", computed.len());
+        if let Some(persistence) = &self.persistence {
+            if let Err(e) = persistence
+                .save_embeddings(self.model.id(), &computed)
+                .await
+            {
+                warn!("Failed to cache embeddings: {}", e);
+            }
+        }
+    }
+
+    /// Callable ids most similar to the query, best first
+    pub(crate) fn search(
+        &self,
+        query: &str,
+        min_similarity: f32,
+        limit: usize,
+    ) -> Vec<(String, f32)> {
+        let Some(query_vector) = self.model.embed(query) else {
+            return Vec::new();
+        };
+
+        let vectors = self.vectors.read();
+        let mut hits: Vec<(String, f32)> = vectors
+            .iter()
+            .filter(|(_, entry)| entry.vector.len() == query_vector.len())
+            .map(|(id, entry)| (id.clone(), similarity(&query_vector, &entry.vector)))
+            .filter(|(_, score)| *score >= min_similarity)
+            .collect();
+        hits.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
@@ -1,8 +1,13 @@
 //! Tests for core module: types, registry, policy, and persistence
 
-use skillsrs::core::persistence::PersistenceLayer;
-use skillsrs::core::policy::{ConsentLevel, PolicyConfig, PolicyEngine};
-use skillsrs::core::registry::{Registry, ServerHealth, ServerInfo};
+use skillsrs::core::consent::{ConsentError, ConsentManager, ConsentStatus};
+use skillsrs::core::persistence::{
+    parse_history_time, redact_arguments, spawn_registry_sync, ExecutionStatus, HistoryFilter,
+    PersistenceLayer,
+};
+use skillsrs::core::policy::{ConsentLevel, PolicyConfig, PolicyEngine, PolicyError};
+use skillsrs::core::registry::{Registry, RegistryEvent, ServerHealth, ServerInfo};
+use skillsrs::core::validation;
 use skillsrs::core::{CallableId, CallableKind, CallableRecord, CostHints, RiskTier, SchemaDigest};
 use tempfile::NamedTempFile;
 
@@ -187,6 +192,118 @@ async fn test_policy_engine_require_consent_for_writes() {
     assert!(result.allowed);
 }
 
+#[tokio::test]
+async fn test_policy_engine_argument_rules() {
+    let config: PolicyConfig = serde_yaml::from_str(
+        r#"
+require_consent_for: [writes]
+argument_rules:
+  - callable: "fs.write_file"
+    path: /path
+    glob: "/etc/**"
+    action: deny
+  - callable: "fs.write_file"
+    path: /path
+    glob: "/tmp/**"
+    action: allow
+  - callable: "shell.*"
+    path: /command
+    regex: "\\brm\\s+-rf\\b"
+    action: require_consent
+    level: admin_confirmed
+  - callable: "db.query"
+    path: /limit
+    min: 1000
+    action: require_consent
+"#,
+    )
+    .unwrap();
+    let engine = PolicyEngine::new(config).unwrap();
+
+    let mut write = create_test_tool_record("write_file", "fs");
+    write.risk_tier = RiskTier::Writes;
+    let authorize = |record: &CallableRecord, args: serde_json::Value, consent| {
+        let engine = &engine;
+        let record = record.clone();
+        async move { engine.authorize(&record, &args, consent).await.unwrap() }
+    };
+
+    // Deny wins, even through `..` traversal and with consent
+    let result = authorize(
+        &write,
+        serde_json::json!({"path": "/tmp/../etc/passwd"}),
+        ConsentLevel::AdminConfirmed,
+    )
+    .await;
+    assert!(!result.allowed);
+    assert!(result.reason.contains("/path"));
+
+    // Allow waives the tier's consent requirement; other paths still need it
+    let result = authorize(
+        &write,
+        serde_json::json!({"path": "/tmp/out.txt"}),
+        ConsentLevel::None,
+    )
+    .await;
+    assert!(result.allowed);
+    let result = authorize(
+        &write,
+        serde_json::json!({"path": "/home/a.txt"}),
+        ConsentLevel::None,
+    )
+    .await;
+    assert_eq!(result.required_consent, Some(ConsentLevel::UserConfirmed));
+
+    // Escalation on a read-only tool
+    let shell = create_test_tool_record("exec", "shell");
+    let result = authorize(
+        &shell,
+        serde_json::json!({"command": "ls -la"}),
+        ConsentLevel::None,
+    )
+    .await;
+    assert!(result.allowed);
+    let result = authorize(
+        &shell,
+        serde_json::json!({"command": "rm -rf /"}),
+        ConsentLevel::UserConfirmed,
+    )
+    .await;
+    assert_eq!(result.required_consent, Some(ConsentLevel::AdminConfirmed));
+
+    let query = create_test_tool_record("query", "db");
+    let result = authorize(&query, serde_json::json!({"limit": 10}), ConsentLevel::None).await;
+    assert!(result.allowed);
+    let result = authorize(
+        &query,
+        serde_json::json!({"limit": 5000}),
+        ConsentLevel::None,
+    )
+    .await;
+    assert_eq!(result.required_consent, Some(ConsentLevel::UserConfirmed));
+}
+
+#[test]
+fn test_policy_engine_rejects_invalid_argument_rules() {
+    let config: PolicyConfig = serde_yaml::from_str(
+        r#"
+argument_rules:
+  - path: /command
+    regex: "(unclosed"
+    action: deny
+"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        PolicyEngine::new(config),
+        Err(PolicyError::ConfigError(msg)) if msg.contains("argument_rules[0]")
+    ));
+
+    let config: PolicyConfig =
+        serde_yaml::from_str("argument_rules:\n  - path: command\n    action: deny\n").unwrap();
+    assert!(PolicyEngine::new(config).is_err());
+}
+
 #[tokio::test]
 async fn test_persistence_lifecycle() {
     let temp_file = NamedTempFile::new().unwrap();
@@ -215,6 +332,7 @@ async fn test_persistence_lifecycle() {
         additional_files: vec![],
         cost_hints: CostHints::default(),
         risk_tier: RiskTier::ReadOnly,
+        annotations: None,
         last_seen: chrono::Utc::now(),
         sandbox_config: None,
     };
@@ -236,6 +354,332 @@ async fn test_persistence_lifecycle() {
     persistence.close().await;
 }
 
+#[test]
+fn test_validation_recursive_schema_allows_deep_data() {
+    let schema = serde_json::json!({
+        "$ref": "#/$defs/node",
+        "$defs": {
+            "node": {
+                "type": "object",
+                "properties": {
+                    "value": { "type": "integer" },
+                    "next": { "$ref": "#/$defs/node" }
+                },
+                "required": ["value"]
+            }
+        }
+    });
+    let list = |depth: usize, last: serde_json::Value| {
+        (0..depth).fold(
+            serde_json::json!({ "value": last }),
+            |next, i| serde_json::json!({ "value": i, "next": next }),
+        )
+    };
+
+    // Data nesting is not `$ref` recursion
+    assert!(validation::validate(&schema, &list(100, serde_json::json!(0))).is_empty());
+    let violations = validation::validate(&schema, &list(100, serde_json::json!("x")));
+    assert_eq!(violations.len(), 1);
+    assert!(violations[0].path.ends_with("/next/value"));
+
+    // A cycle that never descends is still cut off
+    let cycle =
+        serde_json::json!({ "$ref": "#/$defs/a", "$defs": { "a": { "$ref": "#/$defs/a" } } });
+    let violations = validation::validate(&cycle, &serde_json::json!({}));
+    assert_eq!(violations[0].message, "schema $ref nesting too deep");
+}
+
+#[test]
+fn test_validation_nested_paths() {
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "items": {
+                "type": "array",
+                "items": { "$ref": "#/$defs/item" }
+            },
+            "mode": { "enum": ["fast", "safe"] }
+        },
+        "required": ["items"],
+        "additionalProperties": false,
+        "$defs": {
+            "item": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "email": { "type": "string", "format": "email" }
+                },
+                "required": ["name"]
+            }
+        }
+    });
+
+    let valid = serde_json::json!({
+        "items": [{ "name": "a", "email": "a@example.com" }],
+        "mode": "fast"
+    });
+    assert!(validation::validate(&schema, &valid).is_empty());
+
+    let invalid = serde_json::json!({
+        "items": [{ "name": "a" }, {}, { "name": 3, "email": "nope" }],
+        "mode": "slow",
+        "extra": true
+    });
+    let messages: Vec<String> = validation::validate(&schema, &invalid)
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+
+    assert!(messages.contains(&"/items/1/name: missing required property".to_string()));
+    assert!(messages.contains(&"/items/2/name: expected string, got integer".to_string()));
+    assert!(messages.contains(&"/items/2/email: expected format 'email'".to_string()));
+    assert!(messages.contains(&"/extra: unexpected property".to_string()));
+    assert!(messages
+        .iter()
+        .any(|m| m.starts_with("/mode: expected one of")));
+}
+
+#[test]
+fn test_validation_composition() {
+    let schema = serde_json::json!({
+        "oneOf": [
+            { "type": "string", "minLength": 3 },
+            {
+                "type": "object",
+                "properties": { "id": { "type": "integer", "minimum": 1 } },
+                "required": ["id"]
+            }
+        ]
+    });
+
+    assert!(validation::is_valid(&schema, &serde_json::json!("abcd")));
+    assert!(validation::is_valid(
+        &schema,
+        &serde_json::json!({ "id": 7 })
+    ));
+
+    // Errors come from the branch whose type matches the instance
+    let violations = validation::validate(&schema, &serde_json::json!({ "id": 0 }));
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].to_string(), "/id: expected a value >= 1");
+
+    let violations = validation::validate(&schema, &serde_json::json!(true));
+    assert_eq!(
+        violations[0].to_string(),
+        "/: expected string or object, got boolean"
+    );
+}
+
+#[tokio::test]
+async fn test_execution_history_filters() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let persistence = PersistenceLayer::new(temp_file.path()).await.unwrap();
+
+    let read = CallableId::tool("fs", "read_file", "abc12345");
+    let readx = CallableId::tool("fs", "readxfile", "abc12345");
+    let skill = CallableId::skill("read_file", "1.0.0");
+    let now = chrono::Utc::now();
+    let entries = [
+        (&read, false, now - chrono::Duration::days(3)),
+        (&read, true, now - chrono::Duration::hours(1)),
+        (&readx, false, now),
+        (&skill, false, now),
+    ];
+    for (i, (id, is_error, started_at)) in entries.iter().enumerate() {
+        persistence
+            .record_execution(
+                &format!("exec-{}", i),
+                id,
+                &serde_json::json!({}),
+                None,
+                *is_error,
+                Some(5),
+                *started_at,
+                Some(*started_at),
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    let query = |filter: HistoryFilter| {
+        let persistence = &persistence;
+        async move {
+            persistence
+                .query_execution_history(&filter)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.execution_id)
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // `_` is matched literally, so fs/readxfile is not included
+    let by_server_tool = query(HistoryFilter {
+        callable: Some("fs/read_file".to_string()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(by_server_tool, vec!["exec-1", "exec-0"]);
+
+    let by_name = query(HistoryFilter {
+        callable: Some("read_file".to_string()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(by_name.len(), 3);
+
+    let recent_errors = query(HistoryFilter {
+        since: Some(parse_history_time("24h").unwrap()),
+        status: Some("error".parse::<ExecutionStatus>().unwrap()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(recent_errors, vec!["exec-1"]);
+
+    let older = query(HistoryFilter {
+        until: Some(parse_history_time("2d").unwrap()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(older, vec!["exec-0"]);
+
+    assert!(parse_history_time("2026-01-01T00:00:00Z").is_ok());
+    assert!(parse_history_time("yesterday").is_err());
+}
+
+#[test]
+fn test_redact_arguments() {
+    let redacted = redact_arguments(&serde_json::json!({
+        "path": "/tmp/x",
+        "api_key": "sk-123",
+        "headers": [{ "Authorization": "Bearer abc", "accept": "json" }],
+        "github_token": "ghp_123",
+        "tokenizer": "cl100k"
+    }));
+
+    assert_eq!(
+        redacted,
+        serde_json::json!({
+            "path": "/tmp/x",
+            "api_key": "[REDACTED]",
+            "headers": [{ "Authorization": "[REDACTED]", "accept": "json" }],
+            "github_token": "[REDACTED]",
+            "tokenizer": "cl100k"
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_registry_write_through_and_warm_start() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let persistence = std::sync::Arc::new(PersistenceLayer::new(temp_file.path()).await.unwrap());
+
+    let registry = Registry::new();
+    let sync = spawn_registry_sync(persistence.clone(), &registry);
+
+    let read = create_test_tool_record("read", "fs");
+    let write = create_test_tool_record("write", "fs");
+    let fetch = create_test_tool_record("fetch", "web");
+    registry.register(read.clone()).unwrap();
+    registry.register(write.clone()).unwrap();
+    registry.register(fetch.clone()).unwrap();
+    registry
+        .register(create_test_skill_record("helper"))
+        .unwrap();
+    registry.remove(&write.id);
+
+    // Dropping the registry closes the event stream once every change is written
+    drop(registry);
+    sync.await.unwrap();
+    assert_eq!(persistence.load_all_callables().await.unwrap().len(), 3);
+
+    // Only tools of configured servers are hydrated, and they start out stale
+    let registry = Registry::new();
+    let hydrated = persistence
+        .warm_start(&registry, &["fs".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(hydrated, 1);
+    let cached = registry.get(&read.id).unwrap();
+    assert!(registry.is_stale(&cached));
+    assert_eq!(
+        registry.get_server("fs").unwrap().health,
+        ServerHealth::Stale
+    );
+    assert_eq!(registry.stats().stale_servers, vec!["fs".to_string()]);
+
+    // Reconnecting clears the stale flag
+    registry.update_server(ServerInfo {
+        alias: "fs".to_string(),
+        health: ServerHealth::Connected,
+        tool_count: 1,
+        last_refresh: chrono::Utc::now(),
+        tags: vec![],
+    });
+    assert!(!registry.is_stale(&cached));
+}
+
+#[tokio::test]
+async fn test_registry_replace_server_upserts_before_removing() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let persistence = std::sync::Arc::new(PersistenceLayer::new(temp_file.path()).await.unwrap());
+
+    let registry = Registry::new();
+    let sync = spawn_registry_sync(persistence.clone(), &registry);
+    let read = create_test_tool_record("read", "fs");
+    let write = create_test_tool_record("write", "fs");
+    let fetch = create_test_tool_record("fetch", "web");
+    registry
+        .register_batch(vec![read.clone(), write.clone(), fetch.clone()])
+        .unwrap();
+
+    // A refresh that dies partway has only ever added to the cache
+    let mut events = registry.subscribe();
+    let list = create_test_tool_record("list", "fs");
+    let removed = registry.replace_server("fs", vec![read.clone(), list.clone()]);
+    assert_eq!(removed, 1);
+
+    let mut seen = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        seen.push(match event {
+            RegistryEvent::Upserted(record) => format!("+{}", record.fq_name),
+            RegistryEvent::Removed(id) => format!("-{}", id),
+        });
+    }
+    assert_eq!(
+        seen,
+        [
+            "+fs.read".to_string(),
+            "+fs.list".to_string(),
+            format!("-{}", write.id)
+        ]
+    );
+
+    let mut names: Vec<_> = registry
+        .get_by_server("fs")
+        .into_iter()
+        .map(|r| r.name)
+        .collect();
+    names.sort();
+    assert_eq!(names, ["list", "read"]);
+    assert!(registry.get(&fetch.id).is_some());
+
+    drop(registry);
+    drop(events);
+    sync.await.unwrap();
+    let mut cached: Vec<_> = persistence
+        .load_all_callables()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.fq_name)
+        .collect();
+    cached.sort();
+    assert_eq!(cached, ["fs.list", "fs.read", "web.fetch"]);
+}
+
 // Helper functions
 
 fn create_test_tool_record(name: &str, server: &str) -> CallableRecord {
@@ -263,6 +707,7 @@ fn create_test_tool_record(name: &str, server: &str) -> CallableRecord {
         additional_files: vec![],
         cost_hints: CostHints::default(),
         risk_tier: RiskTier::ReadOnly,
+        annotations: None,
         last_seen: chrono::Utc::now(),
         sandbox_config: None,
     }
@@ -293,7 +738,552 @@ fn create_test_skill_record(name: &str) -> CallableRecord {
         additional_files: vec![],
         cost_hints: CostHints::default(),
         risk_tier: RiskTier::ReadOnly,
+        annotations: None,
         last_seen: chrono::Utc::now(),
         sandbox_config: None,
     }
 }
+
+#[tokio::test]
+async fn test_consent_token_lifecycle() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let persistence = std::sync::Arc::new(PersistenceLayer::new(temp_file.path()).await.unwrap());
+    let consent = ConsentManager::new(persistence.clone()).await.unwrap();
+
+    let mut record = create_test_tool_record("delete_file", "fs");
+    record.risk_tier = RiskTier::Destructive;
+    let args = serde_json::json!({"path": "/tmp/a", "force": true});
+
+    let request = consent
+        .request(&record, &args, ConsentLevel::UserConfirmed, "destructive")
+        .await
+        .unwrap();
+    assert_eq!(request.status, ConsentStatus::Pending);
+
+    // Retrying the same call reuses the pending request
+    let reordered = serde_json::json!({"force": true, "path": "/tmp/a"});
+    let again = consent
+        .request(
+            &record,
+            &reordered,
+            ConsentLevel::UserConfirmed,
+            "destructive",
+        )
+        .await
+        .unwrap();
+    assert_eq!(again.id, request.id);
+    assert_eq!(consent.list_pending().await.unwrap().len(), 1);
+
+    let grant = consent.approve(&request.id).await.unwrap();
+    assert_eq!(grant.level, ConsentLevel::UserConfirmed);
+    assert!(matches!(
+        consent.approve(&request.id).await,
+        Err(ConsentError::InvalidState { .. })
+    ));
+
+    // Bound to the callable, the arguments, and the signature
+    let other = create_test_tool_record("write_file", "fs");
+    assert!(matches!(
+        consent.verify(&grant.token, &other.id, &args).await,
+        Err(ConsentError::Mismatch(_))
+    ));
+    let changed = serde_json::json!({"path": "/etc/passwd", "force": true});
+    assert!(matches!(
+        consent.verify(&grant.token, &record.id, &changed).await,
+        Err(ConsentError::Mismatch(_))
+    ));
+    let mut forged = grant.token.clone();
+    forged.replace_range(forged.len() - 4.., "0000");
+    assert!(matches!(
+        consent.verify(&forged, &record.id, &args).await,
+        Err(ConsentError::InvalidToken(_))
+    ));
+
+    // A restarted manager shares the signing key; tokens are single-use
+    let restarted = ConsentManager::new(persistence).await.unwrap();
+    assert_eq!(
+        restarted
+            .consume(&grant.token, &record.id, &reordered)
+            .await
+            .unwrap(),
+        ConsentLevel::UserConfirmed
+    );
+    assert!(matches!(
+        consent.consume(&grant.token, &record.id, &args).await,
+        Err(ConsentError::InvalidState {
+            status: ConsentStatus::Consumed,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn test_consent_expiry_and_denial() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let persistence = std::sync::Arc::new(PersistenceLayer::new(temp_file.path()).await.unwrap());
+    let consent = ConsentManager::new(persistence)
+        .await
+        .unwrap()
+        .with_ttl_secs(0);
+
+    let record = create_test_tool_record("drop_table", "db");
+    let args = serde_json::json!({});
+    let request = consent
+        .request(&record, &args, ConsentLevel::AdminConfirmed, "admin")
+        .await
+        .unwrap();
+    assert!(consent.list_pending().await.unwrap().is_empty());
+    assert!(matches!(
+        consent.approve(&request.id).await,
+        Err(ConsentError::Expired(_))
+    ));
+
+    let denied = consent
+        .request(
+            &record,
+            &serde_json::json!({"table": "users"}),
+            ConsentLevel::AdminConfirmed,
+            "admin",
+        )
+        .await
+        .unwrap();
+    consent.deny(&denied.id).await.unwrap();
+    assert_eq!(
+        consent.get(&denied.id).await.unwrap().status,
+        ConsentStatus::Denied
+    );
+    assert!(matches!(
+        consent.get("missing").await,
+        Err(ConsentError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_policy_engine_rate_limits_and_quotas() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let persistence = std::sync::Arc::new(PersistenceLayer::new(temp_file.path()).await.unwrap());
+    let config: PolicyConfig = serde_yaml::from_str(
+        r#"
+rate_limits:
+  - scope: server
+    match: "paid"
+    rate: 2
+    per: 1h
+  - scope: session
+    quota: 3
+    window: 24h
+"#,
+    )
+    .unwrap();
+
+    let engine = PolicyEngine::new(config.clone())
+        .unwrap()
+        .with_persistence(persistence.clone());
+    let paid = create_test_tool_record("search", "paid");
+    let free = create_test_tool_record("search", "free");
+
+    // Token bucket per server
+    engine.check_rate_limits(&paid, "a").await.unwrap();
+    engine.check_rate_limits(&paid, "b").await.unwrap();
+    let err = engine.check_rate_limits(&paid, "c").await.unwrap_err();
+    assert!(matches!(err, PolicyError::LimitExceeded(ref msg) if msg.contains("server paid")));
+
+    // Rolling quota per session (session "a" already has one call)
+    engine.check_rate_limits(&free, "a").await.unwrap();
+    engine.check_rate_limits(&free, "a").await.unwrap();
+    assert!(engine.check_rate_limits(&free, "a").await.is_err());
+    engine.check_rate_limits(&free, "c").await.unwrap();
+
+    // Counters survive a restart
+    let restarted = PolicyEngine::new(config)
+        .unwrap()
+        .with_persistence(persistence);
+    assert!(restarted.restore_usage().await.unwrap() > 0);
+    assert!(restarted.check_rate_limits(&paid, "d").await.is_err());
+    assert!(restarted.check_rate_limits(&free, "a").await.is_err());
+    restarted.check_rate_limits(&free, "b").await.unwrap();
+
+    let invalid: PolicyConfig =
+        serde_yaml::from_str("rate_limits:\n  - scope: tag\n    quota: 5\n").unwrap();
+    assert!(matches!(
+        PolicyEngine::new(invalid),
+        Err(PolicyError::ConfigError(msg)) if msg.contains("quota requires window")
+    ));
+}
+
+#[tokio::test]
+async fn test_rate_limit_counters_persist_in_order() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let persistence = std::sync::Arc::new(PersistenceLayer::new(temp_file.path()).await.unwrap());
+    let config: PolicyConfig = serde_yaml::from_str(
+        r#"
+rate_limits:
+  - scope: tag
+    rate: 30
+    per: 24h
+"#,
+    )
+    .unwrap();
+    let engine = std::sync::Arc::new(
+        PolicyEngine::new(config.clone())
+            .unwrap()
+            .with_persistence(persistence.clone()),
+    );
+
+    // A repeated tag is one counter, charged once per call
+    let mut tool = create_test_tool_record("search", "paid");
+    tool.tags = vec!["paid".to_string(), "paid".to_string()];
+
+    let calls: Vec<_> = (0..20)
+        .map(|_| {
+            let engine = engine.clone();
+            let tool = tool.clone();
+            tokio::spawn(async move { engine.check_rate_limits(&tool, "a").await })
+        })
+        .collect();
+    for call in calls {
+        call.await.unwrap().unwrap();
+    }
+
+    // The persisted bucket is the newest one: exactly 10 calls remain
+    let restarted = PolicyEngine::new(config)
+        .unwrap()
+        .with_persistence(persistence);
+    restarted.restore_usage().await.unwrap();
+    for _ in 0..10 {
+        restarted.check_rate_limits(&tool, "a").await.unwrap();
+    }
+    assert!(restarted.check_rate_limits(&tool, "a").await.is_err());
+}
+
+#[test]
+fn test_schema_budget_reductions() {
+    use skillsrs::core::budget::{estimate_tokens, SchemaReduction};
+
+    let colors: Vec<String> = (0..20).map(|i| format!("color-{}", i)).collect();
+    let original = serde_json::json!({
+        "type": "object",
+        "description": "Create a chart",
+        "properties": {
+            "title": {"type": "string", "description": "Chart title"},
+            "color": {"type": "string", "enum": colors},
+            "series": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "label": {"type": "string"},
+                        "style": {"type": "object", "properties": {"dash": {"type": "boolean"}}}
+                    }
+                }
+            }
+        }
+    });
+
+    let plan = SchemaReduction::plan(&original);
+    assert_eq!(
+        plan,
+        vec![
+            SchemaReduction::Descriptions,
+            SchemaReduction::Enums,
+            SchemaReduction::Collapse(3),
+            SchemaReduction::Collapse(2),
+            SchemaReduction::Collapse(1),
+        ]
+    );
+
+    let mut schema = original.clone();
+    let mut sizes = vec![estimate_tokens(&schema)];
+    let notes: Vec<String> = plan
+        .iter()
+        .filter_map(|step| {
+            let note = step.apply(&mut schema, "");
+            sizes.push(estimate_tokens(&schema));
+            note
+        })
+        .collect();
+    assert!(sizes.windows(2).all(|w| w[1] <= w[0]), "{:?}", sizes);
+    assert!(sizes[sizes.len() - 1] < sizes[0] / 2, "{:?}", sizes);
+    assert_eq!(notes[0], "2 descriptions");
+    assert!(notes[1].starts_with("1 enums truncated to 5 values"));
+
+    assert_eq!(
+        schema["properties"]["color"]["enum"]
+            .as_array()
+            .unwrap()
+            .len(),
+        5
+    );
+    assert_eq!(schema["properties"]["color"]["x-enum-total"], 20);
+    assert_eq!(
+        schema["properties"]["title"],
+        serde_json::json!({"type": "string"})
+    );
+
+    // Collapsed schemas are handles into the original, fetchable by JSON pointer
+    let handle = schema["properties"]["series"]["$ref"].as_str().unwrap();
+    assert_eq!(handle, "#/properties/series");
+    assert_eq!(
+        original.pointer(&handle[1..]),
+        Some(&original["properties"]["series"])
+    );
+
+    // Handles below a json_pointer subtree stay relative to the whole schema
+    let mut subtree = original["properties"]["series"].clone();
+    SchemaReduction::Collapse(1).apply(&mut subtree, "/properties/series");
+    assert_eq!(subtree["items"]["$ref"], "#/properties/series/items");
+}
+
+#[test]
+fn test_schema_renderings() {
+    use skillsrs::core::render::{Rendering, SchemaFormat};
+    use skillsrs::core::CallableSignature;
+
+    let input = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "CreateIssue",
+        "type": "object",
+        "required": ["repo", "title"],
+        "properties": {
+            "repo": {"type": "string", "description": "owner/name"},
+            "title": {"type": "string", "maxLength": 256},
+            "state": {"enum": ["open", "closed"], "default": "open"},
+            "assignee": {"$ref": "#/$defs/user"},
+            "tasks": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {"name": {"type": "string"}, "done": {"type": "boolean"}}
+                }
+            },
+            "node": {"$ref": "#/$defs/node"}
+        },
+        "$defs": {
+            "user": {"type": "object", "title": "User", "required": ["login"], "properties": {"login": {"type": "string"}}},
+            "node": {"type": "object", "properties": {"next": {"$ref": "#/$defs/node"}}}
+        }
+    });
+    let output =
+        serde_json::json!({"type": "object", "properties": {"number": {"type": "integer"}}});
+
+    let ts = Rendering::new(
+        SchemaFormat::TypeScript,
+        &input,
+        Some(&output),
+        "create_issue",
+    )
+    .unwrap();
+    assert_eq!(ts.bytes, ts.text.len());
+    assert!(ts.tokens > 0);
+    assert!(ts.text.contains("interface CreateIssueInput {"));
+    assert!(ts.text.contains("  repo: string;"));
+    assert!(ts.text.contains("  state?: \"open\" | \"closed\";"));
+    assert!(ts.text.contains("  tasks?: Array<{"));
+    assert!(ts.text.contains("interface CreateIssueOutput {"));
+
+    let outline = Rendering::new(SchemaFormat::Outline, &input, None, "create_issue").unwrap();
+    assert!(outline.text.contains("repo*: string"));
+    assert!(outline
+        .text
+        .contains("tasks: array of object\n  name*: string\n  done: boolean"));
+    assert!(!outline.text.contains("returns:"));
+
+    // Minimal drops annotations and inlines refs, keeping only recursive definitions
+    let minimal = Rendering::new(SchemaFormat::Minimal, &input, None, "create_issue").unwrap();
+    let minimal: serde_json::Value = serde_json::from_str(&minimal.text).unwrap();
+    assert!(minimal.get("$schema").is_none());
+    assert!(minimal.get("title").is_none());
+    assert_eq!(minimal["properties"]["assignee"]["required"][0], "login");
+    assert!(minimal["$defs"].get("user").is_none());
+    assert!(minimal["$defs"].get("node").is_some());
+
+    assert!(Rendering::new(SchemaFormat::JsonSchema, &input, None, "create_issue").is_none());
+    assert_eq!(
+        "ts".parse::<SchemaFormat>().unwrap(),
+        SchemaFormat::TypeScript
+    );
+    assert!("xml".parse::<SchemaFormat>().is_err());
+
+    // Signatures describe nested fields and stop at recursive refs
+    let signature = CallableSignature::from_schema(&input);
+    assert!(signature.required.contains(&"assignee.login".to_string()));
+    assert!(signature.required.contains(&"tasks[].name".to_string()));
+    assert!(signature.optional.contains(&"tasks[].done".to_string()));
+    assert!(signature.optional.contains(&"node.next".to_string()));
+    assert!(!signature
+        .optional
+        .iter()
+        .any(|p| p.starts_with("node.next.")));
+}
+
+#[tokio::test]
+async fn test_example_arguments() {
+    use skillsrs::core::examples;
+    use skillsrs::core::CallableSignature;
+
+    let schema = serde_json::json!({
+        "type": "object",
+        "required": ["path", "mode", "count", "when", "tags"],
+        "properties": {
+            "path": {"type": "string", "minLength": 6},
+            "mode": {"enum": ["read", "write"]},
+            "count": {"type": "integer", "exclusiveMinimum": 2, "multipleOf": 5},
+            "ratio": {"type": "number", "maximum": 0.5},
+            "when": {"type": "string", "format": "date-time"},
+            "code": {"type": "string", "pattern": "^[A-Z]$"},
+            "tags": {"type": "array", "items": {"type": "string"}, "minItems": 2},
+            "owner": {"$ref": "#/$defs/user"},
+            "verbose": {"type": "boolean", "default": false}
+        },
+        "$defs": {
+            "user": {"type": "object", "required": ["email"], "properties": {"email": {"type": "string", "format": "email"}}}
+        }
+    });
+
+    let synthesized = examples::synthesize(&schema);
+    assert_eq!(synthesized.len(), 2);
+    for example in &synthesized {
+        assert!(validation::is_valid(&schema, example), "{}", example);
+    }
+    let minimal = &synthesized[0];
+    assert_eq!(minimal["path"], "pathxx");
+    assert_eq!(minimal["mode"], "read");
+    assert_eq!(minimal["count"], 5);
+    assert_eq!(minimal["tags"], serde_json::json!(["tags", "tags"]));
+    assert!(minimal.get("ratio").is_none());
+    let full = &synthesized[1];
+    assert_eq!(full["code"], "A");
+    assert_eq!(full["owner"]["email"], "user@example.com");
+    assert_eq!(full["verbose"], false);
+
+    // Declared examples win; otherwise signatures carry synthesized ones
+    assert_eq!(
+        CallableSignature::from_schema(&schema).examples,
+        synthesized
+    );
+    let declared = serde_json::json!({"type": "object", "examples": [{"q": "x"}]});
+    assert_eq!(
+        CallableSignature::from_schema(&declared).examples,
+        [serde_json::json!({"q": "x"})]
+    );
+
+    // Past arguments are used only if successful, unredacted, and still valid
+    let temp_file = NamedTempFile::new().unwrap();
+    let persistence = PersistenceLayer::new(temp_file.path()).await.unwrap();
+    let id = CallableId::tool("fs", "open", "abc12345");
+    let mut good = minimal.clone();
+    good["path"] = serde_json::json!("/etc/hosts");
+    let mut secret = good.clone();
+    secret["owner"] = serde_json::json!({"email": "a@b.co", "token": "t"});
+    let now = chrono::Utc::now();
+    let calls = [
+        (good.clone(), false),
+        (serde_json::json!({"path": "/tmp/x"}), false),
+        (redact_arguments(&secret), false),
+        (good.clone(), true),
+    ];
+    for (i, (arguments, is_error)) in calls.iter().enumerate() {
+        let started_at = now + chrono::Duration::seconds(i as i64);
+        persistence
+            .record_execution(
+                &format!("exec-{}", i),
+                &id,
+                arguments,
+                None,
+                *is_error,
+                Some(5),
+                started_at,
+                Some(started_at),
+                None,
+            )
+            .await
+            .unwrap();
+    }
+    let records = persistence.get_execution_history(&id, 20).await.unwrap();
+    let history = examples::from_history(&schema, &records, examples::MAX_EXAMPLES);
+    assert_eq!(history, [good.clone()]);
+    assert_eq!(
+        examples::merge(history, &synthesized),
+        [good, synthesized[0].clone()]
+    );
+}
+
+#[tokio::test]
+async fn test_tool_annotations() {
+    use skillsrs::core::ToolAnnotations;
+    use skillsrs::execution::upstream::UpstreamConfig;
+
+    let hints = |read_only: Option<bool>, destructive: Option<bool>| ToolAnnotations {
+        read_only_hint: read_only,
+        destructive_hint: destructive,
+        ..ToolAnnotations::default()
+    };
+    assert_eq!(
+        hints(Some(true), Some(true)).risk_tier(),
+        RiskTier::ReadOnly
+    );
+    assert_eq!(
+        hints(Some(false), Some(false)).risk_tier(),
+        RiskTier::Writes
+    );
+    assert_eq!(hints(Some(false), None).risk_tier(), RiskTier::Destructive);
+    assert_eq!(hints(None, Some(true)).risk_tier(), RiskTier::Destructive);
+    assert_eq!(hints(None, None).risk_tier(), RiskTier::Unknown);
+    assert!(hints(None, None).open_world());
+
+    // Overrides for `*` apply first, then per tool; unset hints keep the server's
+    let config: UpstreamConfig = serde_yaml::from_str(
+        r#"
+alias: files
+transport: stdio
+command: ["files-server"]
+tags: []
+annotations:
+  "*": { openWorldHint: false }
+  delete_file: { readOnlyHint: false, destructiveHint: true }
+"#,
+    )
+    .unwrap();
+    let annotations = config
+        .tool_annotations("delete_file", Some(hints(Some(true), None)))
+        .unwrap();
+    assert_eq!(annotations.risk_tier(), RiskTier::Destructive);
+    assert_eq!(annotations.open_world_hint, Some(false));
+    let annotations = config.tool_annotations("read_file", None).unwrap();
+    assert_eq!(annotations.risk_tier(), RiskTier::Unknown);
+    assert!(!annotations.open_world());
+    let unconfigured: UpstreamConfig =
+        serde_yaml::from_str("alias: web\ntransport: http\nurl: http://localhost\ntags: []")
+            .unwrap();
+    assert_eq!(unconfigured.tool_annotations("fetch", None), None);
+
+    // Databases from before annotations were stored gain the column
+    let temp_file = NamedTempFile::new().unwrap();
+    let options = sqlx::sqlite::SqliteConnectOptions::new().filename(temp_file.path());
+    let pool = sqlx::SqlitePool::connect_with(options).await.unwrap();
+    sqlx::query(
+        "CREATE TABLE callables (
+            id TEXT PRIMARY KEY, kind TEXT NOT NULL, fq_name TEXT NOT NULL, name TEXT NOT NULL,
+            title TEXT, description TEXT, tags TEXT NOT NULL, input_schema TEXT NOT NULL,
+            output_schema TEXT, schema_digest TEXT NOT NULL, server_alias TEXT,
+            upstream_tool_name TEXT, skill_version TEXT, uses_tools TEXT NOT NULL,
+            skill_directory TEXT, bundled_tools TEXT NOT NULL, additional_files TEXT NOT NULL,
+            cost_hints TEXT NOT NULL, risk_tier TEXT NOT NULL, last_seen INTEGER NOT NULL,
+            created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL
+        )",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    pool.close().await;
+
+    let persistence = PersistenceLayer::new(temp_file.path()).await.unwrap();
+    let mut record = create_test_tool_record("delete_file", "files");
+    record.annotations = Some(annotations.clone());
+    record.output_schema = Some(serde_json::json!({"type": "object"}));
+    persistence.save_callable(&record).await.unwrap();
+    let loaded = persistence.load_callable(&record.id).await.unwrap();
+    assert_eq!(loaded.annotations, Some(annotations));
+    assert_eq!(loaded.output_schema, record.output_schema);
+}
@@ -5,8 +5,10 @@ use skillsrs::core::{CallableId, CallableKind, CallableRecord, CostHints, RiskTi
 use skillsrs::execution::upstream::{Transport, UpstreamConfig, UpstreamManager};
 use skillsrs::execution::{
     sandbox::{Sandbox, SandboxBackend, SandboxConfig},
-    ExecContext, Runtime,
+    workflow::{CallableExecutor, WorkflowDefinition, WorkflowEngine},
+    ExecContext, Runtime, RuntimeError,
 };
+use skillsrs::storage::{EntrypointType, SkillStore, ToolPolicy};
 use std::sync::Arc;
 
 fn create_test_tool_record(name: &str, server: &str) -> CallableRecord {
@@ -40,6 +42,7 @@ fn create_test_tool_record(name: &str, server: &str) -> CallableRecord {
         additional_files: vec![],
         cost_hints: CostHints::default(),
         risk_tier: RiskTier::ReadOnly,
+        annotations: None,
         last_seen: chrono::Utc::now(),
         sandbox_config: None,
     }
@@ -72,6 +75,9 @@ async fn test_add_upstream_stdio() {
         tags: vec!["test".to_string()],
         sandbox_config: None,
         description: None,
+        annotations: Default::default(),
+        restart: Default::default(),
+        max_concurrency: None,
     };
 
     // This will fail because echo is not a valid MCP server
@@ -140,12 +146,13 @@ async fn test_validation_missing_required() {
         arguments: serde_json::json!({}), // Missing 'path'
         timeout_ms: Some(5000),
         trace_enabled: false,
+        session: None,
     };
 
     let result = runtime.execute(ctx).await;
     assert!(result.is_err());
     let err = result.unwrap_err();
-    assert!(err.to_string().contains("Missing required") || err.to_string().contains("validation"));
+    assert!(err.to_string().contains("/path: missing required property"));
 }
 
 #[tokio::test]
@@ -159,6 +166,7 @@ async fn test_callable_not_found() {
         arguments: serde_json::json!({}),
         timeout_ms: Some(5000),
         trace_enabled: false,
+        session: None,
     };
 
     let result = runtime.execute(ctx).await;
@@ -166,3 +174,1220 @@ async fn test_callable_not_found() {
     let err = result.unwrap_err();
     assert!(err.to_string().contains("not found"));
 }
+
+#[test]
+fn test_sse_decoder_handles_split_chunks() {
+    use skillsrs::execution::http::SseDecoder;
+
+    let mut decoder = SseDecoder::new();
+    let mut events = decoder.feed(b": keep-alive\r\nevent: endpoint\r\ndata: /messages");
+    assert!(events.is_empty());
+
+    events.extend(decoder.feed(b"?session=1\r\n\r\ndata: {\"a\":\ndata: 1}\n\n"));
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].event_type(), "endpoint");
+    assert_eq!(events[0].data, "/messages?session=1");
+    assert_eq!(events[1].event_type(), "message");
+    assert_eq!(events[1].data, "{\"a\":\n1}");
+}
+
+/// Minimal Streamable HTTP MCP server used to exercise the upstream client
+mod streamable_http_mock {
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    pub struct MockState {
+        pub initializations: AtomicUsize,
+        pub valid_session: Mutex<Option<String>>,
+    }
+
+    async fn handle(
+        State(state): State<Arc<MockState>>,
+        headers: HeaderMap,
+        Json(message): Json<Value>,
+    ) -> Response {
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+
+        if method == "initialize" {
+            let n = state.initializations.fetch_add(1, Ordering::SeqCst) + 1;
+            let session = format!("session-{}", n);
+            *state.valid_session.lock().unwrap() = Some(session.clone());
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": message["id"],
+                "result": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "mock", "version": "1.0" }
+                }
+            });
+            return ([("mcp-session-id", session)], Json(body)).into_response();
+        }
+
+        let session = headers
+            .get("mcp-session-id")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+        if session.is_none() || session != *state.valid_session.lock().unwrap() {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+
+        if message.get("id").is_none() {
+            return StatusCode::ACCEPTED.into_response();
+        }
+
+        match method.as_str() {
+            // Answer tools/list over SSE, with a notification ahead of the response
+            "tools/list" => {
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/message",
+                    "params": { "level": "info", "data": "listing" }
+                });
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "result": {
+                        "tools": [{
+                            "name": "echo",
+                            "title": "Echo",
+                            "description": "Echo the input",
+                            "inputSchema": {
+                                "type": "object",
+                                "properties": { "text": { "type": "string" } }
+                            },
+                            "annotations": { "readOnlyHint": true, "openWorldHint": false }
+                        }]
+                    }
+                });
+                let body = format!(
+                    "event: message\ndata: {}\n\nevent: message\ndata: {}\n\n",
+                    notification, response
+                );
+                ([("content-type", "text/event-stream")], body).into_response()
+            }
+            // A screenshot tool returning an image, a file, and structured content
+            "tools/call" if message["params"]["name"] == "snap" => Json(json!({
+                "jsonrpc": "2.0",
+                "id": message["id"],
+                "result": {
+                    "content": [
+                        { "type": "image", "data": "iVBORw0KGgo=", "mimeType": "image/png" },
+                        {
+                            "type": "resource",
+                            "resource": {
+                                "uri": "file:///tmp/snap.txt",
+                                "mimeType": "text/plain",
+                                "text": "snapshot"
+                            }
+                        }
+                    ],
+                    "structuredContent": { "path": message["params"]["arguments"]["path"], "bytes": 8 }
+                }
+            }))
+            .into_response(),
+            "tools/call" => Json(json!({
+                "jsonrpc": "2.0",
+                "id": message["id"],
+                "result": {
+                    "content": [{
+                        "type": "text",
+                        "text": message["params"]["arguments"]["text"]
+                    }]
+                }
+            }))
+            .into_response(),
+            _ => StatusCode::BAD_REQUEST.into_response(),
+        }
+    }
+
+    pub async fn start() -> (String, Arc<MockState>) {
+        let state = Arc::new(MockState::default());
+        let app = Router::new()
+            .route("/mcp", post(handle).delete(|| async { StatusCode::OK }))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}/mcp", addr), state)
+    }
+}
+
+#[tokio::test]
+async fn test_streamable_http_upstream() {
+    use std::sync::atomic::Ordering;
+
+    let (url, state) = streamable_http_mock::start().await;
+
+    let registry = Arc::new(Registry::new());
+    let manager = UpstreamManager::new(registry.clone());
+
+    let config = UpstreamConfig {
+        alias: "remote".to_string(),
+        transport: Transport::Http,
+        command: None,
+        url: Some(url),
+        auth: None,
+        repo: None,
+        git_ref: None,
+        skills: None,
+        roots: None,
+        tags: vec![],
+        sandbox_config: None,
+        description: None,
+        annotations: Default::default(),
+        restart: Default::default(),
+        max_concurrency: None,
+    };
+
+    manager.add_upstream(config).await.unwrap();
+
+    // tools/list arrived over SSE, behind an unrelated notification
+    let tools = registry.get_by_server("remote");
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].fq_name, "remote.echo");
+    assert_eq!(tools[0].title.as_deref(), Some("Echo"));
+    assert_eq!(tools[0].risk_tier, RiskTier::ReadOnly);
+    assert!(!tools[0].cost_hints.network_required);
+
+    let result = manager
+        .call_tool("remote", "echo", serde_json::json!({ "text": "hi" }))
+        .await
+        .unwrap();
+    assert_eq!(result["content"][0]["text"], "hi");
+    assert_eq!(state.initializations.load(Ordering::SeqCst), 1);
+
+    // Expire the session: the client must re-initialize and retry
+    *state.valid_session.lock().unwrap() = None;
+    let result = manager
+        .call_tool("remote", "echo", serde_json::json!({ "text": "again" }))
+        .await
+        .unwrap();
+    assert_eq!(result["content"][0]["text"], "again");
+    assert_eq!(state.initializations.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_tool_results_keep_content_and_structured_output() {
+    use skillsrs::core::ToolResultContent;
+
+    let (url, _state) = streamable_http_mock::start().await;
+    let registry = Arc::new(Registry::new());
+    let manager = Arc::new(UpstreamManager::new(registry.clone()));
+    manager
+        .add_upstream(UpstreamConfig {
+            alias: "remote".to_string(),
+            transport: Transport::Http,
+            command: None,
+            url: Some(url),
+            auth: None,
+            repo: None,
+            git_ref: None,
+            skills: None,
+            roots: None,
+            tags: vec![],
+            sandbox_config: None,
+            description: None,
+            annotations: Default::default(),
+            restart: Default::default(),
+            max_concurrency: None,
+        })
+        .await
+        .unwrap();
+
+    let mut record = create_test_tool_record("snap", "remote");
+    record.output_schema = Some(serde_json::json!({
+        "type": "object",
+        "required": ["path", "bytes"],
+        "properties": {
+            "path": { "type": "string", "pattern": "^/" },
+            "bytes": { "type": "integer" }
+        }
+    }));
+    let id = record.id.clone();
+    registry.register(record).unwrap();
+    let runtime = Runtime::new(registry, manager);
+
+    let exec = |path: &str| ExecContext {
+        callable_id: id.clone(),
+        arguments: serde_json::json!({ "path": path }),
+        timeout_ms: None,
+        trace_enabled: false,
+        session: None,
+    };
+
+    let result = runtime.execute(exec("/snap.png")).await.unwrap();
+    assert!(!result.is_error);
+    assert!(matches!(
+        &result.content[0],
+        ToolResultContent::Image { data, mime_type } if data == "iVBORw0KGgo=" && mime_type == "image/png"
+    ));
+    match &result.content[1] {
+        ToolResultContent::Resource { resource } => {
+            assert_eq!(resource.uri, "file:///tmp/snap.txt");
+            assert_eq!(resource.mime_type.as_deref(), Some("text/plain"));
+            assert_eq!(resource.text.as_deref(), Some("snapshot"));
+        }
+        other => panic!("expected a resource, got {:?}", other),
+    }
+    assert_eq!(
+        result.structured_content,
+        Some(serde_json::json!({ "path": "/snap.png", "bytes": 8 }))
+    );
+
+    // Structured content that breaks the output schema makes an error result
+    let result = runtime.execute(exec("snap.png")).await.unwrap();
+    assert!(result.is_error);
+    match &result.content[0] {
+        ToolResultContent::Text { text } => {
+            assert!(
+                text.starts_with("Output schema validation failed: /path"),
+                "{}",
+                text
+            )
+        }
+        other => panic!("expected a text item, got {:?}", other),
+    }
+    assert_eq!(result.content.len(), 3);
+}
+
+/// Minimal legacy HTTP+SSE MCP server: responses travel over the GET stream
+mod legacy_sse_mock {
+    use axum::body::Body;
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+
+    #[derive(Default)]
+    pub struct MockState {
+        pub stream: Mutex<Option<mpsc::UnboundedSender<String>>>,
+        pub received: Mutex<Vec<Value>>,
+    }
+
+    impl MockState {
+        fn push(&self, event: &str, data: &str) {
+            if let Some(tx) = self.stream.lock().unwrap().as_ref() {
+                let _ = tx.send(format!("event: {}\ndata: {}\n\n", event, data));
+            }
+        }
+    }
+
+    async fn events(State(state): State<Arc<MockState>>) -> Response {
+        let (tx, rx) = mpsc::unbounded_channel::<String>();
+        let _ = tx.send("event: endpoint\ndata: /messages?session=abc\n\n".to_string());
+        *state.stream.lock().unwrap() = Some(tx);
+
+        let stream = futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv()
+                .await
+                .map(|chunk| (Ok::<_, std::convert::Infallible>(chunk), rx))
+        });
+        (
+            [("content-type", "text/event-stream")],
+            Body::from_stream(stream),
+        )
+            .into_response()
+    }
+
+    async fn messages(
+        State(state): State<Arc<MockState>>,
+        Json(message): Json<Value>,
+    ) -> StatusCode {
+        state.received.lock().unwrap().push(message.clone());
+
+        let id = message["id"].clone();
+        let result = match message["method"].as_str() {
+            Some("initialize") => {
+                // Ping the client before answering; it must reply on its own
+                state.push(
+                    "message",
+                    &json!({"jsonrpc": "2.0", "id": "srv-1", "method": "ping"}).to_string(),
+                );
+                json!({
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "legacy", "version": "1.0" }
+                })
+            }
+            Some("tools/list") => json!({
+                "tools": [{ "name": "legacy_tool", "inputSchema": { "type": "object" } }]
+            }),
+            Some("tools/call") => json!({
+                "content": [{ "type": "text", "text": "called" }]
+            }),
+            _ => return StatusCode::ACCEPTED,
+        };
+
+        state.push(
+            "message",
+            &json!({"jsonrpc": "2.0", "method": "notifications/progress", "params": {}})
+                .to_string(),
+        );
+        state.push(
+            "message",
+            &json!({"jsonrpc": "2.0", "id": id, "result": result}).to_string(),
+        );
+        if message["params"]["arguments"]["slow"] == true {
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        }
+        StatusCode::ACCEPTED
+    }
+
+    pub async fn start() -> (String, Arc<MockState>) {
+        let state = Arc::new(MockState::default());
+        let app = Router::new()
+            .route("/sse", get(events))
+            .route("/messages", post(messages))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}/sse", addr), state)
+    }
+}
+
+#[tokio::test]
+async fn test_legacy_sse_upstream() {
+    let (url, state) = legacy_sse_mock::start().await;
+
+    let registry = Arc::new(Registry::new());
+    let manager = UpstreamManager::new(registry.clone());
+
+    let config = UpstreamConfig {
+        alias: "legacy".to_string(),
+        transport: Transport::HttpSse,
+        command: None,
+        url: Some(url),
+        auth: None,
+        repo: None,
+        git_ref: None,
+        skills: None,
+        roots: None,
+        tags: vec![],
+        sandbox_config: None,
+        description: None,
+        annotations: Default::default(),
+        restart: Default::default(),
+        max_concurrency: None,
+    };
+
+    manager.add_upstream(config).await.unwrap();
+
+    let tools = registry.get_by_server("legacy");
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].name, "legacy_tool");
+
+    let result = manager
+        .call_tool("legacy", "legacy_tool", serde_json::json!({}))
+        .await
+        .unwrap();
+    assert_eq!(result["content"][0]["text"], "called");
+
+    // The client answered the server's ping through the message endpoint
+    let received = state.received.lock().unwrap();
+    assert!(received
+        .iter()
+        .any(|m| m["id"] == "srv-1" && m.get("result").is_some()));
+    assert!(received
+        .iter()
+        .any(|m| m["method"] == "notifications/initialized" && m.get("id").is_none()));
+
+    // The handshake offers the same protocol version as Streamable HTTP
+    let initialize = received
+        .iter()
+        .find(|m| m["method"] == "initialize")
+        .unwrap();
+    assert_eq!(
+        initialize["params"]["protocolVersion"],
+        skillsrs::execution::upstream::PROTOCOL_VERSION
+    );
+    assert_eq!(
+        initialize["params"]["clientInfo"]["version"],
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+#[tokio::test]
+async fn test_legacy_sse_routes_responses_during_slow_posts() {
+    let (url, _state) = legacy_sse_mock::start().await;
+
+    let registry = Arc::new(Registry::new());
+    let manager = UpstreamManager::new(registry);
+    let config = UpstreamConfig {
+        alias: "legacy".to_string(),
+        transport: Transport::HttpSse,
+        command: None,
+        url: Some(url),
+        auth: None,
+        repo: None,
+        git_ref: None,
+        skills: None,
+        roots: None,
+        tags: vec![],
+        sandbox_config: None,
+        description: None,
+        annotations: Default::default(),
+        restart: Default::default(),
+        max_concurrency: None,
+    };
+    manager.add_upstream(config).await.unwrap();
+
+    // The response arrives on the event stream long before its POST completes
+    let started = std::time::Instant::now();
+    let result = manager
+        .call_tool("legacy", "legacy_tool", serde_json::json!({"slow": true}))
+        .await
+        .unwrap();
+    assert_eq!(result["content"][0]["text"], "called");
+    assert!(
+        started.elapsed() < std::time::Duration::from_secs(1),
+        "{:?}",
+        started.elapsed()
+    );
+}
+
+/// Executor that serves `fs.read` and fails the first read of `flaky`
+struct MockExecutor {
+    registry: Arc<Registry>,
+    calls: std::sync::Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl CallableExecutor for MockExecutor {
+    async fn execute_callable(
+        &self,
+        ctx: ExecContext,
+    ) -> skillsrs::execution::Result<skillsrs::core::ToolResult> {
+        let record = self.registry.get(&ctx.callable_id).unwrap();
+        let path = ctx.arguments["path"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let mut calls = self.calls.lock().unwrap();
+        let first_attempt = !calls.contains(&path);
+        calls.push(path.clone());
+
+        let is_error = path == "flaky" && first_attempt;
+        Ok(skillsrs::core::ToolResult {
+            content: vec![skillsrs::core::ToolResultContent::Text {
+                text: if is_error {
+                    "temporarily unavailable".to_string()
+                } else {
+                    format!("{} of {}", record.name, path)
+                },
+            }],
+            structured_content: (!is_error)
+                .then(|| serde_json::json!({ "text": format!("content of {}", path) })),
+            is_error,
+        })
+    }
+}
+
+fn workflow_fixture() -> (Arc<Registry>, MockExecutor) {
+    let registry = Arc::new(Registry::new());
+    registry
+        .register(create_test_tool_record("read", "fs"))
+        .unwrap();
+    registry
+        .register(create_test_tool_record("delete", "fs"))
+        .unwrap();
+    let executor = MockExecutor {
+        registry: registry.clone(),
+        calls: std::sync::Mutex::new(Vec::new()),
+    };
+    (registry, executor)
+}
+
+#[tokio::test]
+async fn test_workflow_engine_constructs() {
+    let (registry, executor) = workflow_fixture();
+    let engine = WorkflowEngine::new(registry);
+
+    let workflow = WorkflowDefinition::from_value(&serde_json::json!({
+        "inputs": {
+            "type": "object",
+            "properties": { "paths": { "type": "array", "items": { "type": "string" } } },
+            "required": ["paths"]
+        },
+        "steps": [
+            { "assign": { "files": "{{ inputs.paths }}" } },
+            {
+                "map": {
+                    "over": "{{ files }}",
+                    "item": "p",
+                    "steps": [
+                        { "assign": { "doc": null } },
+                        {
+                            "retry": {
+                                "attempts": 2,
+                                "steps": [
+                                    { "call": "fs.read", "with": { "path": "{{ p }}" }, "as": "doc" }
+                                ]
+                            }
+                        },
+                        { "emit": "{{ doc.text | upper }}" }
+                    ]
+                },
+                "as": "contents"
+            },
+            { "validate": { "value": "{{ contents }}", "schema": { "type": "array", "minItems": 2 } } },
+            {
+                "branch": {
+                    "if": "{{ contents | length >= 2 and not inputs.quiet }}",
+                    "then": [{
+                        "emit": {
+                            "count": "{{ contents | length }}",
+                            "first": "{{ contents[0] }}",
+                            "summary": "read {{ contents | length }} files"
+                        }
+                    }],
+                    "else": [{ "emit": { "count": 0 } }]
+                }
+            }
+        ]
+    }))
+    .unwrap();
+
+    let policy = ToolPolicy {
+        allow: vec!["fs.*".to_string()],
+        deny: vec![],
+        required: vec![],
+    };
+
+    let result = engine
+        .execute(
+            &executor,
+            &workflow,
+            &serde_json::json!({ "paths": ["a", "flaky"] }),
+            &policy,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert!(!result.is_error);
+    assert_eq!(
+        result.structured_content.unwrap(),
+        serde_json::json!({
+            "count": 2,
+            "first": "CONTENT OF A",
+            "summary": "read 2 files"
+        })
+    );
+    assert_eq!(*executor.calls.lock().unwrap(), vec!["a", "flaky", "flaky"]);
+
+    // Workflow inputs are validated before any step runs
+    let err = engine
+        .execute(
+            &executor,
+            &workflow,
+            &serde_json::json!({}),
+            &policy,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, RuntimeError::ValidationFailed(_)));
+}
+
+#[tokio::test]
+async fn test_workflow_engine_enforces_tool_policy() {
+    let (registry, executor) = workflow_fixture();
+    let engine = WorkflowEngine::new(registry);
+
+    let workflow = WorkflowDefinition::from_value(&serde_json::json!({
+        "steps": [{ "call": "fs.delete", "with": { "path": "/etc" } }]
+    }))
+    .unwrap();
+    let policy = ToolPolicy {
+        allow: vec!["fs.*".to_string()],
+        deny: vec!["fs.delete".to_string()],
+        required: vec![],
+    };
+
+    let err = engine
+        .execute(
+            &executor,
+            &workflow,
+            &serde_json::json!({}),
+            &policy,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("denied by the skill's tool policy"));
+    assert!(executor.calls.lock().unwrap().is_empty());
+
+    // Steps must name exactly one construct
+    let invalid = WorkflowDefinition::from_value(&serde_json::json!({
+        "steps": [{ "call": "fs.read", "emit": "x" }]
+    }));
+    assert!(invalid.unwrap_err().contains("exactly one of"));
+}
+
+#[tokio::test]
+async fn test_workflow_calls_are_authorized_with_rendered_arguments() {
+    use skillsrs::core::policy::{PolicyConfig, PolicyEngine};
+
+    let (registry, executor) = workflow_fixture();
+    let config: PolicyConfig = serde_yaml::from_str(
+        r#"
+argument_rules:
+  - callable: "fs.delete"
+    path: /path
+    glob: "/etc/**"
+    action: deny
+"#,
+    )
+    .unwrap();
+    let engine = WorkflowEngine::new(registry)
+        .with_policy_engine(Arc::new(PolicyEngine::new(config).unwrap()));
+
+    let workflow = WorkflowDefinition::from_value(&serde_json::json!({
+        "steps": [{
+            "map": {
+                "over": "{{ inputs.paths }}",
+                "item": "p",
+                "steps": [{ "call": "fs.delete", "with": { "path": "{{ p }}" } }]
+            }
+        }]
+    }))
+    .unwrap();
+    let policy = ToolPolicy {
+        allow: vec!["fs.*".to_string()],
+        deny: vec![],
+        required: vec![],
+    };
+
+    let err = engine
+        .execute(
+            &executor,
+            &workflow,
+            &serde_json::json!({ "paths": ["/tmp/a", "/etc/passwd"] }),
+            &policy,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("fs.delete denied"), "{}", err);
+    assert_eq!(*executor.calls.lock().unwrap(), vec!["/tmp/a"]);
+}
+
+#[tokio::test]
+async fn test_workflow_calls_needing_consent_are_denied() {
+    use skillsrs::core::policy::{PolicyConfig, PolicyEngine};
+
+    let registry = Arc::new(Registry::new());
+    registry
+        .register(create_test_tool_record("read", "fs"))
+        .unwrap();
+    let mut delete = create_test_tool_record("delete", "fs");
+    delete.risk_tier = RiskTier::Destructive;
+    registry.register(delete).unwrap();
+    let executor = MockExecutor {
+        registry: registry.clone(),
+        calls: std::sync::Mutex::new(Vec::new()),
+    };
+
+    let config: PolicyConfig =
+        serde_yaml::from_str("require_consent_for: [writes, destructive]\n").unwrap();
+    let engine = WorkflowEngine::new(registry)
+        .with_policy_engine(Arc::new(PolicyEngine::new(config).unwrap()));
+    let policy = ToolPolicy {
+        allow: vec!["fs.*".to_string()],
+        deny: vec![],
+        required: vec![],
+    };
+
+    // A read-only step runs; a destructive one cannot borrow the skill's consent
+    let workflow = WorkflowDefinition::from_value(&serde_json::json!({
+        "steps": [
+            { "call": "fs.read", "with": { "path": "a" } },
+            { "call": "fs.delete", "with": { "path": "a" } }
+        ]
+    }))
+    .unwrap();
+    let err = engine
+        .execute(
+            &executor,
+            &workflow,
+            &serde_json::json!({}),
+            &policy,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("workflow steps cannot obtain consent"),
+        "{}",
+        err
+    );
+    assert_eq!(*executor.calls.lock().unwrap(), vec!["a"]);
+}
+
+#[tokio::test]
+async fn test_workflow_calls_spend_rate_limits() {
+    use skillsrs::core::policy::{PolicyConfig, PolicyEngine};
+
+    let (registry, executor) = workflow_fixture();
+    let config: PolicyConfig = serde_yaml::from_str(
+        r#"
+rate_limits:
+  - scope: server
+    match: "fs"
+    rate: 3
+    per: 1h
+"#,
+    )
+    .unwrap();
+    let policy_engine = Arc::new(PolicyEngine::new(config).unwrap());
+    let engine = WorkflowEngine::new(registry.clone()).with_policy_engine(policy_engine.clone());
+
+    let workflow = WorkflowDefinition::from_value(&serde_json::json!({
+        "steps": [{
+            "map": {
+                "over": "{{ inputs.paths }}",
+                "item": "p",
+                "steps": [{ "call": "fs.read", "with": { "path": "{{ p }}" } }]
+            }
+        }]
+    }))
+    .unwrap();
+    let policy = ToolPolicy {
+        allow: vec!["fs.*".to_string()],
+        deny: vec![],
+        required: vec![],
+    };
+
+    // The map stops at the limit instead of running every item
+    let err = engine
+        .execute(
+            &executor,
+            &workflow,
+            &serde_json::json!({ "paths": ["a", "b", "c", "d", "e"] }),
+            &policy,
+            Some("agent"),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("fs.read denied"), "{}", err);
+    assert_eq!(*executor.calls.lock().unwrap(), vec!["a", "b", "c"]);
+
+    // The budget is spent for direct calls too
+    let read = registry.get_by_fq_name("fs.read").unwrap();
+    assert!(policy_engine
+        .check_rate_limits(&read, "agent")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_workflow_calls_with_invalid_arguments_spend_no_rate_limits() {
+    use skillsrs::core::policy::{PolicyConfig, PolicyEngine};
+
+    let (registry, executor) = workflow_fixture();
+    let config: PolicyConfig = serde_yaml::from_str(
+        r#"
+rate_limits:
+  - scope: server
+    match: "fs"
+    rate: 1
+    per: 1h
+"#,
+    )
+    .unwrap();
+    let policy_engine = Arc::new(PolicyEngine::new(config).unwrap());
+    let engine = WorkflowEngine::new(registry.clone()).with_policy_engine(policy_engine.clone());
+
+    let workflow = WorkflowDefinition::from_value(&serde_json::json!({
+        "steps": [{ "call": "fs.read", "with": { "path": 7 } }]
+    }))
+    .unwrap();
+    let policy = ToolPolicy {
+        allow: vec!["fs.*".to_string()],
+        deny: vec![],
+        required: vec![],
+    };
+
+    for _ in 0..2 {
+        let err = engine
+            .execute(
+                &executor,
+                &workflow,
+                &serde_json::json!({}),
+                &policy,
+                Some("agent"),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("fs.read failed"), "{}", err);
+    }
+    assert!(executor.calls.lock().unwrap().is_empty());
+
+    let read = registry.get_by_fq_name("fs.read").unwrap();
+    policy_engine
+        .check_rate_limits(&read, "agent")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_runtime_dispatches_workflow_skills() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let skill_dir = temp.path().join("greet-user");
+    std::fs::create_dir(&skill_dir).unwrap();
+    std::fs::write(
+        skill_dir.join("SKILL.md"),
+        "---\nname: greet-user\ndescription: Greets a user by name\n---\n\n# Greet\n",
+    )
+    .unwrap();
+    std::fs::write(
+        skill_dir.join("workflow.yaml"),
+        r#"inputs:
+  type: object
+  properties:
+    name: { type: string }
+  required: [name]
+steps:
+  - assign:
+      greeting: "Hello, {{ inputs.name }}!"
+  - emit:
+      greeting: "{{ greeting }}"
+"#,
+    )
+    .unwrap();
+
+    let registry = Arc::new(Registry::new());
+    let store = SkillStore::new(temp.path(), registry.clone()).unwrap();
+    let skill = store.load_skill(&skill_dir).await.unwrap();
+    assert!(matches!(
+        skill.manifest.entrypoint,
+        EntrypointType::Workflow
+    ));
+    let id = store.register_skill(&skill).unwrap();
+
+    let runtime = Runtime::new(registry.clone(), Arc::new(UpstreamManager::new(registry)));
+    let result = runtime
+        .execute(ExecContext {
+            callable_id: id.clone(),
+            arguments: serde_json::json!({ "name": "Ada" }),
+            timeout_ms: None,
+            trace_enabled: false,
+            session: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        result.structured_content.unwrap(),
+        serde_json::json!({ "greeting": "Hello, Ada!" })
+    );
+
+    // The workflow's input schema becomes the skill's input schema
+    let err = runtime
+        .execute(ExecContext {
+            callable_id: id,
+            arguments: serde_json::json!({}),
+            timeout_ms: None,
+            trace_enabled: false,
+            session: None,
+        })
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("/name: missing required property"));
+}
+
+#[tokio::test]
+async fn test_runtime_records_execution_history() {
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let persistence = Arc::new(
+        skillsrs::core::persistence::PersistenceLayer::new(temp_file.path())
+            .await
+            .unwrap(),
+    );
+
+    let registry = Arc::new(Registry::new());
+    let record = create_test_tool_record("read", "fs");
+    registry.register(record.clone()).unwrap();
+    let runtime = Runtime::new(
+        registry.clone(),
+        Arc::new(UpstreamManager::new(registry.clone())),
+    )
+    .with_persistence(persistence.clone());
+
+    // No upstream is connected, so the call fails but is still recorded
+    let result = runtime
+        .execute(ExecContext {
+            callable_id: record.id.clone(),
+            arguments: serde_json::json!({ "path": "/tmp/x", "api_key": "sk-123" }),
+            timeout_ms: None,
+            trace_enabled: true,
+            session: None,
+        })
+        .await;
+    assert!(result.is_err());
+
+    let history = persistence
+        .query_execution_history(&skillsrs::core::persistence::HistoryFilter {
+            callable: Some("fs.read".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    let entry = &history[0];
+    assert_eq!(entry.callable_id, record.id.as_str());
+    assert!(entry.is_error);
+    assert_eq!(entry.arguments["path"], "/tmp/x");
+    assert_eq!(entry.arguments["api_key"], "[REDACTED]");
+    assert!(entry.result.as_ref().unwrap()["error"].is_string());
+    assert_eq!(
+        entry.trace.as_ref().unwrap()["execution_id"],
+        entry.execution_id.as_str()
+    );
+}
+
+/// A stdio MCP server whose only tool kills the process
+const CRASHING_SERVER: &str = r#"#!/bin/sh
+echo "booting" >&2
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -nE 's/.*"id":("[^"]*"|[0-9]+).*/\1/p')
+  case "$line" in
+    *'"method":"initialize"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"crashy","version":"1"}}}\n' "$id" ;;
+    *'"method":"tools/list"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":[{"name":"crash","inputSchema":{"type":"object"}}]}}\n' "$id" ;;
+    *'"method":"tools/call"'*)
+      echo "fatal: crash requested" >&2
+      exit 3 ;;
+  esac
+done
+"#;
+
+/// Poll the only upstream until its status matches
+async fn wait_for_status(
+    manager: &UpstreamManager,
+    matches: impl Fn(&skillsrs::execution::upstream::UpstreamStatus) -> bool,
+) -> skillsrs::execution::upstream::UpstreamStatus {
+    for _ in 0..250 {
+        let status = manager.statuses().await.remove(0);
+        if matches(&status) {
+            return status;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("upstream status never matched");
+}
+
+#[tokio::test]
+async fn test_stdio_upstream_restarts_after_crash() {
+    use skillsrs::core::registry::ServerHealth;
+    use skillsrs::execution::supervisor::RestartPolicy;
+    use skillsrs::execution::upstream::ConnectionState;
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("server.sh");
+    std::fs::write(&script, CRASHING_SERVER).unwrap();
+
+    let registry = Arc::new(Registry::new());
+    let manager = UpstreamManager::new(registry.clone());
+    manager
+        .add_upstream(UpstreamConfig {
+            alias: "crashy".to_string(),
+            transport: Transport::Stdio,
+            command: Some(vec!["sh".to_string(), script.display().to_string()]),
+            url: None,
+            auth: None,
+            repo: None,
+            git_ref: None,
+            skills: None,
+            roots: None,
+            tags: vec![],
+            sandbox_config: None,
+            description: None,
+            annotations: Default::default(),
+            restart: RestartPolicy {
+                initial_backoff_ms: 20,
+                max_restarts: 2,
+                ..Default::default()
+            },
+            max_concurrency: None,
+        })
+        .await
+        .unwrap();
+
+    // Two crashes are restarted
+    for restarts in 1..=2 {
+        let err = manager
+            .call_tool("crashy", "crash", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(!err.to_string().contains("timed out"), "{}", err);
+
+        let current = wait_for_status(&manager, |s| {
+            s.state == ConnectionState::Connected && s.restarts == restarts
+        })
+        .await;
+        assert!(current.last_exit.unwrap().contains("exit"));
+        assert!(current
+            .stderr
+            .contains(&"fatal: crash requested".to_string()));
+        assert_eq!(current.stderr.first().map(String::as_str), Some("booting"));
+        assert_eq!(registry.get_by_server("crashy").len(), 1);
+    }
+
+    // The third within the window is a crash loop
+    let _ = manager
+        .call_tool("crashy", "crash", serde_json::json!({}))
+        .await;
+    let current = wait_for_status(&manager, |s| s.state == ConnectionState::Failed).await;
+    assert_eq!(current.restarts, 2);
+    assert_eq!(
+        registry.get_server("crashy").unwrap().health,
+        ServerHealth::Down
+    );
+    let err = manager
+        .call_tool("crashy", "crash", serde_json::json!({}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("reconnect"), "{}", err);
+
+    // Reconnecting by hand starts over with a fresh crash budget
+    manager.reconnect("crashy").await.unwrap();
+    let _ = manager
+        .call_tool("crashy", "crash", serde_json::json!({}))
+        .await;
+    let current = wait_for_status(&manager, |s| s.restarts == 3).await;
+    assert_eq!(current.state, ConnectionState::Connected);
+}
+
+/// A stdio MCP server answering `echo` calls out of order and `slow` calls
+/// after a delay, logging to stderr when a call starts, ends, or is cancelled
+const CONCURRENT_SERVER: &str = r#"#!/bin/sh
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -nE 's/.*"id":("[^"]*"|[0-9]+).*/\1/p')
+  n=$(printf '%s' "$line" | sed -nE 's/.*"n":([0-9]+).*/\1/p')
+  case "$line" in
+    *'"method":"initialize"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"fanout","version":"1"}}}\n' "$id" ;;
+    *'"method":"tools/list"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":[{"name":"echo","inputSchema":{"type":"object"}},{"name":"slow","inputSchema":{"type":"object"}}]}}\n' "$id" ;;
+    *'"method":"notifications/cancelled"'*)
+      echo "cancelled $(printf '%s' "$line" | sed -nE 's/.*"requestId":([0-9]+).*/\1/p')" >&2 ;;
+    *'"name":"slow"'*)
+      ( echo "start $n" >&2; sleep 0.2; echo "end $n" >&2
+        printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"%s"}]}}\n' "$id" "$n" ) & ;;
+    *'"method":"tools/call"'*)
+      ( sleep "0.0$((n % 5))"
+        printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"%s"}]}}\n' "$id" "$n" ) & ;;
+  esac
+done
+"#;
+
+#[tokio::test]
+async fn test_stdio_requests_are_multiplexed() {
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("server.sh");
+    std::fs::write(&script, CONCURRENT_SERVER).unwrap();
+
+    let registry = Arc::new(Registry::new());
+    let manager = UpstreamManager::new(registry.clone());
+    let config = |alias: &str, max_concurrency| UpstreamConfig {
+        alias: alias.to_string(),
+        transport: Transport::Stdio,
+        command: Some(vec!["sh".to_string(), script.display().to_string()]),
+        url: None,
+        auth: None,
+        repo: None,
+        git_ref: None,
+        skills: None,
+        roots: None,
+        tags: vec![],
+        sandbox_config: None,
+        description: None,
+        annotations: Default::default(),
+        restart: Default::default(),
+        max_concurrency,
+    };
+    manager.add_upstream(config("fanout", None)).await.unwrap();
+
+    // Concurrent calls get their own responses, even when answered out of order
+    let calls = (0..12).map(|n| {
+        let manager = &manager;
+        async move {
+            let result = manager
+                .call_tool("fanout", "echo", serde_json::json!({ "n": n }))
+                .await
+                .unwrap();
+            assert_eq!(result["content"][0]["text"], n.to_string());
+        }
+    });
+    futures::future::join_all(calls).await;
+
+    // A timed-out call is cancelled upstream and its late response ignored
+    let err = manager
+        .call_tool_with_timeout(
+            "fanout",
+            "slow",
+            serde_json::json!({ "n": 1 }),
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("timed out"), "{}", err);
+    let status = wait_for_status(&manager, |s| s.stderr.len() == 3).await;
+    assert!(status.stderr.iter().any(|l| l.starts_with("cancelled ")));
+    let result = manager
+        .call_tool("fanout", "echo", serde_json::json!({ "n": 7 }))
+        .await
+        .unwrap();
+    assert_eq!(result["content"][0]["text"], "7");
+
+    // With one request in flight at a time, calls queue rather than overlap
+    manager.disconnect("fanout").await.unwrap();
+    manager
+        .add_upstream(config("serial", Some(1)))
+        .await
+        .unwrap();
+    let calls = (1..=2).map(|n| manager.call_tool("serial", "slow", serde_json::json!({ "n": n })));
+    for result in futures::future::join_all(calls).await {
+        result.unwrap();
+    }
+    let stderr = wait_for_status(&manager, |s| s.stderr.len() == 4)
+        .await
+        .stderr;
+    for pair in stderr.chunks(2) {
+        assert_eq!(pair[1], pair[0].replace("start", "end"), "{:?}", stderr);
+    }
+}
@@ -93,6 +93,7 @@ async fn test_search_tool_through_server() {
             additional_files: vec![],
             cost_hints: skillsrs::core::CostHints::default(),
             risk_tier: skillsrs::core::RiskTier::ReadOnly,
+            annotations: None,
             last_seen: chrono::Utc::now(),
             sandbox_config: None,
         })
@@ -105,3 +106,232 @@ async fn test_search_tool_through_server() {
     // The actual search test would require calling the MCP protocol
     // which is tested at the integration level
 }
+
+#[tokio::test]
+async fn test_consent_approval_is_not_served_on_mcp_app() {
+    use skillsrs::core::consent::{ConsentManager, ConsentStatus};
+    use skillsrs::core::persistence::PersistenceLayer;
+    use skillsrs::core::policy::ConsentLevel;
+    use skillsrs::mcp::http;
+
+    let registry = Arc::new(Registry::new());
+    let search_engine = Arc::new(SearchEngine::new(registry.clone()));
+    let policy_engine =
+        Arc::new(skillsrs::core::policy::PolicyEngine::new(PolicyConfig::default()).unwrap());
+    let upstream_manager = Arc::new(UpstreamManager::new(registry.clone()));
+    let runtime = Arc::new(Runtime::new(registry.clone(), upstream_manager));
+    let temp_dir = TempDir::new().unwrap();
+    let skill_store = Arc::new(
+        SkillStore::with_search_engine(temp_dir.path(), registry.clone(), search_engine.clone())
+            .unwrap(),
+    );
+    let persistence = Arc::new(
+        PersistenceLayer::new(temp_dir.path().join("skills.db"))
+            .await
+            .unwrap(),
+    );
+    let consent = Arc::new(ConsentManager::new(persistence).await.unwrap());
+    let server = SkillsServer::new(registry, search_engine, policy_engine, runtime, skill_store)
+        .with_consent(consent.clone());
+
+    let schema = serde_json::json!({"type": "object"});
+    let digest = skillsrs::core::SchemaDigest::from_schema(&schema).unwrap();
+    let record = skillsrs::core::CallableRecord {
+        id: skillsrs::core::CallableId::tool("fs", "delete_file", digest.as_str()),
+        kind: skillsrs::core::CallableKind::Tool,
+        fq_name: "fs.delete_file".to_string(),
+        name: "delete_file".to_string(),
+        title: None,
+        description: None,
+        tags: vec![],
+        input_schema: schema,
+        output_schema: None,
+        schema_digest: digest,
+        server_alias: Some("fs".to_string()),
+        upstream_tool_name: Some("delete_file".to_string()),
+        skill_version: None,
+        uses: vec![],
+        skill_directory: None,
+        bundled_tools: vec![],
+        additional_files: vec![],
+        cost_hints: skillsrs::core::CostHints::default(),
+        risk_tier: skillsrs::core::RiskTier::Destructive,
+        annotations: None,
+        last_seen: chrono::Utc::now(),
+        sandbox_config: None,
+    };
+    let request = consent
+        .request(
+            &record,
+            &serde_json::json!({}),
+            ConsentLevel::UserConfirmed,
+            "destructive",
+        )
+        .await
+        .unwrap();
+
+    let serve = |app: axum::Router| async move {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    };
+    let client = reqwest::Client::new();
+
+    // The agent's listener has no way to approve its own request
+    let mcp = serve(http::router(server)).await;
+    let response = client
+        .post(format!("{}/consent/{}/approve", mcp, request.id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    let response = client.get(format!("{}/consent", mcp)).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    assert_eq!(
+        consent.get(&request.id).await.unwrap().status,
+        ConsentStatus::Pending
+    );
+
+    // The separate consent listener approves it
+    let admin = serve(http::consent_router(consent.clone())).await;
+    let response = client
+        .post(format!("{}/consent/{}/approve", admin, request.id))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        consent.get(&request.id).await.unwrap().status,
+        ConsentStatus::Approved
+    );
+}
+
+#[tokio::test]
+async fn test_exec_rejected_before_running_spends_no_quota() {
+    use rmcp::ServiceExt;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let registry = Arc::new(Registry::new());
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": { "path": { "type": "string" } },
+        "required": ["path"]
+    });
+    let digest = skillsrs::core::SchemaDigest::from_schema(&schema).unwrap();
+    let record = skillsrs::core::CallableRecord {
+        id: skillsrs::core::CallableId::tool("fs", "read_file", digest.as_str()),
+        kind: skillsrs::core::CallableKind::Tool,
+        fq_name: "fs.read_file".to_string(),
+        name: "read_file".to_string(),
+        title: None,
+        description: None,
+        tags: vec![],
+        input_schema: schema,
+        output_schema: None,
+        schema_digest: digest,
+        server_alias: Some("fs".to_string()),
+        upstream_tool_name: Some("read_file".to_string()),
+        skill_version: None,
+        uses: vec![],
+        skill_directory: None,
+        bundled_tools: vec![],
+        additional_files: vec![],
+        cost_hints: skillsrs::core::CostHints::default(),
+        risk_tier: skillsrs::core::RiskTier::ReadOnly,
+        annotations: None,
+        last_seen: chrono::Utc::now(),
+        sandbox_config: None,
+    };
+    registry.register(record.clone()).unwrap();
+
+    let config: PolicyConfig = serde_yaml::from_str(
+        "rate_limits:\n  - scope: server\n    match: \"fs\"\n    quota: 1\n    window: 1h\n",
+    )
+    .unwrap();
+    let policy_engine = Arc::new(skillsrs::core::policy::PolicyEngine::new(config).unwrap());
+    let search_engine = Arc::new(SearchEngine::new(registry.clone()));
+    let upstream_manager = Arc::new(UpstreamManager::new(registry.clone()));
+    let runtime = Arc::new(Runtime::new(registry.clone(), upstream_manager));
+    let temp_dir = TempDir::new().unwrap();
+    let skill_store = Arc::new(
+        SkillStore::with_search_engine(temp_dir.path(), registry.clone(), search_engine.clone())
+            .unwrap(),
+    );
+    let server = SkillsServer::new(
+        registry,
+        search_engine,
+        policy_engine.clone(),
+        runtime,
+        skill_store,
+    );
+
+    let (client, transport) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        let running = server.serve(tokio::io::split(transport)).await.unwrap();
+        let _ = running.waiting().await;
+    });
+    let (read, mut write) = tokio::io::split(client);
+    let mut lines = BufReader::new(read).lines();
+    let send = |message: serde_json::Value| {
+        let mut line = message.to_string();
+        line.push('\n');
+        line
+    };
+
+    write
+        .write_all(
+            send(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": { "name": "test", "version": "0" }
+                }
+            }))
+            .as_bytes(),
+        )
+        .await
+        .unwrap();
+    lines.next_line().await.unwrap().unwrap();
+    write
+        .write_all(
+            send(serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+                .as_bytes(),
+        )
+        .await
+        .unwrap();
+
+    // Invalid arguments are refused without touching the quota
+    for (id, dry_run) in [(1, false), (2, true), (3, false)] {
+        write
+            .write_all(
+                send(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "method": "tools/call",
+                    "params": {
+                        "name": "exec",
+                        "arguments": {
+                            "id": record.id.as_str(),
+                            "arguments": { "path": 7 },
+                            "dry_run": dry_run
+                        }
+                    }
+                }))
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let response = lines.next_line().await.unwrap().unwrap();
+        assert!(response.contains("failed"), "{}", response);
+        assert!(!response.contains("denied"), "{}", response);
+    }
+
+    policy_engine
+        .check_rate_limits(&record, skillsrs::core::limits::DEFAULT_SESSION)
+        .await
+        .unwrap();
+}
@@ -2,7 +2,9 @@
 
 use skillsrs::core::registry::Registry;
 use skillsrs::core::CallableKind;
-use skillsrs::storage::search::{SearchEngine, SearchQuery};
+use skillsrs::storage::search::{
+    SearchEngine, SearchFilters, SearchInclude, SearchMode, SearchQuery,
+};
 use skillsrs::storage::{CreateSkillRequest, SkillStore};
 use std::sync::Arc;
 use tempfile::TempDir;
@@ -248,6 +250,7 @@ async fn test_search_engine() {
         additional_files: vec![],
         cost_hints: skillsrs::core::CostHints::default(),
         risk_tier: skillsrs::core::RiskTier::ReadOnly,
+        annotations: None,
         last_seen: chrono::Utc::now(),
         sandbox_config: None,
     };
@@ -262,6 +265,9 @@ async fn test_search_engine() {
         limit: 10,
         filters: None,
         cursor: None,
+        mode: SearchMode::Literal,
+        session: None,
+        include: SearchInclude::default(),
     };
 
     let results = search_engine.search(&query).await.unwrap();
@@ -302,6 +308,7 @@ async fn test_search_with_filters() {
             additional_files: vec![],
             cost_hints: skillsrs::core::CostHints::default(),
             risk_tier: skillsrs::core::RiskTier::ReadOnly,
+            annotations: None,
             last_seen: chrono::Utc::now(),
             sandbox_config: None,
         })
@@ -328,6 +335,7 @@ async fn test_search_with_filters() {
             additional_files: vec![],
             cost_hints: skillsrs::core::CostHints::default(),
             risk_tier: skillsrs::core::RiskTier::ReadOnly,
+            annotations: None,
             last_seen: chrono::Utc::now(),
             sandbox_config: None,
         })
@@ -342,6 +350,9 @@ async fn test_search_with_filters() {
         limit: 10,
         filters: None,
         cursor: None,
+        mode: SearchMode::Literal,
+        session: None,
+        include: SearchInclude::default(),
     };
 
     let results = search_engine.search(&query).await.unwrap();
@@ -355,6 +366,9 @@ async fn test_search_with_filters() {
         limit: 10,
         filters: None,
         cursor: None,
+        mode: SearchMode::Literal,
+        session: None,
+        include: SearchInclude::default(),
     };
 
     let results = search_engine.search(&query).await.unwrap();
@@ -402,6 +416,9 @@ This skill tests that loaded skills are searchable.
         limit: 10,
         filters: None,
         cursor: None,
+        mode: SearchMode::Literal,
+        session: None,
+        include: SearchInclude::default(),
     };
 
     let results = search_engine.search(&query).await.unwrap();
@@ -419,6 +436,9 @@ This skill tests that loaded skills are searchable.
         limit: 10,
         filters: None,
         cursor: None,
+        mode: SearchMode::Literal,
+        session: None,
+        include: SearchInclude::default(),
     };
 
     let results = search_engine.search(&query).await.unwrap();
@@ -476,3 +496,887 @@ async fn test_create_skill_writes_yaml_escaped_frontmatter() {
         "Handles: colon values\nand newlines safely"
     );
 }
+
+#[tokio::test]
+async fn test_search_marks_cached_tools_stale() {
+    let registry = Arc::new(Registry::new());
+    let schema = serde_json::json!({"type": "object"});
+    let digest = skillsrs::core::SchemaDigest::from_schema(&schema).unwrap();
+    let record = |server: &str| skillsrs::core::CallableRecord {
+        id: skillsrs::core::CallableId::tool(server, "read_file", digest.as_str()),
+        kind: CallableKind::Tool,
+        fq_name: format!("{}.read_file", server),
+        name: "read_file".to_string(),
+        title: None,
+        description: Some("Read a file".to_string()),
+        tags: vec![],
+        input_schema: schema.clone(),
+        output_schema: None,
+        schema_digest: digest.clone(),
+        server_alias: Some(server.to_string()),
+        upstream_tool_name: Some("read_file".to_string()),
+        skill_version: None,
+        uses: vec![],
+        skill_directory: None,
+        bundled_tools: vec![],
+        additional_files: vec![],
+        cost_hints: skillsrs::core::CostHints::default(),
+        risk_tier: skillsrs::core::RiskTier::ReadOnly,
+        annotations: None,
+        last_seen: chrono::Utc::now(),
+        sandbox_config: None,
+    };
+
+    registry.hydrate(vec![record("cached")]);
+    registry.register(record("live")).unwrap();
+
+    let engine = SearchEngine::new(registry);
+    let results = engine
+        .search(&SearchQuery {
+            q: "read_file".to_string(),
+            kind: "tools".to_string(),
+            limit: 10,
+            filters: None,
+            cursor: None,
+            mode: SearchMode::Literal,
+            session: None,
+            include: SearchInclude::default(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(results.matches.len(), 2);
+    for m in &results.matches {
+        assert_eq!(
+            m.stale,
+            m.server.as_deref() == Some("cached"),
+            "{}",
+            m.fq_name
+        );
+    }
+}
+
+fn search_record(
+    server: &str,
+    name: &str,
+    description: &str,
+    params: &[&str],
+) -> skillsrs::core::CallableRecord {
+    let properties: serde_json::Map<String, serde_json::Value> = params
+        .iter()
+        .map(|p| (p.to_string(), serde_json::json!({"type": "string"})))
+        .collect();
+    let schema = serde_json::json!({"type": "object", "properties": properties});
+    let digest = skillsrs::core::SchemaDigest::from_schema(&schema).unwrap();
+    skillsrs::core::CallableRecord {
+        id: skillsrs::core::CallableId::tool(server, name, digest.as_str()),
+        kind: CallableKind::Tool,
+        fq_name: format!("{}.{}", server, name),
+        name: name.to_string(),
+        title: None,
+        description: Some(description.to_string()),
+        tags: vec![],
+        input_schema: schema,
+        output_schema: None,
+        schema_digest: digest,
+        server_alias: Some(server.to_string()),
+        upstream_tool_name: Some(name.to_string()),
+        skill_version: None,
+        uses: vec![],
+        skill_directory: None,
+        bundled_tools: vec![],
+        additional_files: vec![],
+        cost_hints: skillsrs::core::CostHints::default(),
+        risk_tier: skillsrs::core::RiskTier::ReadOnly,
+        annotations: None,
+        last_seen: chrono::Utc::now(),
+        sandbox_config: None,
+    }
+}
+
+fn text_query(q: &str) -> SearchQuery {
+    SearchQuery {
+        q: q.to_string(),
+        kind: "any".to_string(),
+        limit: 10,
+        filters: None,
+        cursor: None,
+        mode: SearchMode::Literal,
+        session: None,
+        include: SearchInclude::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_bm25_search_ranks_multi_term_queries() {
+    let registry = Arc::new(Registry::new());
+    for record in [
+        search_record(
+            "github",
+            "get_issue",
+            "Get details of an issue",
+            &["owner", "repo", "issue_number"],
+        ),
+        search_record(
+            "github",
+            "list_issues",
+            "List issues in a repository",
+            &["owner", "repo"],
+        ),
+        search_record(
+            "filesystem",
+            "readFile",
+            "Read the complete contents of a file",
+            &["path"],
+        ),
+        search_record(
+            "slack",
+            "post_message",
+            "Post a message to a channel",
+            &["channel", "text"],
+        ),
+    ] {
+        registry.register(record).unwrap();
+    }
+    let engine = SearchEngine::new(registry);
+
+    // No single field contains the whole query, but every term matches somewhere
+    let results = engine
+        .search(&text_query("read github issue"))
+        .await
+        .unwrap();
+    assert_eq!(results.matches[0].fq_name, "github.get_issue");
+    assert!(results.total_matches >= 3);
+    assert!(results.matches[0].score > results.matches[2].score);
+
+    // camelCase names split into words; plurals match singulars
+    let results = engine.search(&text_query("read file")).await.unwrap();
+    assert_eq!(results.matches[0].name, "readFile");
+    let results = engine.search(&text_query("issues")).await.unwrap();
+    assert_eq!(results.total_matches, 2);
+
+    // Partial terms match as prefixes and are explained
+    let results = engine.search(&text_query("chan")).await.unwrap();
+    assert_eq!(results.matches[0].fq_name, "slack.post_message");
+    let explanation = results.matches[0].explanation.as_deref().unwrap();
+    assert!(
+        explanation.contains("chan→channel (prefix)"),
+        "{}",
+        explanation
+    );
+    assert!(explanation.contains("params"), "{}", explanation);
+}
+
+#[tokio::test]
+async fn test_search_index_follows_registry_changes() {
+    let registry = Arc::new(Registry::new());
+    let engine = SearchEngine::new(registry.clone());
+
+    let record = search_record("notes", "create_note", "Create a note", &["title"]);
+    registry.register(record.clone()).unwrap();
+    let results = engine.search(&text_query("note")).await.unwrap();
+    assert_eq!(results.total_matches, 1);
+
+    // Re-registering with a new description reindexes without a rebuild
+    let mut updated = record.clone();
+    updated.description = Some("Create a markdown memo".to_string());
+    registry.register(updated).unwrap();
+    let results = engine.search(&text_query("memo")).await.unwrap();
+    assert_eq!(results.total_matches, 1);
+
+    registry.remove(&record.id).unwrap();
+    let results = engine.search(&text_query("note")).await.unwrap();
+    assert_eq!(results.total_matches, 0);
+}
+
+#[tokio::test]
+async fn test_fts_index_tracks_callables_table() {
+    let temp_dir = TempDir::new().unwrap();
+    let persistence = Arc::new(
+        skillsrs::core::persistence::PersistenceLayer::new(temp_dir.path().join("skills.db"))
+            .await
+            .unwrap(),
+    );
+
+    let issue = search_record(
+        "github",
+        "get_issue",
+        "Get details of an issue",
+        &["owner", "issue_number"],
+    );
+    let message = search_record(
+        "slack",
+        "post_message",
+        "Post a message to a channel",
+        &["channel", "text"],
+    );
+    persistence.save_callable(&issue).await.unwrap();
+    persistence.save_callable(&message).await.unwrap();
+
+    // Prefix queries match parameter names through the FTS5 prefix index
+    let hits = persistence
+        .search_callables("chan", None, 10)
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].0, message.id.as_str());
+
+    // Upserts and deletes keep the FTS table in step
+    let mut updated = issue.clone();
+    updated.description = Some("Fetch a ticket".to_string());
+    persistence.save_callable(&updated).await.unwrap();
+    assert!(persistence
+        .search_callables("details", None, 10)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        persistence
+            .search_callables("ticket", Some("Tool"), 10)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    persistence.delete_callable(&message.id).await.unwrap();
+    assert!(persistence
+        .search_callables("channel", None, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // At or above the threshold, SearchEngine answers from FTS5
+    let registry = Arc::new(Registry::new());
+    registry.register(updated).unwrap();
+    let engine = SearchEngine::new(registry).with_fts(persistence, 1);
+    let results = engine.search(&text_query("tick")).await.unwrap();
+    assert_eq!(results.total_matches, 1);
+    assert_eq!(results.matches[0].fq_name, "github.get_issue");
+    let explanation = results.matches[0].explanation.as_deref().unwrap();
+    assert!(
+        explanation.contains("tick→ticket (prefix)"),
+        "{}",
+        explanation
+    );
+}
+
+#[tokio::test]
+async fn test_fts_filters_reach_past_the_first_candidate_batch() {
+    let temp_dir = TempDir::new().unwrap();
+    let persistence = Arc::new(
+        skillsrs::core::persistence::PersistenceLayer::new(temp_dir.path().join("skills.db"))
+            .await
+            .unwrap(),
+    );
+    let registry = Arc::new(Registry::new());
+
+    // 600 strong matches outrank every callable of the filtered server
+    let mut records: Vec<_> = (0..600)
+        .map(|i| search_record("bulk", &format!("list_files_{}", i), "List files", &[]))
+        .collect();
+    records.extend((0..5).map(|i| {
+        search_record(
+            "late",
+            &format!("archive_{}", i),
+            "Archive storage that can also list the files it holds",
+            &[],
+        )
+    }));
+    for record in records {
+        persistence.save_callable(&record).await.unwrap();
+        registry.register(record).unwrap();
+    }
+    let engine = SearchEngine::new(registry).with_fts(persistence.clone(), 1);
+
+    let results = engine
+        .search(&text_query("list files server:late"))
+        .await
+        .unwrap();
+    assert_eq!(results.total_matches, 5);
+
+    // Cursor pages keep going until the filtered matches run out
+    let page = |cursor: Option<String>| SearchQuery {
+        limit: 2,
+        cursor,
+        filters: Some(SearchFilters {
+            server: Some("late".to_string()),
+            ..Default::default()
+        }),
+        ..text_query("list files")
+    };
+    let mut names = Vec::new();
+    let mut cursor = None;
+    while names.len() <= 5 {
+        let results = engine.search(&page(cursor)).await.unwrap();
+        names.extend(results.matches.into_iter().map(|m| m.name));
+        cursor = match results.next_cursor {
+            Some(cursor) => Some(cursor),
+            None => break,
+        };
+    }
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "archive_0",
+            "archive_1",
+            "archive_2",
+            "archive_3",
+            "archive_4"
+        ]
+    );
+
+    // Tools of servers dropped from the config leave the FTS index at startup
+    persistence
+        .warm_start(&Registry::new(), &["late".to_string()])
+        .await
+        .unwrap();
+    let hits = persistence
+        .search_callables("list files", None, 1000)
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 5);
+}
+
+#[tokio::test]
+async fn test_fuzzy_and_regex_search_modes() {
+    let registry = Arc::new(Registry::new());
+    for record in [
+        search_record("github", "get_issue", "Get an issue", &["issue_number"]),
+        search_record("github", "create_pull_request", "Open a PR", &["head"]),
+        search_record("filesystem", "read_file", "Read a file", &["path"]),
+    ] {
+        registry.register(record).unwrap();
+    }
+    let engine = SearchEngine::new(registry);
+    let query = |q: &str, mode: SearchMode| SearchQuery {
+        q: q.to_string(),
+        mode,
+        ..Default::default()
+    };
+
+    // Misspelled names resolve in fuzzy mode but not literally
+    let results = engine
+        .search(&query("gthub_isue", SearchMode::Fuzzy))
+        .await
+        .unwrap();
+    assert_eq!(results.matches[0].fq_name, "github.get_issue");
+    let explanation = results.matches[0].explanation.as_deref().unwrap();
+    assert!(
+        explanation.contains("isue→issue (1 edit)"),
+        "{}",
+        explanation
+    );
+    let results = engine
+        .search(&query("gthub_isue", SearchMode::Literal))
+        .await
+        .unwrap();
+    assert_eq!(results.total_matches, 0);
+
+    // Abbreviations match as subsequences
+    let results = engine
+        .search(&query("crtpullreq", SearchMode::Fuzzy))
+        .await
+        .unwrap();
+    assert_eq!(results.matches[0].name, "create_pull_request");
+
+    // Regex mode matches any field, case-insensitively
+    let results = engine
+        .search(&query("^(READ|get)_", SearchMode::Regex))
+        .await
+        .unwrap();
+    assert_eq!(results.total_matches, 2);
+    let results = engine
+        .search(&query("issue_num", SearchMode::Regex))
+        .await
+        .unwrap();
+    assert_eq!(results.matches[0].name, "get_issue");
+    assert!(results.matches[0]
+        .explanation
+        .as_deref()
+        .unwrap()
+        .contains("params"));
+
+    let err = engine
+        .search(&query("(unclosed", SearchMode::Regex))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        skillsrs::storage::search::IndexError::RegexError(_)
+    ));
+    assert!("FUZZY".parse::<SearchMode>().unwrap() == SearchMode::Fuzzy);
+    assert!("glob".parse::<SearchMode>().is_err());
+}
+
+#[tokio::test]
+async fn test_inline_query_filters() {
+    use skillsrs::core::RiskTier;
+    use skillsrs::storage::query::parse_query;
+
+    let mut create_pr = search_record("github", "create_pr", "Create a pull request", &["repo"]);
+    create_pr.risk_tier = RiskTier::Writes;
+    create_pr.tags = vec!["pr".to_string()];
+    let mut old_pr = search_record("github", "open_pr", "Open a pull request", &["repo"]);
+    old_pr.risk_tier = RiskTier::Writes;
+    old_pr.tags = vec!["pr".to_string(), "deprecated".to_string()];
+    let mut delete_repo = search_record("github", "delete_repo", "Delete a repository", &["repo"]);
+    delete_repo.risk_tier = RiskTier::Destructive;
+    delete_repo.tags = vec!["pr".to_string()];
+    let mut fetch = search_record("web", "fetch_pr", "Fetch a pull request page", &["url"]);
+    fetch.risk_tier = RiskTier::ReadOnly;
+    fetch.tags = vec!["pr".to_string()];
+    fetch.cost_hints.network_required = true;
+
+    let registry = Arc::new(Registry::new());
+    for record in [create_pr, old_pr, delete_repo, fetch] {
+        registry.register(record).unwrap();
+    }
+    let engine = SearchEngine::new(registry);
+
+    let results = engine
+        .search(&text_query(
+            "server:github kind:tool risk:<=writes tag:pr -tag:deprecated param:repo net:false",
+        ))
+        .await
+        .unwrap();
+    let names: Vec<&str> = results.matches.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["create_pr"]);
+    assert!(results.matches[0]
+        .explanation
+        .as_deref()
+        .unwrap()
+        .starts_with("filters: server:github, kind:tool, risk:<=writes"));
+
+    // Text and filters combine; filters apply in every mode
+    let results = engine
+        .search(&text_query("pull request net:true"))
+        .await
+        .unwrap();
+    assert_eq!(results.total_matches, 1);
+    assert_eq!(results.matches[0].name, "fetch_pr");
+    let results = engine
+        .search(&SearchQuery {
+            q: "crate_pr -risk:read_only".to_string(),
+            mode: SearchMode::Fuzzy,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(results.matches[0].name, "create_pr");
+    assert!(results.matches.iter().all(|m| m.name != "fetch_pr"));
+
+    // Unknown keys stay text; bad values are rejected
+    let parsed = parse_query("see http://example.com version:^1.2").unwrap();
+    assert_eq!(parsed.text, "see http://example.com");
+    assert_eq!(parsed.describe_filters(), "version:^1.2");
+    assert!(parse_query("risk:<=dangerous").is_err());
+    assert!(parse_query("net:maybe").is_err());
+    assert!(engine.search(&text_query("kind:widget")).await.is_err());
+}
+
+#[tokio::test]
+async fn test_semantic_search_fuses_with_lexical_ranking() {
+    use skillsrs::core::persistence::PersistenceLayer;
+    use skillsrs::storage::semantic::EmbeddingModel;
+
+    // Three dimensions: communication, files, messaging detail
+    let temp_dir = TempDir::new().unwrap();
+    let model_path = temp_dir.path().join("vectors.txt");
+    std::fs::write(
+        &model_path,
+        "9 3\n\
+         send 1.0 0.0 0.1\n\
+         post 0.9 0.1 0.0\n\
+         message 1.0 0.0 0.3\n\
+         text 0.8 0.1 0.2\n\
+         team 0.9 0.0 0.4\n\
+         channel 0.9 0.0 0.3\n\
+         read 0.0 1.0 0.0\n\
+         file 0.1 1.0 0.1\n\
+         contents 0.0 0.9 0.0\n",
+    )
+    .unwrap();
+    let model = Arc::new(EmbeddingModel::load(&model_path).unwrap());
+    assert_eq!(model.dim(), 3);
+    assert_eq!(model.vocabulary_size(), 9);
+
+    let persistence = Arc::new(
+        PersistenceLayer::new(temp_dir.path().join("skills.db"))
+            .await
+            .unwrap(),
+    );
+    let registry = Arc::new(Registry::new());
+    let post = search_record("slack", "post", "Post text to a channel", &["channel"]);
+    let read = search_record("filesystem", "read_file", "Read file contents", &["path"]);
+    registry.register(post.clone()).unwrap();
+    registry.register(read).unwrap();
+
+    // No keyword overlap, but the intent matches
+    let lexical = SearchEngine::new(registry.clone());
+    let query = text_query("send a message to my team");
+    assert_eq!(lexical.search(&query).await.unwrap().total_matches, 0);
+
+    let engine =
+        SearchEngine::new(registry.clone()).with_semantic(model.clone(), Some(persistence.clone()));
+    let results = engine.search(&query).await.unwrap();
+    assert_eq!(results.total_matches, 1);
+    assert_eq!(results.matches[0].fq_name, "slack.post");
+    assert!(results.matches[0]
+        .explanation
+        .as_deref()
+        .unwrap()
+        .starts_with("semantic: "));
+
+    // Lexical and semantic hits on the same callable both contribute
+    let results = engine.search(&text_query("post message")).await.unwrap();
+    assert_eq!(results.matches[0].fq_name, "slack.post");
+    assert!(results.matches[0].score > 1.0);
+    let explanation = results.matches[0].explanation.as_deref().unwrap();
+    assert!(explanation.contains("post: name"), "{}", explanation);
+    assert!(explanation.contains("; semantic: "), "{}", explanation);
+
+    // Vectors are cached per model, keyed to the callable's digests
+    let cached = persistence.load_embeddings(model.id()).await.unwrap();
+    assert_eq!(cached.len(), 2);
+    let cached_post = cached
+        .iter()
+        .find(|c| c.callable_id == post.id.as_str())
+        .unwrap();
+    assert_eq!(cached_post.schema_digest, post.schema_digest.as_str());
+    assert_eq!(cached_post.vector.len(), 3);
+}
+
+#[tokio::test]
+async fn test_search_points_at_skill_sections() {
+    let temp = TempDir::new().unwrap();
+    let registry = Arc::new(Registry::new());
+    let engine = SearchEngine::new(registry.clone());
+    let store = SkillStore::new(temp.path(), registry.clone()).unwrap();
+
+    store
+        .create_skill(CreateSkillRequest {
+            name: "pdf-tools".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Work with PDF documents".to_string(),
+            skill_md_content: "# PDF Tools\n\nHelpers for PDF files.\n\n\
+                ## Extracting tables\n\nUse pdfplumber to pull tabular data out of a page.\n\n\
+                ```markdown\n## Not a heading\n```\n\n\
+                ### Merged cells\n\nSpan detection is heuristic.\n\n\
+                ## Filling forms\n\nWrite values into AcroForm fields.\n"
+                .to_string(),
+            uses_tools: vec![],
+            tags: vec![],
+            scripts: vec![],
+            references: vec![(
+                "ocr.md".to_string(),
+                "# OCR\n\nScanned pages need tesseract before any text is available.\n".to_string(),
+            )],
+            assets: vec![],
+        })
+        .await
+        .unwrap();
+
+    // Body text that appears nowhere in the skill's metadata
+    let results = engine
+        .search(&text_query("pdfplumber tables"))
+        .await
+        .unwrap();
+    assert_eq!(results.total_matches, 1);
+    let section = results.matches[0].section.as_ref().unwrap();
+    assert_eq!(section.file, "SKILL.md");
+    assert_eq!(section.heading, "Extracting tables");
+    assert!(
+        section.snippet.starts_with("Use pdfplumber"),
+        "{:?}",
+        section
+    );
+    let explanation = results.matches[0].explanation.as_deref().unwrap();
+    assert!(
+        explanation.contains("§ Extracting tables (SKILL.md)"),
+        "{}",
+        explanation
+    );
+
+    let results = engine.search(&text_query("tesseract")).await.unwrap();
+    let section = results.matches[0].section.as_ref().unwrap();
+    assert_eq!(
+        (section.file.as_str(), section.heading.as_str()),
+        ("references/ocr.md", "OCR")
+    );
+
+    // Sections load with their subsections, but not past the next sibling
+    let text = store
+        .load_skill_section("pdf-tools", None, "extracting TABLES")
+        .unwrap();
+    assert!(text.starts_with("## Extracting tables"));
+    assert!(text.contains("## Not a heading"));
+    assert!(text.contains("### Merged cells"));
+    assert!(!text.contains("Filling forms"));
+    let text = store
+        .load_skill_section("pdf-tools", Some("references/ocr.md"), "OCR")
+        .unwrap();
+    assert!(text.contains("tesseract"));
+    let err = store
+        .load_skill_section("pdf-tools", None, "Rotating pages")
+        .unwrap_err();
+    assert!(err.to_string().contains("Filling forms"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_feedback_boosts_clicked_callables_and_demotes_failures() {
+    use skillsrs::core::persistence::PersistenceLayer;
+
+    let temp_dir = TempDir::new().unwrap();
+    let persistence = Arc::new(
+        PersistenceLayer::new(temp_dir.path().join("skills.db"))
+            .await
+            .unwrap(),
+    );
+    let registry = Arc::new(Registry::new());
+    let disk = search_record("disk", "read_file", "Read a file", &["path"]);
+    let fs = search_record("fs", "read_file", "Read a file", &["path"]);
+    registry.register(disk.clone()).unwrap();
+    registry.register(fs.clone()).unwrap();
+    let engine = SearchEngine::new(registry.clone()).with_feedback(persistence.clone());
+    let search = |session: &str| SearchQuery {
+        q: "read file".to_string(),
+        session: Some(session.to_string()),
+        ..Default::default()
+    };
+
+    // Equal lexical scores tie-break by name
+    let results = engine.search(&search("agent-1")).await.unwrap();
+    assert_eq!(results.matches[0].fq_name, "disk.read_file");
+
+    // Executing a result counts toward the query's terms
+    engine.record_outcome("agent-1", &fs.id, true).await;
+    let results = engine.search(&search("agent-1")).await.unwrap();
+    assert_eq!(results.matches[0].fq_name, "fs.read_file");
+    let explanation = results.matches[0].explanation.as_deref().unwrap();
+    assert!(
+        explanation.contains("similar queries 1 ok, 0 failed"),
+        "{}",
+        explanation
+    );
+    assert!(explanation.contains("this session"), "{}", explanation);
+
+    // Failures outside any search only count toward totals, and demote everywhere
+    for _ in 0..3 {
+        engine.record_outcome("agent-2", &disk.id, false).await;
+    }
+    let results = engine.search(&search("agent-3")).await.unwrap();
+    assert_eq!(results.matches[0].fq_name, "fs.read_file");
+    let demoted = &results.matches[1];
+    assert!(demoted.score < results.matches[0].score / 1.5);
+    assert!(demoted
+        .explanation
+        .as_deref()
+        .unwrap()
+        .contains("overall 0 ok, 3 failed"));
+
+    let rows = persistence
+        .load_search_feedback(&["read".to_string(), "file".to_string()])
+        .await
+        .unwrap();
+    let term_rows: Vec<_> = rows.iter().filter(|r| !r.term.is_empty()).collect();
+    assert_eq!(term_rows.len(), 2);
+    assert!(term_rows.iter().all(|r| r.callable_id == fs.id.as_str()));
+
+    // Without opting in, ranking ignores recorded feedback
+    let plain = SearchEngine::new(registry);
+    let results = plain.search(&search("agent-1")).await.unwrap();
+    assert_eq!(results.matches[0].fq_name, "disk.read_file");
+}
+
+#[tokio::test]
+async fn test_search_includes_short_signature_on_request() {
+    let registry = Arc::new(Registry::new());
+    let mut record = search_record("github", "add_labels", "Label an issue", &[]);
+    record.input_schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "repo": {"type": "string"},
+            "issue": {"type": "integer"},
+            "labels": {"type": "array", "items": {"type": "string"}}
+        },
+        "required": ["repo", "issue"]
+    });
+    record.output_schema = Some(serde_json::json!({"title": "Issue", "type": "object"}));
+    registry.register(record).unwrap();
+    let engine = SearchEngine::new(registry);
+
+    // Signatures are opt-in; digests are included by default
+    let results = engine.search(&text_query("label issue")).await.unwrap();
+    assert_eq!(results.matches[0].signature_short, None);
+    assert!(results.matches[0].schema_digest.is_some());
+
+    let query = SearchQuery {
+        include: SearchInclude {
+            signature: true,
+            schema_digest: false,
+            uses: false,
+        },
+        ..text_query("label issue")
+    };
+    let results = engine.search(&query).await.unwrap();
+    assert_eq!(
+        results.matches[0].signature_short.as_deref(),
+        Some("(repo: string, issue: int, labels?: string[]) -> Issue")
+    );
+    assert_eq!(results.matches[0].schema_digest, None);
+
+    assert_eq!(
+        skillsrs::core::CallableSignature::short(
+            &serde_json::json!({
+                "properties": {
+                    "mode": {"enum": ["a", "b"]},
+                    "ids": {"type": "array", "items": {"type": ["string", "integer"]}},
+                    "opts": {"type": "object", "properties": {"deep": {"type": "boolean"}}}
+                }
+            }),
+            None
+        ),
+        r#"(ids?: (string | int)[], mode?: "a" | "b", opts?: {deep?: bool})"#
+    );
+}
+
+#[tokio::test]
+async fn test_search_cursors_survive_catalog_changes() {
+    let registry = Arc::new(Registry::new());
+    for name in ["a", "b", "c", "d"] {
+        registry
+            .register(search_record(name, "list_files", "List files", &["path"]))
+            .unwrap();
+    }
+    let engine = SearchEngine::new(registry.clone());
+    let page = |cursor: Option<String>| SearchQuery {
+        limit: 2,
+        cursor,
+        ..text_query("list files")
+    };
+
+    let first = engine.search(&page(None)).await.unwrap();
+    let names: Vec<_> = first.matches.iter().map(|m| m.fq_name.as_str()).collect();
+    assert_eq!(names, ["a.list_files", "b.list_files"]);
+    let cursor = first.next_cursor.unwrap();
+    assert!(cursor.starts_with("sc1."));
+
+    // A callable ranked before the cursor neither shifts nor repeats the next page
+    let generation = registry.generation();
+    registry
+        .register(search_record("aa", "list_files", "List files", &["path"]))
+        .unwrap();
+    assert!(registry.generation() > generation);
+    let second = engine.search(&page(Some(cursor.clone()))).await.unwrap();
+    let names: Vec<_> = second.matches.iter().map(|m| m.fq_name.as_str()).collect();
+    assert_eq!(names, ["c.list_files", "d.list_files"]);
+    assert_eq!(second.next_cursor, None);
+
+    // Cursors are bound to their query and cannot be forged
+    let other = SearchQuery {
+        cursor: Some(cursor.clone()),
+        ..text_query("list")
+    };
+    assert!(engine.search(&other).await.is_err());
+    let flipped = if cursor.ends_with('0') { '1' } else { '0' };
+    let forged = format!("{}{}", &cursor[..cursor.len() - 1], flipped);
+    assert!(engine.search(&page(Some(forged))).await.is_err());
+    assert!(engine.search(&page(Some("2".to_string()))).await.is_err());
+
+    // Removing the callable the page ended on expires the cursor
+    let b = registry.get_by_fq_name("b.list_files").unwrap();
+    registry.remove(&b.id);
+    let err = engine.search(&page(Some(cursor))).await.unwrap_err();
+    assert!(matches!(
+        err,
+        skillsrs::storage::search::IndexError::CursorExpired(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_search_cursors_page_deterministically_with_feedback() {
+    use skillsrs::core::persistence::PersistenceLayer;
+
+    let temp_dir = TempDir::new().unwrap();
+    let persistence = Arc::new(
+        PersistenceLayer::new(temp_dir.path().join("skills.db"))
+            .await
+            .unwrap(),
+    );
+    let registry = Arc::new(Registry::new());
+    for name in ["a", "b", "c", "d", "e"] {
+        registry
+            .register(search_record(name, "list_files", "List files", &["path"]))
+            .unwrap();
+    }
+    let engine = SearchEngine::new(registry.clone()).with_feedback(persistence);
+    let page = |cursor: Option<String>| SearchQuery {
+        limit: 1,
+        cursor,
+        session: Some("agent".to_string()),
+        ..text_query("list files")
+    };
+
+    // Session use boosts these, and the boost decays between pages
+    for name in ["c", "e"] {
+        let record = registry
+            .get_by_fq_name(&format!("{}.list_files", name))
+            .unwrap();
+        engine.record_outcome("agent", &record.id, true).await;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    let mut names = Vec::new();
+    let mut cursor = None;
+    while names.len() <= 5 {
+        let results = engine.search(&page(cursor)).await.unwrap();
+        names.extend(results.matches.into_iter().map(|m| m.fq_name));
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        cursor = match results.next_cursor {
+            Some(cursor) => Some(cursor),
+            None => break,
+        };
+    }
+    assert_eq!(
+        names,
+        [
+            "e.list_files",
+            "c.list_files",
+            "a.list_files",
+            "b.list_files",
+            "d.list_files"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_search_cursor_after_a_shortened_page() {
+    let registry = Arc::new(Registry::new());
+    for name in ["a", "b", "c", "d", "e"] {
+        registry
+            .register(search_record(name, "list_files", "List files", &["path"]))
+            .unwrap();
+    }
+    let engine = SearchEngine::new(registry);
+    let query = SearchQuery {
+        limit: 3,
+        ..text_query("list files")
+    };
+
+    // A page cut to two matches continues with the third, not the fourth
+    let first = engine.search(&query).await.unwrap();
+    let cursor = engine.cursor_after(&query, &first, 2).unwrap();
+    let next = SearchQuery {
+        cursor: Some(cursor),
+        ..query.clone()
+    };
+    let second = engine.search(&next).await.unwrap();
+    let names: Vec<_> = second.matches.iter().map(|m| m.fq_name.as_str()).collect();
+    assert_eq!(names, ["c.list_files", "d.list_files", "e.list_files"]);
+
+    // Keeping the whole page keeps its cursor; keeping none resumes where it began
+    assert_eq!(engine.cursor_after(&query, &first, 3), first.next_cursor);
+    assert_eq!(engine.cursor_after(&next, &second, 0), next.cursor);
+    assert_eq!(engine.cursor_after(&query, &first, 0), None);
+}
@@ -56,6 +56,7 @@ fn create_test_tool_record(name: &str, server: &str) -> CallableRecord {
         additional_files: vec![],
         cost_hints: CostHints::default(),
         risk_tier: RiskTier::ReadOnly,
+        annotations: None,
         last_seen: chrono::Utc::now(),
         sandbox_config: None,
     }
@@ -99,6 +100,7 @@ fn create_test_skill_with_bundled_tool(name: &str, tool_name: &str) -> CallableR
         additional_files: vec![],
         cost_hints: CostHints::default(),
         risk_tier: RiskTier::ReadOnly,
+        annotations: None,
         last_seen: chrono::Utc::now(),
         sandbox_config: None,
     }